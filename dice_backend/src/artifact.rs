@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+
+use super::diagnostic::{Code, Diagnostic};
+use super::namespace::Bindings;
+use super::ordering::OrderedCollection;
+use super::program::{Diagnostics, Program};
+use super::runtime::{build_report, Report};
+use super::seahash::hash;
+use super::serde_json::{self, Value};
+use super::stdlib::Registry;
+
+/// identifies an artifact, before its version is read
+const FORMAT: &'static str = "foxhole-artifact";
+
+/// the layout of an artifact, this changes when the graph's JSON
+/// does, an artifact is only loaded by the same version.
+const FORMAT_VERSION: u64 = 1;
+
+/// the compiler which writes artifacts, recorded for information
+const COMPILER_VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+/// Artifact is a compiled program, its optimized graph may be
+/// saved & evaluated later without the source.
+///
+/// It is written as JSON, a header records the format version,
+/// the compiler version, a hash of the source, and the parameters
+/// the graph was built with.
+#[derive(Clone)]
+pub struct Artifact {
+    /// the version of the compiler which wrote the artifact
+    compiler: String,
+    source_hash: u64,
+    /// the value of each parameter the graph depends upon
+    parameters: BTreeMap<String, String>,
+    graph: OrderedCollection,
+}
+impl Artifact {
+    /// the artifact of a program, as optimized for the bindings
+    /// it was compiled with
+    pub fn new(program: &Program) -> Artifact {
+        let bindings = program.get_bindings();
+        Artifact {
+            compiler: COMPILER_VERSION.to_string(),
+            source_hash: hash_source(program.get_source()),
+            parameters: program
+                .get_parameters()
+                .iter()
+                .flat_map(|name| bindings.get(name).ok().map(|value| (name.clone(), value)))
+                .collect(),
+            graph: program.get_graph().clone(),
+        }
+    }
+
+    /// checks if source code looks like an artifact, rather than
+    /// a program
+    pub fn is_artifact(source: &str) -> bool {
+        source.trim_start().starts_with('{')
+    }
+
+    /// loads an artifact. Artifacts of another format version are
+    /// rejected, they should be compiled again. Artifacts from
+    /// other versions of the compiler are loaded.
+    pub fn parse(data: &str, registry: &Registry) -> Result<Artifact, String> {
+        let value: Value = match serde_json::from_str(data) {
+            Ok(value) => value,
+            Err(e) => return Err(format!("artifact is not valid JSON, error={}", e)),
+        };
+        if value["format"] != FORMAT {
+            return Err("file is not a compiled foxhole artifact".to_string());
+        }
+        let compiler = match value["compiler"].as_str() {
+            Option::Some(version) => version.to_string(),
+            Option::None => return Err("artifact does not record its compiler".to_string()),
+        };
+        match value["version"].as_u64() {
+            Option::Some(version) if version == FORMAT_VERSION => {}
+            Option::Some(version) => {
+                return Err(format!(
+                    "artifact has format version {}, written by foxhole {}, \
+                     while this compiler reads version {}, compile it again",
+                    version, compiler, FORMAT_VERSION
+                ));
+            }
+            Option::None => return Err("artifact does not record its format version".to_string()),
+        };
+        let source_hash = match value["source_hash"]
+            .as_str()
+            .map(|x| u64::from_str_radix(x, 16))
+        {
+            Option::Some(Ok(source_hash)) => source_hash,
+            _ => return Err("artifact does not record its source hash".to_string()),
+        };
+        let parameters = value["parameters"]
+            .as_object()
+            .into_iter()
+            .flat_map(|x| x.iter())
+            .map(|(name, value)| match value.as_str() {
+                Option::Some(value) => Ok((name.clone(), value.to_string())),
+                Option::None => Err(format!("artifact parameter '{}' is not a string", name)),
+            })
+            .collect::<Result<BTreeMap<String, String>, String>>()?;
+        let graph = OrderedCollection::from_json(&value["graph"], registry)
+            .map_err(|e| format!("artifact is malformed, {}", e))?;
+        Ok(Artifact {
+            compiler,
+            source_hash,
+            parameters,
+            graph,
+        })
+    }
+
+    /// writes the artifact as JSON
+    pub fn serialize(&self) -> String {
+        let value = json!({
+            "format": FORMAT,
+            "version": FORMAT_VERSION,
+            "compiler": self.compiler,
+            "source_hash": format!("{:016x}", self.source_hash),
+            "parameters": self.parameters,
+            "graph": self.graph.to_json(),
+        });
+        value.to_string()
+    }
+
+    /// the version of the compiler which wrote the artifact
+    pub fn get_compiler(&self) -> &str {
+        &self.compiler
+    }
+
+    /// a hash of the source the artifact was compiled from
+    pub fn get_source_hash(&self) -> u64 {
+        self.source_hash
+    }
+
+    /// checks if the artifact was compiled from `source`
+    pub fn is_compiled_from(&self, source: &str) -> bool {
+        self.source_hash == hash_source(source)
+    }
+
+    pub fn get_parameters(&self) -> &BTreeMap<String, String> {
+        &self.parameters
+    }
+
+    pub fn get_graph(&self) -> &OrderedCollection {
+        &self.graph
+    }
+
+    /// evaluates every possible outcome of the artifact. The
+    /// graph is fixed, so parameters within `bindings` must agree
+    /// with those it was compiled with.
+    pub fn evaluate(&self, bindings: &Bindings) -> Result<Report, Diagnostics> {
+        for (name, value) in self.parameters.iter() {
+            match bindings.get(name) {
+                Ok(ref given) if given != value => {
+                    return Err(vec![Diagnostic::error(
+                        Code::Evaluation,
+                        format!(
+                            "artifact was compiled with {}={}, compile it again to use {}={}",
+                            name, value, name, given
+                        ),
+                    )]);
+                }
+                _ => {}
+            };
+        }
+        build_report(&self.graph).map_err(|e| vec![Diagnostic::error(Code::Evaluation, e)])
+    }
+}
+
+fn hash_source(source: &str) -> u64 {
+    hash(source.as_bytes())
+}
+
+#[test]
+fn test_artifact() {
+    use super::program::{compile, CompileOptions};
+    use super::runtime::Datum;
+
+    let dut = r#"analyze (sum(roll_d6(2)) >= %d{{T}});"#;
+    let mut options = CompileOptions::default();
+    options.bindings.read_environment(false);
+    options.bindings.define("T", "7");
+    let program = compile(dut, &options).unwrap();
+    let saved = Artifact::new(&program).serialize();
+    assert!(Artifact::is_artifact(&saved));
+    assert!(!Artifact::is_artifact(dut));
+
+    // the artifact runs without its source
+    let artifact = Artifact::parse(&saved, &Registry::standard()).unwrap();
+    assert!(artifact.is_compiled_from(dut));
+    assert_eq!(artifact.get_parameters()["T"], "7");
+    let report = artifact.evaluate(&options.bindings).unwrap();
+    let expected = program.evaluate(&options.bindings).unwrap();
+    assert_eq!(
+        report.get_probability(&Datum::from(true)),
+        expected.get_probability(&Datum::from(true))
+    );
+    let mut other = options.bindings.clone();
+    other.define("T", "8");
+    assert!(artifact.evaluate(&other).is_err());
+
+    // other compilers are loaded, other formats, and other files,
+    // are rejected
+    let old = saved.replace(
+        &format!("\"compiler\":\"{}\"", COMPILER_VERSION),
+        "\"compiler\":\"0.0.0\"",
+    );
+    let artifact = Artifact::parse(&old, &Registry::standard()).unwrap();
+    assert_eq!(artifact.get_compiler(), "0.0.0");
+    let other_format = saved.replace(
+        &format!("\"version\":{}", FORMAT_VERSION),
+        "\"version\":0",
+    );
+    let e = Artifact::parse(&other_format, &Registry::standard())
+        .err()
+        .unwrap();
+    assert!(e.contains("format version 0"), "{}", e);
+    assert!(Artifact::parse("{}", &Registry::standard()).is_err());
+    assert!(Artifact::parse(dut, &Registry::standard()).is_err());
+}
@@ -1,8 +1,10 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::BTreeMap;
+use std::fmt;
 
+use super::super::dump::Dump;
 use super::super::namespace::{BasicBlock, BlockExpression, Namespace};
-use super::super::parser_output::FunctionDeclaration;
-use super::super::seahasher::DefaultSeaHasher;
+use super::super::parser_output::{FunctionDeclaration, Literal};
+use super::super::stdlib::{Registry, StdFunc};
 
 use super::expression::HashedExpression;
 use super::identifier::Identifier;
@@ -18,6 +20,7 @@ pub struct ExpressionCollection<'a> {
     vars: BTreeMap<Identifier, u64>,
     functions: BTreeMap<Identifier, ExpressionCollection<'a>>,
     function_signature: BTreeMap<Identifier, FunctionDeclaration<'a>>,
+    registry: Registry,
     ret: Option<u64>,
 }
 impl<'a> ExpressionCollection<'a> {
@@ -25,6 +28,7 @@ impl<'a> ExpressionCollection<'a> {
     /// an expression collection.
     pub fn new(namespace: &Namespace<'a>) -> ExpressionCollection<'a> {
         let mut expression = ExpressionCollection::from_namespace(namespace);
+        expression.registry = namespace.get_registry().clone();
         for (name, block) in namespace.get_all_function_blocks() {
             expression.insert_function(namespace, name, block)
         }
@@ -34,7 +38,6 @@ impl<'a> ExpressionCollection<'a> {
     /// get the return statement
     pub fn get_return<'b>(&'b self) -> Option<&'b HashedExpression<'a>> {
         self.ret
-            .clone()
             .into_iter()
             .flat_map(|ret_id| self.data.get(&ret_id))
             .next()
@@ -71,14 +74,14 @@ impl<'a> ExpressionCollection<'a> {
             .next()
     }
 
-    /// returns if the function is or is not part of the stdlib
-    pub fn is_function_stdlib(&self, id: &Identifier) -> bool {
+    /// returns the standard library function, when the identifier
+    /// names one.
+    pub fn get_std_function(&self, id: &Identifier) -> Option<StdFunc> {
         self.function_signature
             .get(id)
-            .into_iter()
-            .map(|sig| sig.stdlib.clone())
-            .next()
-            .unwrap_or(false)
+            .filter(|sig| sig.stdlib)
+            .and_then(|sig| self.registry.get(sig.name))
+            .cloned()
     }
 
     /// returns an iterator of all variables within this scope
@@ -107,6 +110,58 @@ impl<'a> ExpressionCollection<'a> {
             .next()
     }
 
+    /// the top level graph as it is written, each variable reads
+    /// the expression which defines it.
+    pub fn dump(&self) -> Dump {
+        self.dump_scope(&self.get_names())
+    }
+
+    /// the name of every variable & function, within every scope
+    fn get_names(&self) -> BTreeMap<Identifier, &'a str> {
+        let mut names = BTreeMap::new();
+        for (id, sig) in self.function_signature.iter() {
+            names.insert(*id, sig.name);
+        }
+        for coll in Some(self).into_iter().chain(self.functions.values()) {
+            names.extend(coll.var_names.iter().map(|(id, name)| (*id, *name)));
+        }
+        names
+    }
+
+    fn dump_scope(&self, names: &BTreeMap<Identifier, &'a str>) -> Dump {
+        let name = |id: &Identifier| names.get(id).cloned().unwrap_or("?");
+        let mut dump = Dump::default();
+        for (id, expr) in self.data.iter() {
+            let (op, sources) = match expr {
+                &HashedExpression::FunctionArg(_, ref index, _) => {
+                    (format!("arg {}", index), vec![])
+                }
+                &HashedExpression::ConstantValue(ref lit, _, _) => {
+                    (format!("const {}", lit), vec![])
+                }
+                &HashedExpression::ExternalConstant(ref var, _) => (
+                    format!("extern {}", name(var)),
+                    self.vars.get(var).cloned().into_iter().collect(),
+                ),
+                &HashedExpression::Var(ref var, _) => (
+                    format!("var {}", name(var)),
+                    self.vars.get(var).cloned().into_iter().collect(),
+                ),
+                &HashedExpression::Func(ref func, ref args, _, _) => {
+                    (name(func).to_string(), args.to_vec())
+                }
+                &HashedExpression::Op(ref left, ref op, ref right, _) => {
+                    (op.to_string(), vec![*left, *right])
+                }
+            };
+            dump.insert(*id, op, expr.get_type(), sources);
+        }
+        if let Option::Some(ret) = self.ret {
+            dump.set_return(ret);
+        }
+        dump
+    }
+
     /// This converts a basic block into a much lower CFG like expression.
     fn from_block(
         namespace: &'a str,
@@ -136,17 +191,17 @@ impl<'a> ExpressionCollection<'a> {
     /// convert the top level namespace to a value.
     fn from_namespace(n: &Namespace<'a>) -> Self {
         let mut collection = ExpressionCollection::default();
-        for (name, expr) in n.get_own_block().into_iter().flat_map(|b| b.get_vars()) {
+        for (name, expr) in n.get_own_block().iter().flat_map(|b| b.get_vars()) {
             collection.insert_vars(None, name, expr);
         }
         match n
             .get_own_block()
-            .into_iter()
+            .iter()
             .flat_map(|b| b.get_return())
             .next()
         {
             Option::None => _unreachable_panic!(),
-            Option::Some(ref expr) => {
+            Option::Some(expr) => {
                 collection.ret = Some(collection.insert_block(None, expr));
             }
         };
@@ -186,34 +241,46 @@ impl<'a> ExpressionCollection<'a> {
     /// into the internal collection.
     fn insert_block(&mut self, n: Option<&'a str>, expr: &BlockExpression<'a>) -> u64 {
         let expr = match expr {
-            &BlockExpression::FunctionArg(ref name, ref index, ref kind) => {
+            &BlockExpression::FunctionArg(name, ref index, ref kind, _) => {
                 let name_id = Identifier::new(n, name);
-                HashedExpression::FunctionArg(name_id, index.clone(), kind.clone())
+                HashedExpression::FunctionArg(name_id, *index, *kind)
             }
-            &BlockExpression::ConstantValue(ref value, ref kind) => {
-                HashedExpression::ConstantValue(value.clone(), kind.clone())
+            &BlockExpression::ConstantValue(ref value, ref kind, span) => {
+                // decks are not random, but drawing from one deck
+                // is not drawing from another
+                let occurrence = match value {
+                    &Literal::Deck(_) => Some(span.start),
+                    _ if expr.get_determinism().is_random() => Some(span.start),
+                    _ => None,
+                };
+                HashedExpression::ConstantValue(value.clone(), *kind, occurrence)
             }
-            &BlockExpression::ExternalConstant(ref name, ref kind) => {
+            &BlockExpression::ExternalConstant(name, ref kind, _, _) => {
                 let name_id = Identifier::new(None, name);
-                HashedExpression::ExternalConstant(name_id, kind.clone())
+                HashedExpression::ExternalConstant(name_id, *kind)
             }
-            &BlockExpression::Var(ref name, ref kind) => {
+            &BlockExpression::Var(name, ref kind, _, _) => {
                 let name_id = Identifier::new(n, name);
-                HashedExpression::Var(name_id, kind.clone())
+                HashedExpression::Var(name_id, *kind)
             }
-            &BlockExpression::Func(ref name, ref args, ref kind) => {
+            &BlockExpression::Func(name, ref args, ref kind, ref determinism, span) => {
                 let name_id = Identifier::new(None, name);
                 let arg_refs = args
                     .iter()
                     .map(|argument| self.insert_block(n, argument))
                     .collect::<Vec<u64>>()
                     .into_boxed_slice();
-                HashedExpression::Func(name_id, arg_refs, kind.clone())
+                let occurrence = if determinism.is_random() {
+                    Some(span.start)
+                } else {
+                    None
+                };
+                HashedExpression::Func(name_id, arg_refs, *kind, occurrence)
             }
-            &BlockExpression::Op(ref left, ref op, ref right, ref kind) => {
+            &BlockExpression::Op(ref left, ref op, ref right, ref kind, _) => {
                 let left = self.insert_block(n, left);
                 let right = self.insert_block(n, right);
-                HashedExpression::Op(left, op.clone(), right, kind.clone())
+                HashedExpression::Op(left, *op, right, *kind)
             }
         };
         self.insert_expression(expr)
@@ -221,7 +288,26 @@ impl<'a> ExpressionCollection<'a> {
 
     fn insert_expression(&mut self, expr: HashedExpression<'a>) -> u64 {
         let key = expr.get_hash();
-        self.data.insert(key.clone(), expr);
+        self.data.insert(key, expr);
         key
     }
 }
+impl<'a> fmt::Display for ExpressionCollection<'a> {
+    /// writes the top level graph, then the body of each function
+    /// sorted by name. Standard library functions have no body.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self.get_names();
+        write!(f, "{}", self.dump_scope(&names))?;
+        let mut functions: Vec<(&'a str, &ExpressionCollection<'a>)> = self
+            .functions
+            .iter()
+            .filter(|(_, func)| func.ret.is_some())
+            .map(|(id, func)| (names.get(id).cloned().unwrap_or("?"), func))
+            .collect();
+        functions.sort_by_key(|(name, _)| *name);
+        for (name, func) in functions {
+            write!(f, "\n\nfn {}:\n{}", name, func.dump_scope(&names))?;
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,6 @@
 use std::hash::{Hash, Hasher};
 
-use super::super::parser_output::{
-    Expression, FunctionInvocation, GetType, Literal, Operation, TypeData,
-};
+use super::super::parser_output::{Literal, Operation, TypeData};
 use super::super::seahash::SeaHasher;
 
 use super::identifier::Identifier;
@@ -17,30 +15,36 @@ use super::identifier::Identifier;
 /// hashes, and shove everything into local tables.
 ///
 /// Since names are immutable, and defined only once hashing
-/// is all we need.
+/// is all we need. Random expressions are the exception, each
+/// is a variable of its own, so they carry their `Occurrence`.
+/// Occurrence is where a random expression was written. Two
+/// identical random expressions are still independent, while
+/// every other expression is shared by all that read it.
+pub type Occurrence = Option<usize>;
+
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum HashedExpression<'a> {
     FunctionArg(Identifier, usize, TypeData),
-    ConstantValue(Literal<'a>, TypeData),
+    ConstantValue(Literal<'a>, TypeData, Occurrence),
     ExternalConstant(Identifier, TypeData),
     Var(Identifier, TypeData),
-    Func(Identifier, Box<[u64]>, TypeData),
+    Func(Identifier, Box<[u64]>, TypeData, Occurrence),
     Op(u64, Operation, u64, TypeData),
 }
 impl<'a> HashedExpression<'a> {
     pub fn get_type(&self) -> TypeData {
         match self {
-            HashedExpression::FunctionArg(_, _, ref op) => op.clone(),
-            HashedExpression::ConstantValue(_, ref op) => op.clone(),
-            HashedExpression::ExternalConstant(_, ref op) => op.clone(),
-            HashedExpression::Var(_, ref op) => op.clone(),
-            HashedExpression::Func(_, _, ref op) => op.clone(),
-            HashedExpression::Op(_, _, _, ref op) => op.clone(),
+            HashedExpression::FunctionArg(_, _, ref op) => *op,
+            HashedExpression::ConstantValue(_, ref op, _) => *op,
+            HashedExpression::ExternalConstant(_, ref op) => *op,
+            HashedExpression::Var(_, ref op) => *op,
+            HashedExpression::Func(_, _, ref op, _) => *op,
+            HashedExpression::Op(_, _, _, ref op) => *op,
         }
     }
 
     pub fn hash(h: &Option<HashedExpression<'a>>) -> Option<u64> {
-        h.into_iter().map(|expr| expr.get_hash()).next()
+        h.iter().map(|expr| expr.get_hash()).next()
     }
 
     pub fn get_hash(&self) -> u64 {
@@ -50,15 +54,15 @@ impl<'a> HashedExpression<'a> {
     }
 
     pub fn get_func_arg(index: &usize) -> impl Fn(&HashedExpression<'a>) -> Option<u64> {
-        let index = index.clone();
-        return move |arg: &HashedExpression<'a>| -> Option<u64> {
+        let index = *index;
+        move |arg: &HashedExpression<'a>| -> Option<u64> {
             let index = index;
             match arg {
-                &HashedExpression::Func(_, ref args, _) if index.clone() < args.len() => {
-                    Some(args[index.clone()].clone())
+                &HashedExpression::Func(_, ref args, _, _) if index < args.len() => {
+                    Some(args[index])
                 }
                 _ => None,
             }
-        };
+        }
     }
 }
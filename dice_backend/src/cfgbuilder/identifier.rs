@@ -25,7 +25,7 @@ impl Identifier {
     /// we need to enter that scope.
     pub fn defining_namespace(&self) -> Option<Identifier> {
         match self {
-            &Identifier::Scoped(ref namespace, _) => Some(Identifier::Global(namespace.clone())),
+            &Identifier::Scoped(ref namespace, _) => Some(Identifier::Global(*namespace)),
             _ => None,
         }
     }
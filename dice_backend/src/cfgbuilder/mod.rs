@@ -1,5 +1,5 @@
 mod expression;
-pub use self::expression::HashedExpression;
+pub use self::expression::{HashedExpression, Occurrence};
 mod collection;
 pub use self::collection::ExpressionCollection;
 mod identifier;
@@ -40,20 +40,20 @@ analyze lol_add(4,0);
 
     // `analyze lol_add(4,0)` means we should see that function
     let lol_add_id = match cfgcoll.get_return().unwrap() {
-        &HashedExpression::Func(ref id, ref args, ref kind) => {
+        &HashedExpression::Func(ref id, ref args, ref kind, _) => {
             assert_eq!(args.len(), 2);
             assert_eq!(kind.clone(), TypeData::Int);
 
             // check the function's arguments
             // `analyze lol_add(4,0)` means this should be `4`
             match cfgcoll.get_expr(None, &args[0]).unwrap() {
-                &HashedExpression::ConstantValue(Literal::Number(4), TypeData::Int) => {}
-                anything_else => panic!("expected a constant of 4, found {:?}"),
+                &HashedExpression::ConstantValue(Literal::Number(4), TypeData::Int, _) => {}
+                anything_else => panic!("expected a constant of 4, found {:?}", anything_else),
             };
             // check the function's arguments
             // `analyze lol_add(4,0)` means this should be `0`
             match cfgcoll.get_expr(None, &args[1]).unwrap() {
-                &HashedExpression::ConstantValue(Literal::Number(0), TypeData::Int) => {}
+                &HashedExpression::ConstantValue(Literal::Number(0), TypeData::Int, _) => {}
                 anything_else => panic!("expected a constant of 0, found {:?}", anything_else),
             };
             id
@@ -71,7 +71,7 @@ analyze lol_add(4,0);
             // return ( ( lol_sub(x) + lol_sub(y) ) + TEN);
             // ten is a global var so we need to use the full context
             match lol_add_ctx.get_expr(Some(*lol_add_id), right) {
-                Option::Some(HashedExpression::ExternalConstant(ref id, TypeData::Int)) => {}
+                Option::Some(HashedExpression::ExternalConstant(_, TypeData::Int)) => {}
                 anything_else => panic!("expected a constant, found {:?}", anything_else),
             };
             // the left is another addition
@@ -89,6 +89,7 @@ analyze lol_add(4,0);
                             ref left_id,
                             ref left_args,
                             TypeData::Int,
+                            _,
                         )) => (left_id, left_args),
                         anything_else => panic!("{:?}", anything_else),
                     };
@@ -99,6 +100,7 @@ analyze lol_add(4,0);
                                 ref right_id,
                                 ref right_args,
                                 TypeData::Int,
+                                _,
                             )) => (right_id, right_args),
                             anything_else => panic!("{:?}", anything_else),
                         };
@@ -1,4 +1,8 @@
+use std::hash::{Hash, Hasher};
+
 use super::super::cfgbuilder::{ExpressionCollection, HashedExpression, Identifier};
+use super::super::seahash::SeaHasher;
+use super::super::stdlib::StdFunc;
 
 /// CallStack manages how namespace expression look ups are handled
 /// Namely, that we keep track of _where_ we are within the call stack while doing inlining.
@@ -9,6 +13,8 @@ pub struct CallStack<'a, 'b> {
     name: Vec<Identifier>,
     args: Vec<Box<[u64]>>,
     expr: Vec<u64>,
+    bindings: Vec<(Identifier, usize)>,
+    entry: Vec<Option<Identifier>>,
 }
 impl<'a, 'b> CallStack<'a, 'b> {
     /// This will build a new instance of CallStack from a root
@@ -20,6 +26,8 @@ impl<'a, 'b> CallStack<'a, 'b> {
             name: Vec::with_capacity(10),
             args: Vec::with_capacity(10),
             expr: Vec::with_capacity(10),
+            bindings: Vec::with_capacity(10),
+            entry: Vec::with_capacity(10),
         }
     }
 
@@ -27,17 +35,12 @@ impl<'a, 'b> CallStack<'a, 'b> {
     /// context
     pub fn push(&mut self, id: &Identifier, expr: &u64) {
         // ensure no recursive calls happen
-        let recursion = self
-            .name
-            .iter()
-            .filter(|existing| *existing == id)
-            .next()
-            .is_some();
+        let recursion = self.name.iter().any(|existing| existing == id);
         if recursion {
             panic!("recursion detected");
         }
         let args = match self.get_expr(expr) {
-            Option::Some(HashedExpression::Func(ref looked_up, ref args, _)) => {
+            Option::Some(HashedExpression::Func(ref looked_up, ref args, _, _)) => {
                 assert_eq!(looked_up, id);
                 args.clone()
             }
@@ -47,10 +50,11 @@ impl<'a, 'b> CallStack<'a, 'b> {
             Option::None => _unreachable_panic!(),
             Option::Some(namespace) => namespace,
         };
-        self.name.push(id.clone());
+        self.name.push(*id);
         self.body.push(namespace);
         self.args.push(args);
-        self.expr.push(expr.clone());
+        self.expr.push(*expr);
+        self.entry.push(self.bindings.last().map(|&(id, _)| id));
     }
 
     /// removes a function from the namespace
@@ -59,10 +63,49 @@ impl<'a, 'b> CallStack<'a, 'b> {
         self.body.pop();
         self.args.pop();
         self.expr.pop();
+        self.entry.pop();
+    }
+
+    /// note that the definition of a `let` or `const` is being inlined
+    pub fn push_binding(&mut self, id: &Identifier) {
+        self.bindings.push((*id, self.expr.len()));
+    }
+
+    /// the definition of the innermost binding is finished
+    pub fn pop_binding(&mut self) {
+        self.bindings.pop();
+    }
+
+    /// returns an identifier for the expression `expr`, within the
+    /// binding that is presently being inlined, and the chain of
+    /// calls which reached it.
+    ///
+    /// Expressions with identity (a roll, a deck, a draw from a deck)
+    /// use this so that reading one binding twice yields the same
+    /// value, while two bindings with identical definitions, or two
+    /// calls of one function, stay distinct.
+    pub fn get_site(&self, expr: &u64) -> u64 {
+        let mut hasher = SeaHasher::default();
+        match self.bindings.last() {
+            Option::Some(&(ref id, depth)) if id.is_global() => {
+                // global definitions don't depend on who called them,
+                // only on the calls made while defining them
+                id.hash(&mut hasher);
+                self.expr[depth..].hash(&mut hasher);
+            }
+            innermost => {
+                self.entry.hash(&mut hasher);
+                self.expr.hash(&mut hasher);
+                innermost.map(|&(id, _)| id).hash(&mut hasher);
+            }
+        };
+        expr.hash(&mut hasher);
+        hasher.finish()
     }
 
-    pub fn is_stdlib(&self, id: &Identifier) -> bool {
-        self.namespace.is_function_stdlib(id)
+    /// returns the standard library function an identifier names
+    pub fn get_std_function(&self, id: &Identifier) -> Option<StdFunc> {
+        self.namespace.get_std_function(id)
     }
 
     pub fn get_function_name(&self, id: &Identifier) -> Option<&'a str> {
@@ -93,7 +136,7 @@ impl<'a, 'b> CallStack<'a, 'b> {
     pub fn get_context(&self) -> Option<Identifier> {
         self.get_last_index()
             .into_iter()
-            .map(|index| self.name[index].clone())
+            .map(|index| self.name[index])
             .next()
     }
     /// returns the expression of the function invocation
@@ -101,7 +144,7 @@ impl<'a, 'b> CallStack<'a, 'b> {
     pub fn get_ctx_expr(&self) -> Option<u64> {
         self.get_last_index()
             .into_iter()
-            .map(|index| self.expr[index].clone())
+            .map(|index| self.expr[index])
             .next()
     }
     /// returns the expression data of the arg's index we're in
@@ -109,7 +152,7 @@ impl<'a, 'b> CallStack<'a, 'b> {
         use std::ops::Index;
         self.get_last_index()
             .into_iter()
-            .map(|index| self.args.index(index).index(arg_index).clone())
+            .map(|index| *self.args.index(index).index(arg_index))
             .next()
     }
 
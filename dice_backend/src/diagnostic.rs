@@ -0,0 +1,502 @@
+use std::fmt;
+use std::fmt::Write;
+
+use super::parser_output::Span;
+use super::syntaxhelper::CharacterLookup;
+
+/// Label marks a span of the source with a short message.
+///
+/// The primary label is where the error is, secondary labels
+/// give context such as "declared here".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+/// Severity is how seriously a diagnostic should be taken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            &Severity::Error => "error",
+            &Severity::Warning => "warning",
+        }
+    }
+}
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Code identifies the kind of a diagnostic.
+///
+/// Codes are stable, once assigned a code is never reused for
+/// a different kind of error, so tools may match upon them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Code {
+    /// E0001 a variable or constant which was never declared
+    UnknownIdentifier,
+    /// E0002 a function which was never declared
+    UnknownFunction,
+    /// E0003 a name which is declared twice
+    DuplicateDeclaration,
+    /// E0004 a value whose type differs from its declaration
+    TypeMismatch,
+    /// E0005 a function invoked with the wrong number of arguments
+    ArgumentCount,
+    /// E0006 a function argument of the wrong type
+    ArgumentType,
+    /// E0007 an operator applied to types it does not support
+    InvalidOperation,
+    /// E0008 source which does not match the grammar
+    SyntaxError,
+    /// E0009 a program without an `analyze` statement
+    MissingAnalyze,
+    /// E0010 a function with more than one `return`
+    MultipleReturns,
+    /// E0011 a literal or parameter which cannot be resolved
+    InvalidLiteral,
+    /// E0012 an error while evaluating the program
+    Evaluation,
+    /// E0013 a function or constant defined in terms of itself
+    RecursiveDefinition,
+    /// E0014 a random value where a constant is required
+    RandomArgument,
+    /// W0001 a constant which never affects the result
+    UnusedConstant,
+    /// W0002 a function which is never invoked
+    UnusedFunction,
+    /// W0003 a `let` binding which never affects a return
+    UnusedVariable,
+    /// W0004 a function parameter which is never read
+    UnusedParameter,
+    /// W0005 a `let` binding with the name of a constant
+    ShadowedConstant,
+    /// W0006 a pool of dice whose size is random
+    RandomLength,
+    /// W0007 an `#[allow(..)]` of a lint which does not exist
+    UnknownLint,
+}
+impl Code {
+    /// the stable identifier, such as `E0001`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            &Code::UnknownIdentifier => "E0001",
+            &Code::UnknownFunction => "E0002",
+            &Code::DuplicateDeclaration => "E0003",
+            &Code::TypeMismatch => "E0004",
+            &Code::ArgumentCount => "E0005",
+            &Code::ArgumentType => "E0006",
+            &Code::InvalidOperation => "E0007",
+            &Code::SyntaxError => "E0008",
+            &Code::MissingAnalyze => "E0009",
+            &Code::MultipleReturns => "E0010",
+            &Code::InvalidLiteral => "E0011",
+            &Code::Evaluation => "E0012",
+            &Code::RecursiveDefinition => "E0013",
+            &Code::RandomArgument => "E0014",
+            &Code::UnusedConstant => "W0001",
+            &Code::UnusedFunction => "W0002",
+            &Code::UnusedVariable => "W0003",
+            &Code::UnusedParameter => "W0004",
+            &Code::ShadowedConstant => "W0005",
+            &Code::RandomLength => "W0006",
+            &Code::UnknownLint => "W0007",
+        }
+    }
+
+    /// a short human readable description of the code
+    pub fn get_description(&self) -> &'static str {
+        match self {
+            &Code::UnknownIdentifier => "unknown identifier",
+            &Code::UnknownFunction => "unknown function",
+            &Code::DuplicateDeclaration => "duplicate declaration",
+            &Code::TypeMismatch => "type mismatch",
+            &Code::ArgumentCount => "wrong number of arguments",
+            &Code::ArgumentType => "wrong argument type",
+            &Code::InvalidOperation => "invalid operation",
+            &Code::SyntaxError => "syntax error",
+            &Code::MissingAnalyze => "missing analyze statement",
+            &Code::MultipleReturns => "multiple return statements",
+            &Code::InvalidLiteral => "invalid literal",
+            &Code::Evaluation => "evaluation failed",
+            &Code::RecursiveDefinition => "recursive definition",
+            &Code::RandomArgument => "random argument where a constant is required",
+            &Code::UnusedConstant => "unused constant",
+            &Code::UnusedFunction => "unused function",
+            &Code::UnusedVariable => "unused variable",
+            &Code::UnusedParameter => "unused parameter",
+            &Code::ShadowedConstant => "variable shadows a constant",
+            &Code::RandomLength => "collection of random length",
+            &Code::UnknownLint => "unknown lint",
+        }
+    }
+
+    /// the name used to allow a warning, with `#[allow(name)]`
+    pub fn get_lint_name(&self) -> Option<&'static str> {
+        match self {
+            &Code::UnusedConstant => Some("unused_const"),
+            &Code::UnusedFunction => Some("unused_function"),
+            &Code::UnusedVariable => Some("unused_variable"),
+            &Code::UnusedParameter => Some("unused_parameter"),
+            &Code::ShadowedConstant => Some("shadowed_const"),
+            &Code::RandomLength => Some("random_length"),
+            &Code::UnknownLint => Some("unknown_lint"),
+            _ => None,
+        }
+    }
+
+    /// every warning, in the order of their codes
+    pub fn warnings() -> &'static [Code] {
+        &[
+            Code::UnusedConstant,
+            Code::UnusedFunction,
+            Code::UnusedVariable,
+            Code::UnusedParameter,
+            Code::ShadowedConstant,
+            Code::RandomLength,
+            Code::UnknownLint,
+        ]
+    }
+}
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// ErrorFormat selects how diagnostics are rendered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// rustc style, with the offending source underlined
+    Human,
+    /// one JSON object per diagnostic, for tools
+    Json,
+}
+impl ErrorFormat {
+    pub fn parse(arg: &str) -> Result<ErrorFormat, String> {
+        match arg {
+            "human" => Ok(ErrorFormat::Human),
+            "json" => Ok(ErrorFormat::Json),
+            _ => Err(format!(
+                "error format '{}' is not one of 'human' or 'json'",
+                arg
+            )),
+        }
+    }
+}
+
+/// Diagnostic is an error which knows where in the source it
+/// originated, so it can be rendered with the offending lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    severity: Severity,
+    code: Code,
+    message: String,
+    labels: Vec<Label>,
+    notes: Vec<String>,
+    help: Vec<String>,
+}
+impl Diagnostic {
+    pub fn new<S: Into<String>>(severity: Severity, code: Code, message: S) -> Diagnostic {
+        Diagnostic {
+            severity,
+            code,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    pub fn error<S: Into<String>>(code: Code, message: S) -> Diagnostic {
+        Diagnostic::new(Severity::Error, code, message)
+    }
+
+    pub fn warning<S: Into<String>>(code: Code, message: S) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, code, message)
+    }
+
+    /// marks where the error is
+    pub fn with_label<S: Into<String>>(mut self, span: Span, message: S) -> Diagnostic {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: true,
+        });
+        self
+    }
+
+    /// adds context from elsewhere in the source. Spans which
+    /// were not parsed from the source (the standard library)
+    /// are ignored.
+    pub fn with_secondary<S: Into<String>>(mut self, span: Span, message: S) -> Diagnostic {
+        if !span.is_empty() {
+            self.labels.push(Label {
+                span,
+                message: message.into(),
+                primary: false,
+            });
+        }
+        self
+    }
+
+    /// adds a note which is displayed after the source
+    pub fn with_note<S: Into<String>>(mut self, note: S) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// adds a suggestion of how to fix the error
+    pub fn with_help<S: Into<String>>(mut self, help: S) -> Diagnostic {
+        self.help.push(help.into());
+        self
+    }
+
+    pub fn get_severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn get_code(&self) -> Code {
+        self.code
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn get_labels(&self) -> &[Label] {
+        &self.labels
+    }
+
+    pub fn get_notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    pub fn get_help(&self) -> &[String] {
+        &self.help
+    }
+
+    /// renders in the requested format
+    pub fn render_as(&self, format: ErrorFormat, file_name: &str, source: &str) -> String {
+        match format {
+            ErrorFormat::Human => self.render(file_name, source),
+            ErrorFormat::Json => self.render_json(file_name, source),
+        }
+    }
+
+    /// renders the diagnostic in the style of rustc
+    ///
+    /// ```text
+    /// error[E0004]: message
+    ///  --> file:line:column
+    ///   |
+    /// 3 | analyze (roll_d6(3) + true);
+    ///   |         ^^^^^^^^^^^^^^^^^^^ label
+    ///   |
+    /// ```
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        let lookup = CharacterLookup::new(source);
+        let mut labels: Vec<&Label> = self
+            .labels
+            .iter()
+            .filter(|label| label.span.start <= source.len())
+            .collect();
+        labels.sort_by_key(|label| (label.span.start, !label.primary));
+        let location = labels
+            .iter()
+            .find(|label| label.primary)
+            .or_else(|| labels.first());
+        let width = labels
+            .iter()
+            .map(|label| (lookup.get_line_number(label.span.start) + 1).to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        let mut s = String::with_capacity(512);
+        writeln!(&mut s, "{}[{}]: {}", self.severity, self.code, self.message).unwrap();
+        match location {
+            Option::Some(label) => writeln!(
+                &mut s,
+                "{:w$}--> {}:{}:{}",
+                "",
+                file_name,
+                lookup.get_line_number(label.span.start) + 1,
+                lookup.get_column(label.span.start) + 1,
+                w = width
+            )
+            .unwrap(),
+            Option::None => writeln!(&mut s, "{:w$}--> {}", "", file_name, w = width).unwrap(),
+        };
+        if !labels.is_empty() {
+            writeln!(&mut s, "{:w$} |", "", w = width).unwrap();
+            let mut last_line: Option<usize> = None;
+            for label in labels.iter() {
+                let line = lookup.get_line_number(label.span.start);
+                match last_line {
+                    Option::Some(last) if last == line => {}
+                    Option::Some(last) if last + 1 != line => {
+                        writeln!(&mut s, "{:w$} |", "", w = width).unwrap();
+                        writeln!(&mut s, "{:>w$} | {}", line + 1, lookup.get_line(line), w = width)
+                            .unwrap();
+                    }
+                    _ => {
+                        writeln!(&mut s, "{:>w$} | {}", line + 1, lookup.get_line(line), w = width)
+                            .unwrap();
+                    }
+                };
+                last_line = Some(line);
+
+                // spans which cover many lines are underlined to the end of their first line
+                let start = lookup.get_column(label.span.start);
+                let end = if lookup.get_line_number(label.span.end) == line {
+                    lookup.get_column(label.span.end)
+                } else {
+                    lookup.get_line(line).chars().count()
+                };
+                let length = if end > start { end - start } else { 1 };
+                let marker = if label.primary { "^" } else { "-" };
+                let underline = format!(
+                    "{}{} {}",
+                    " ".repeat(start),
+                    marker.repeat(length),
+                    label.message
+                );
+                writeln!(&mut s, "{:w$} | {}", "", underline.trim_end(), w = width).unwrap();
+            }
+            writeln!(&mut s, "{:w$} |", "", w = width).unwrap();
+        }
+        for note in self.notes.iter() {
+            writeln!(&mut s, "{:w$} = note: {}", "", note, w = width).unwrap();
+        }
+        for help in self.help.iter() {
+            writeln!(&mut s, "{:w$} = help: {}", "", help, w = width).unwrap();
+        }
+        s
+    }
+
+    /// renders the diagnostic as a single line of JSON
+    ///
+    /// lines & columns are 1 based, offsets are bytes into the source.
+    pub fn render_json(&self, file_name: &str, source: &str) -> String {
+        let lookup = CharacterLookup::new(source);
+        let mut s = String::with_capacity(512);
+        write!(
+            &mut s,
+            "{{\"severity\":{},\"code\":{},\"message\":{},\"file\":{},\"labels\":[",
+            json_string(self.severity.as_str()),
+            json_string(self.code.as_str()),
+            json_string(&self.message),
+            json_string(file_name)
+        )
+        .unwrap();
+        let labels = self
+            .labels
+            .iter()
+            .filter(|label| label.span.start <= source.len());
+        for (index, label) in labels.enumerate() {
+            if index != 0 {
+                s.push(',');
+            }
+            write!(
+                &mut s,
+                "{{\"start\":{},\"end\":{},\"line\":{},\"column\":{},\"primary\":{},\"message\":{}}}",
+                label.span.start,
+                label.span.end,
+                lookup.get_line_number(label.span.start) + 1,
+                lookup.get_column(label.span.start) + 1,
+                label.primary,
+                json_string(&label.message)
+            )
+            .unwrap();
+        }
+        s.push_str("],\"notes\":[");
+        s.push_str(&self.notes.iter().map(|n| json_string(n)).collect::<Vec<_>>().join(","));
+        s.push_str("],\"help\":[");
+        s.push_str(&self.help.iter().map(|h| json_string(h)).collect::<Vec<_>>().join(","));
+        s.push_str("]}");
+        s
+    }
+}
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)?;
+        for note in self.notes.iter() {
+            write!(f, " ({})", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// renders every diagnostic, human diagnostics are separated by a
+/// blank line & JSON diagnostics are one per line.
+pub fn render_all(
+    diagnostics: &[Diagnostic],
+    format: ErrorFormat,
+    file_name: &str,
+    source: &str,
+) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.render_as(format, file_name, source))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// quotes & escapes a string for JSON
+fn json_string(arg: &str) -> String {
+    let mut s = String::with_capacity(arg.len() + 2);
+    s.push('"');
+    for c in arg.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(&mut s, "\\u{:04x}", c as u32).unwrap(),
+            c => s.push(c),
+        };
+    }
+    s.push('"');
+    s
+}
+
+#[test]
+fn test_render_diagnostic() {
+    let source = "const x: int = 5;\nanalyze (x + true);\n";
+    let diagnostic = Diagnostic::error(Code::InvalidOperation, "type error")
+        .with_label(Span::new(26, 36), "int cannot + with bool")
+        .with_secondary(Span::new(0, 17), "x declared here")
+        .with_secondary(Span::default(), "ignored")
+        .with_note("only int & vec<int> may be added")
+        .with_help("compare with `x == 1` instead");
+    let expected = r#"error[E0007]: type error
+ --> test.dice:2:9
+  |
+1 | const x: int = 5;
+  | ----------------- x declared here
+2 | analyze (x + true);
+  |         ^^^^^^^^^^ int cannot + with bool
+  |
+  = note: only int & vec<int> may be added
+  = help: compare with `x == 1` instead
+"#;
+    assert_eq!(diagnostic.render("test.dice", source), expected);
+
+    let expected = concat!(
+        r#"{"severity":"error","code":"E0007","message":"type error","file":"test.dice","labels":["#,
+        r#"{"start":26,"end":36,"line":2,"column":9,"primary":true,"message":"int cannot + with bool"},"#,
+        r#"{"start":0,"end":17,"line":1,"column":1,"primary":false,"message":"x declared here"}],"#,
+        r#""notes":["only int & vec<int> may be added"],"help":["compare with `x == 1` instead"]}"#
+    );
+    assert_eq!(diagnostic.render_json("test.dice", source), expected);
+    assert_eq!(json_string("a \"b\"\n"), r#""a \"b\"\n""#);
+}
@@ -0,0 +1,213 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use super::diagnostic::Diagnostic;
+use super::namespace::{Bindings, Namespace};
+use super::parser_output::{
+    AbstractSyntaxTree, ConstantDeclaration, FunctionDeclaration, Structures,
+};
+use super::stdlib::Registry;
+
+/// DocFormat selects how reference documentation is written
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocFormat {
+    Markdown,
+    Html,
+}
+impl DocFormat {
+    pub fn parse(arg: &str) -> Result<DocFormat, String> {
+        match arg {
+            "markdown" | "md" => Ok(DocFormat::Markdown),
+            "html" => Ok(DocFormat::Html),
+            _ => Err(format!(
+                "doc format '{}' is not one of 'markdown' or 'html'",
+                arg
+            )),
+        }
+    }
+}
+
+/// Entry is one documented declaration
+struct Entry {
+    name: String,
+    signature: String,
+    doc: String,
+}
+impl Entry {
+    /// a constant, whose type is inferred if the program builds
+    fn constant(constant: &ConstantDeclaration, namespace: Option<&Namespace>) -> Entry {
+        let inferred = namespace.and_then(|n| n.get_constant_type(constant.name));
+        Entry {
+            name: constant.name.to_string(),
+            signature: constant.get_signature(inferred),
+            doc: constant.get_doc(),
+        }
+    }
+}
+impl<'a, 'b> From<&'b FunctionDeclaration<'a>> for Entry {
+    fn from(func: &'b FunctionDeclaration<'a>) -> Entry {
+        Entry {
+            name: func.name.to_string(),
+            signature: func.get_signature(),
+            doc: func.get_doc(),
+        }
+    }
+}
+
+/// Section is a titled list of declarations
+struct Section {
+    title: String,
+    entries: Vec<Entry>,
+}
+
+/// writes reference documentation for the constants & functions
+/// of a program, followed by the standard library. Without a
+/// program only the standard library is documented.
+pub fn document(
+    program: Option<(&str, &str)>,
+    format: DocFormat,
+) -> Result<String, Vec<Diagnostic>> {
+    let mut sections = Vec::new();
+    match program {
+        Option::Some((name, source)) => {
+            let tree = AbstractSyntaxTree::parse(source)?;
+            let source_dir = Path::new(name).parent().unwrap_or(Path::new(""));
+            let namespace = Namespace::build(
+                &tree,
+                source_dir,
+                &Bindings::default(),
+                &Registry::standard(),
+            )
+            .ok();
+            let constants = tree
+                .ast
+                .iter()
+                .filter_map(Structures::to_const)
+                .map(|constant| Entry::constant(constant, namespace.as_ref()))
+                .collect();
+            let functions = tree
+                .ast
+                .iter()
+                .filter_map(Structures::to_func)
+                .map(Entry::from)
+                .collect();
+            sections.push(Section {
+                title: format!("{} constants", name),
+                entries: constants,
+            });
+            sections.push(Section {
+                title: format!("{} functions", name),
+                entries: functions,
+            });
+        }
+        Option::None => {}
+    };
+
+    let stdlib = Namespace::standard_library();
+    let mut functions: Vec<&FunctionDeclaration> =
+        stdlib.get_all_functions().map(|(_, func)| func).collect();
+    functions.sort_by_key(|func| func.name);
+    sections.push(Section {
+        title: "standard library".to_string(),
+        entries: functions.into_iter().map(Entry::from).collect(),
+    });
+
+    sections.retain(|section| !section.entries.is_empty());
+    Ok(match format {
+        DocFormat::Markdown => markdown(&sections),
+        DocFormat::Html => html(&sections),
+    })
+}
+
+fn markdown(sections: &[Section]) -> String {
+    let mut s = String::with_capacity(4096);
+    for section in sections {
+        writeln!(&mut s, "## {}\n", section.title).unwrap();
+        for entry in section.entries.iter() {
+            writeln!(&mut s, "### `{}`\n", entry.name).unwrap();
+            writeln!(&mut s, "```\n{}\n```\n", entry.signature).unwrap();
+            if !entry.doc.is_empty() {
+                writeln!(&mut s, "{}\n", entry.doc).unwrap();
+            }
+        }
+    }
+    s
+}
+
+fn html(sections: &[Section]) -> String {
+    let mut s = String::with_capacity(4096);
+    s.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    s.push_str("<title>dice reference</title>\n</head>\n<body>\n");
+    for section in sections {
+        writeln!(&mut s, "<h2>{}</h2>", escape(&section.title)).unwrap();
+        for entry in section.entries.iter() {
+            writeln!(
+                &mut s,
+                "<h3 id=\"{name}\"><code>{name}</code></h3>",
+                name = escape(&entry.name)
+            )
+            .unwrap();
+            writeln!(
+                &mut s,
+                "<pre><code>{}</code></pre>",
+                escape(&entry.signature)
+            )
+            .unwrap();
+            // blank lines separate paragraphs, like markdown
+            for paragraph in entry.doc.split("\n\n").filter(|p| !p.trim().is_empty()) {
+                writeln!(&mut s, "<p>{}</p>", escape(paragraph.trim())).unwrap();
+            }
+        }
+    }
+    s.push_str("</body>\n</html>\n");
+    s
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[test]
+fn test_document() {
+    let source = r#"
+/// the number of attacks
+/// in a volley
+const attacks = 4;
+
+// not documentation
+fn hit(x: vec<int>) -> vec<bool> {
+    return (x >= 4);
+}
+
+/// hits from every attack
+#[allow(unused_function)]
+fn hits() -> int {
+    return count(hit(roll_d6(attacks)));
+}
+analyze hits();
+"#;
+    let tree = AbstractSyntaxTree::parse(source).unwrap();
+    let attacks = Structures::to_const(&tree.ast[0]).unwrap();
+    assert_eq!(attacks.get_doc(), "the number of attacks\nin a volley");
+    assert_eq!(Structures::to_func(&tree.ast[1]).unwrap().get_doc(), "");
+    assert_eq!(
+        Structures::to_func(&tree.ast[2]).unwrap().get_doc(),
+        "hits from every attack"
+    );
+
+    let markdown = document(Some(("volley.dice", source)), DocFormat::Markdown).unwrap();
+    assert!(markdown.contains("## volley.dice constants\n\n### `attacks`\n\n```\nconst attacks: int\n```\n\nthe number of attacks\nin a volley\n"));
+    assert!(markdown.contains("```\nfn hit(x: vec<int>) -> vec<bool>\n```\n\n###"));
+    assert!(markdown.contains("## standard library"));
+    assert!(markdown.contains("fn roll_d6(number_of_d6_to_roll: int) -> vec<int>"));
+
+    let html = document(None, DocFormat::Html).unwrap();
+    assert!(!html.contains("volley"));
+    assert!(html.contains(
+        "<pre><code>fn sum(collection_to_sum_with: vec&lt;int&gt;) -&gt; int</code></pre>"
+    ));
+    assert!(html.contains("<p>the total of every value within a collection.</p>"));
+}
@@ -0,0 +1,303 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use super::parser_output::TypeData;
+
+/// Dump is a readable listing of one stage's expression graph.
+/// Every node is written after its sources, and is numbered in
+/// that order.
+///
+/// ```text
+/// %0: int = const 2 -> %1
+/// %1: vec<int> = roll_d6 %0 -> %2
+/// %2: int = sum %1
+/// return %2
+/// ```
+#[derive(Default)]
+pub struct Dump {
+    nodes: BTreeMap<u64, Node>,
+    ret: Option<u64>,
+    /// nodes which are highlighted when drawn
+    changed: BTreeSet<u64>,
+}
+
+struct Node {
+    op: String,
+    kind: TypeData,
+    sources: Vec<u64>,
+    /// when `None` the sinks are derived from the other nodes
+    sinks: Option<Vec<u64>>,
+}
+
+impl Dump {
+    /// adds a node, its sinks are the nodes which read it
+    pub fn insert(&mut self, id: u64, op: String, kind: TypeData, sources: Vec<u64>) {
+        self.nodes.insert(
+            id,
+            Node {
+                op,
+                kind,
+                sources,
+                sinks: None,
+            },
+        );
+    }
+
+    /// adds a node whose sinks are recorded by the stage itself
+    pub fn insert_with_sinks(
+        &mut self,
+        id: u64,
+        op: String,
+        kind: TypeData,
+        sources: Vec<u64>,
+        sinks: Vec<u64>,
+    ) {
+        self.nodes.insert(
+            id,
+            Node {
+                op,
+                kind,
+                sources,
+                sinks: Some(sinks),
+            },
+        );
+    }
+
+    pub fn set_return(&mut self, id: u64) {
+        self.ret = Some(id);
+    }
+
+    /// highlights nodes when the graph is drawn, such as those a
+    /// peephole rewrite changed.
+    pub fn set_changed(&mut self, ids: BTreeSet<u64>) {
+        self.changed = ids;
+    }
+
+    /// every node follows its sources. Nodes the return reads
+    /// come first, then any nodes it does not.
+    ///
+    /// sources which are not within the graph are included, so
+    /// they may be reported.
+    pub fn topological_order(&self) -> Vec<u64> {
+        fn visit(dump: &Dump, id: u64, seen: &mut BTreeSet<u64>, order: &mut Vec<u64>) {
+            if !seen.insert(id) {
+                return;
+            }
+            for source in dump
+                .nodes
+                .get(&id)
+                .into_iter()
+                .flat_map(|n| n.sources.iter())
+            {
+                visit(dump, *source, seen, order);
+            }
+            order.push(id);
+        }
+
+        let mut seen = BTreeSet::new();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        for id in self.ret.iter().chain(self.nodes.keys()) {
+            visit(self, *id, &mut seen, &mut order);
+        }
+        order
+    }
+}
+
+impl fmt::Display for Dump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let order = self.topological_order();
+        let names: BTreeMap<u64, usize> = order
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (*id, index))
+            .collect();
+        let name = |id: &u64| match names.get(id) {
+            Option::Some(index) => format!("%{}", index),
+            Option::None => "%?".to_string(),
+        };
+        let mut derived = BTreeMap::<u64, Vec<u64>>::new();
+        for id in order.iter() {
+            for source in self
+                .nodes
+                .get(id)
+                .into_iter()
+                .flat_map(|n| n.sources.iter())
+            {
+                let sinks = derived.entry(*source).or_default();
+                if !sinks.contains(id) {
+                    sinks.push(*id);
+                }
+            }
+        }
+        for id in order.iter() {
+            let node = match self.nodes.get(id) {
+                Option::Some(node) => node,
+                Option::None => {
+                    writeln!(f, "{} = <missing>", name(id))?;
+                    continue;
+                }
+            };
+            write!(f, "{}: {} = {}", name(id), node.kind, node.op)?;
+            for (index, source) in node.sources.iter().enumerate() {
+                write!(f, "{}{}", if index == 0 { " " } else { ", " }, name(source))?;
+            }
+            let sinks = match node.sinks {
+                Option::Some(ref sinks) => sinks.as_slice(),
+                Option::None => derived.get(id).map(|v| v.as_slice()).unwrap_or(&[]),
+            };
+            for (index, sink) in sinks.iter().enumerate() {
+                write!(
+                    f,
+                    "{}{}",
+                    if index == 0 { " -> " } else { ", " },
+                    name(sink)
+                )?;
+            }
+            writeln!(f)?;
+        }
+        match self.ret {
+            Option::Some(ref ret) => write!(f, "return {}", name(ret)),
+            Option::None => Ok(()),
+        }
+    }
+}
+
+/// writes graphs as one Graphviz digraph, each graph is a cluster
+/// labelled with its name when there is more than one.
+///
+/// nodes are labelled with their operation & type, edges with the
+/// index of the argument they are. The return is drawn bold, and
+/// changed nodes are filled.
+pub fn to_dot(graphs: &[(&str, &Dump)]) -> String {
+    let mut out = String::with_capacity(4096);
+    out.push_str("digraph program {\n");
+    out.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+    for (name, dump) in graphs.iter() {
+        let indent = if graphs.len() > 1 {
+            out.push_str(&format!(
+                "    subgraph cluster_{} {{\n        label=\"{}\";\n",
+                name,
+                escape(name)
+            ));
+            "        "
+        } else {
+            "    "
+        };
+        dump.write_dot(&mut out, name, indent);
+        if graphs.len() > 1 {
+            out.push_str("    }\n");
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+impl Dump {
+    /// writes each node, then each edge in argument order. Node
+    /// names begin with `prefix` so graphs may share a digraph.
+    fn write_dot(&self, out: &mut String, prefix: &str, indent: &str) {
+        let order = self.topological_order();
+        let names: BTreeMap<u64, usize> = order
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (*id, index))
+            .collect();
+        for id in order.iter() {
+            let mut attrs = match self.nodes.get(id) {
+                Option::Some(node) => vec![format!(
+                    "label=\"%{} {}\\n{}\"",
+                    names[id],
+                    escape(&node.op),
+                    node.kind
+                )],
+                Option::None => vec![
+                    format!("label=\"%{} <missing>\"", names[id]),
+                    format!("color=red"),
+                ],
+            };
+            let mut styles = Vec::new();
+            if self.ret == Some(*id) {
+                styles.push("bold");
+                attrs.push("peripheries=2".to_string());
+            }
+            if self.changed.contains(id) {
+                styles.push("filled");
+                attrs.push("fillcolor=lightyellow".to_string());
+            }
+            if !styles.is_empty() {
+                attrs.push(format!("style=\"{}\"", styles.join(",")));
+            }
+            out.push_str(&format!(
+                "{}{}_{} [{}];\n",
+                indent,
+                prefix,
+                names[id],
+                attrs.join(", ")
+            ));
+        }
+        for id in order.iter() {
+            let sources = self
+                .nodes
+                .get(id)
+                .into_iter()
+                .flat_map(|n| n.sources.iter());
+            for (index, source) in sources.enumerate() {
+                out.push_str(&format!(
+                    "{}{}_{} -> {}_{} [label=\"{}\"];\n",
+                    indent, prefix, names[source], prefix, names[id], index
+                ));
+            }
+        }
+    }
+}
+
+/// escapes a Graphviz string
+fn escape(arg: &str) -> String {
+    arg.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[test]
+fn test_graph_dump() {
+    let mut dump = Dump::default();
+    dump.insert(30, "sum".to_string(), TypeData::Int, vec![20]);
+    dump.insert(20, "roll_d6".to_string(), TypeData::CollectionOfInt, vec![10]);
+    dump.insert(10, "const 2".to_string(), TypeData::Int, vec![]);
+    dump.insert(40, "const 1".to_string(), TypeData::Int, vec![]);
+    dump.insert(50, "add".to_string(), TypeData::Int, vec![40, 60]);
+    dump.set_return(30);
+    let expected = r#"%0: int = const 2 -> %1
+%1: vec<int> = roll_d6 %0 -> %2
+%2: int = sum %1
+%3: int = const 1 -> %5
+%4 = <missing>
+%5: int = add %3, %4
+return %2"#;
+    assert_eq!(dump.to_string(), expected);
+}
+
+#[test]
+fn test_dot() {
+    let mut dump = Dump::default();
+    dump.insert(2, "sum".to_string(), TypeData::Int, vec![1]);
+    dump.insert(1, "roll_d6".to_string(), TypeData::CollectionOfInt, vec![0]);
+    dump.insert(0, "deck [1, 2]".to_string(), TypeData::Int, vec![]);
+    dump.insert(3, "\"add\"".to_string(), TypeData::Int, vec![0, 2]);
+    dump.set_return(3);
+    dump.set_changed(vec![3].into_iter().collect());
+    let expected = r#"digraph program {
+    node [shape=box, fontname="monospace"];
+    g_0 [label="%0 deck [1, 2]\nint"];
+    g_1 [label="%1 roll_d6\nvec<int>"];
+    g_2 [label="%2 sum\nint"];
+    g_3 [label="%3 \"add\"\nint", peripheries=2, fillcolor=lightyellow, style="bold,filled"];
+    g_0 -> g_1 [label="0"];
+    g_1 -> g_2 [label="0"];
+    g_0 -> g_3 [label="0"];
+    g_2 -> g_3 [label="1"];
+}
+"#;
+    assert_eq!(to_dot(&[("g", &dump)]), expected);
+    let both = to_dot(&[("before", &dump), ("after", &dump)]);
+    assert!(both.contains("    subgraph cluster_before {\n        label=\"before\";\n"));
+    assert!(both.contains("        after_2 -> after_3 [label=\"1\"];\n"));
+}
@@ -1,26 +1,351 @@
-use std::fmt::Display;
 use std::fs::OpenOptions;
-use std::io::{stdout, Read, Write};
+use std::io::{Read, Write};
 
-use super::parser_output::AbstractSyntaxTree;
+use super::diagnostic::{render_all, Diagnostic, ErrorFormat};
+use super::parser_output::{
+    annotation, AbstractSyntaxTree, Comment, Expression, FunctionDeclaration, Span, Statement,
+    Structures,
+};
 
-pub fn formatter(path: &str) -> Result<(), String> {
-    /*
+/// formatted source is wrapped at this column
+const WIDTH: usize = 80;
+
+/// the indentation of each nested block
+const INDENT: usize = 4;
+
+/// formats the file at `path`.
+///
+/// the file is rewritten in place, unless `check` is set. Returns
+/// if the file was already formatted.
+pub fn formatter(path: &str, check: bool) -> Result<bool, String> {
     let mut input_file = match OpenOptions::new().read(true).open(path) {
         Ok(input_file) => input_file,
-        Err(e) => return Err(format!("failed to open file={:?} with error={:?}",path, e))
+        Err(e) => return Err(format!("failed to open file={:?} with error={:?}", path, e)),
     };
     let mut file_data = String::with_capacity(4096);
     match input_file.read_to_string(&mut file_data) {
-        Ok(_) => { },
-        Err(e) => return Err(format!("failed to read file={:?} with error={:?}", path, e))
+        Ok(_) => {}
+        Err(e) => return Err(format!("failed to read file={:?} with error={:?}", path, e)),
+    };
+
+    let formatted = match format_source(&file_data) {
+        Ok(formatted) => formatted,
+        Err(e) => return Err(render_all(&e, ErrorFormat::Human, path, &file_data)),
     };
+    if formatted == file_data {
+        return Ok(true);
+    }
+    if !check {
+        let mut output_file = match OpenOptions::new().write(true).truncate(true).open(path) {
+            Ok(output_file) => output_file,
+            Err(e) => return Err(format!("failed to open file={:?} with error={:?}", path, e)),
+        };
+        match output_file.write_all(formatted.as_bytes()) {
+            Ok(()) => {}
+            Err(e) => return Err(format!("failed to write file={:?} with error={:?}", path, e)),
+        };
+    }
+    Ok(false)
+}
+
+/// converts source code into its canonical formatting.
+///
+/// comments & single blank lines between items are preserved,
+/// formatting formatted source returns it unchanged.
+pub fn format_source(source: &str) -> Result<String, Vec<Diagnostic>> {
+    let tree = AbstractSyntaxTree::parse(source)?;
+    let mut elements: Vec<Element> = tree
+        .ast
+        .iter()
+        .map(Element::Item)
+        .chain(tree.comments.iter().map(Element::Comment))
+        .collect();
+    elements.sort_by_key(|element| element.get_span().start);
+
+    let mut output = String::with_capacity(source.len());
+    write_elements(&mut output, source, &elements, 0);
+    Ok(output)
+}
+
+/// formats a single item of a program parsed from `source`,
+/// comments within a function's body are kept.
+pub fn format_item(source: &str, item: &Structures) -> String {
+    let mut output = String::new();
+    write_element(&mut output, source, &Element::Item(item), 0);
+    output
+}
+
+/// Element is anything which starts on its own line
+enum Element<'a, 'b> {
+    Comment(&'b Comment<'a>),
+    Item(&'b Structures<'a>),
+    Stmt(&'b Statement<'a>),
+}
+impl<'a, 'b> Element<'a, 'b> {
+    /// where the element was written, functions end at their
+    /// last statement.
+    fn get_span(&self) -> Span {
+        match self {
+            &Element::Comment(comment) => comment.span,
+            &Element::Item(&Structures::Constant(ref constant)) => constant.span,
+            &Element::Item(&Structures::Analyze(ref analysis)) => analysis.span,
+            &Element::Item(&Structures::Func(ref func)) => {
+                let end = body_elements(func)
+                    .iter()
+                    .map(|element| element.get_span().end)
+                    .max()
+                    .unwrap_or(func.span.end);
+                Span::new(func.span.start, end)
+            }
+            &Element::Stmt(&Statement::Variable(ref var)) => var.span,
+            &Element::Stmt(&Statement::Return(ref term)) => term.span,
+        }
+    }
+
+    fn is_function(&self) -> bool {
+        match self {
+            &Element::Item(&Structures::Func(_)) => true,
+            _ => false,
+        }
+    }
+
+    fn is_comment(&self) -> bool {
+        match self {
+            &Element::Comment(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// the statements & comments of a function, in source order
+fn body_elements<'a, 'b>(func: &'b FunctionDeclaration<'a>) -> Vec<Element<'a, 'b>> {
+    let mut elements: Vec<Element> = func
+        .body
+        .data
+        .iter()
+        .map(Element::Stmt)
+        .chain(func.body.comments.iter().map(Element::Comment))
+        .collect();
+    elements.sort_by_key(|element| element.get_span().start);
+    elements
+}
+
+/// writes a sequence of elements, one per line.
+///
+/// a comment on the same line as the element before it stays
+/// there. Blank lines in the source are kept (but never more than
+/// one), and functions are always surrounded by blank lines.
+fn write_elements(output: &mut String, source: &str, elements: &[Element], indent: usize) {
+    for (index, element) in elements.iter().enumerate() {
+        if index != 0 {
+            let previous = &elements[index - 1];
+            let between = &source[previous.get_span().end..element.get_span().start];
+            if element.is_comment() && !between.contains('\n') {
+                output.push(' ');
+                write_element(output, source, element, indent);
+                continue;
+            }
+            let blank = between.matches('\n').count() > 1
+                || previous.is_function()
+                || (element.is_function() && !previous.is_comment());
+            output.push('\n');
+            if blank {
+                output.push('\n');
+            }
+        }
+        push_indent(output, indent);
+        write_element(output, source, element, indent);
+    }
+    if !elements.is_empty() {
+        output.push('\n');
+    }
+}
+
+fn write_element(output: &mut String, source: &str, element: &Element, indent: usize) {
+    match element {
+        &Element::Comment(comment) => output.push_str(comment.text),
+        &Element::Item(&Structures::Constant(ref constant)) => {
+            write_allow(output, &constant.allow, indent);
+            let head = format!("const {}{} = ", constant.name, annotation(constant.kind));
+            write_terminated(output, &head, &constant.expr, indent);
+        }
+        &Element::Item(&Structures::Analyze(ref analysis)) => {
+            write_allow(output, &analysis.allow, indent);
+            write_terminated(output, "analyze ", &analysis.expr, indent);
+        }
+        &Element::Item(&Structures::Func(ref func)) => {
+            write_allow(output, &func.allow, indent);
+            write_signature(output, func, indent);
+            output.push_str(" {\n");
+            let body = body_elements(func);
+            write_elements(output, source, &body, indent + INDENT);
+            push_indent(output, indent);
+            output.push('}');
+        }
+        &Element::Stmt(&Statement::Variable(ref var)) => {
+            write_allow(output, &var.allow, indent);
+            let head = format!("let {}{} = ", var.name, annotation(var.kind));
+            write_terminated(output, &head, &var.expr, indent);
+        }
+        &Element::Stmt(&Statement::Return(ref term)) => {
+            write_terminated(output, "return ", &term.expr, indent);
+        }
+    }
+}
 
-    let tree = match AbstractSyntaxTree::parse(&file_data) {
-        Ok(tree) => tree,
-        Err(e) => return Err(e),
+/// writes `#[allow(..)]` upon its own line
+fn write_allow(output: &mut String, allow: &[&str], indent: usize) {
+    if !allow.is_empty() {
+        output.push_str(&format!("#[allow({})]\n", allow.join(", ")));
+        push_indent(output, indent);
+    }
+}
+
+fn write_signature(output: &mut String, func: &FunctionDeclaration, indent: usize) {
+    let args: Vec<String> = func
+        .args
+        .iter()
+        .map(|(name, kind)| format!("{}: {}", name, kind))
+        .collect();
+    let flat = format!("fn {}({}) -> {}", func.name, args.join(", "), func.ret);
+    if indent + flat.len() + 2 <= WIDTH || args.is_empty() {
+        output.push_str(&flat);
+        return;
+    }
+    output.push_str(&format!("fn {}(\n", func.name));
+    for arg in args {
+        push_indent(output, indent + INDENT);
+        output.push_str(&arg);
+        output.push_str(",\n");
+    }
+    push_indent(output, indent);
+    output.push_str(&format!(") -> {}", func.ret));
+}
+
+/// writes `head`, the expression, then `;`
+fn write_terminated(output: &mut String, head: &str, expr: &Expression, indent: usize) {
+    output.push_str(head);
+    output.push_str(&format_expression(expr, indent, indent + head.len()));
+    output.push(';');
+}
+
+/// formats an expression which starts at `column`. Expressions
+/// which would pass the line width are split over many lines.
+fn format_expression(expr: &Expression, indent: usize, column: usize) -> String {
+    let flat = flat_expression(expr);
+    // leave room for the `;`, `,` or `)` which follows
+    if column + flat.len() < WIDTH {
+        return flat;
+    }
+    let inner = indent + INDENT;
+    let mut s = String::new();
+    match expr {
+        &Expression::Func(ref func) if !func.args.is_empty() => {
+            s.push_str(func.name);
+            s.push_str("(\n");
+            for arg in func.args.iter() {
+                push_indent(&mut s, inner);
+                s.push_str(&format_expression(arg, inner, inner));
+                s.push_str(",\n");
+            }
+            push_indent(&mut s, indent);
+            s.push(')');
+        }
+        &Expression::Operation(ref op) => {
+            s.push_str("(\n");
+            push_indent(&mut s, inner);
+            s.push_str(&format_expression(&op.left, inner, inner));
+            s.push('\n');
+            push_indent(&mut s, inner);
+            let op_text = format!("{} ", op.op);
+            s.push_str(&op_text);
+            s.push_str(&format_expression(&op.right, inner, inner + op_text.len()));
+            s.push('\n');
+            push_indent(&mut s, indent);
+            s.push(')');
+        }
+        _ => s.push_str(&flat),
     };
-    println!("{}", tree);
-    */
-    Ok(())
+    s
+}
+
+/// formats an expression upon a single line
+fn flat_expression(expr: &Expression) -> String {
+    match expr {
+        &Expression::Literal(ref lit) => lit.lit.to_string(),
+        &Expression::Variable(ref var) => var.name.to_string(),
+        &Expression::Func(ref func) => {
+            let args: Vec<String> = func.args.iter().map(flat_expression).collect();
+            format!("{}({})", func.name, args.join(", "))
+        }
+        &Expression::Operation(ref op) => format!(
+            "({} {} {})",
+            flat_expression(&op.left),
+            op.op,
+            flat_expression(&op.right)
+        ),
+    }
+}
+
+fn push_indent(output: &mut String, indent: usize) {
+    for _ in 0..indent {
+        output.push(' ');
+    }
+}
+
+#[test]
+fn test_format_source() {
+    let messy = r#"// damage of a volley
+const   attacks:int=%d{{ATTACKS}} ;
+const hits : vec<int>=roll_d6( attacks );  // one die per attack
+
+
+#[allow(unused_function)]
+fn   wound( x:int,y:int )->int{
+// strength beats toughness
+let   total:int=( x+y );
+    return (total*2);}
+analyze ( sum(hits)>=wound(1,2) );
+"#;
+    let expected = r#"// damage of a volley
+const attacks: int = %d{{ATTACKS}};
+const hits: vec<int> = roll_d6(attacks); // one die per attack
+
+#[allow(unused_function)]
+fn wound(x: int, y: int) -> int {
+    // strength beats toughness
+    let total: int = (x + y);
+    return (total * 2);
+}
+
+analyze (sum(hits) >= wound(1, 2));
+"#;
+    let formatted = format_source(messy).unwrap();
+    assert_eq!(formatted, expected);
+    assert_eq!(format_source(&formatted).unwrap(), formatted);
+
+    // long expressions wrap at 80 columns, and stay wrapped
+    let long = "const total: int = (sum(roll_d6(%d{{NUMBER_OF_ATTACKS}})) + (sum(roll_d3(%d{{NUMBER_OF_BONUS_DICE}})) * 2));\nanalyze total;\n";
+    let expected = r#"const total: int = (
+    sum(roll_d6(%d{{NUMBER_OF_ATTACKS}}))
+    + (sum(roll_d3(%d{{NUMBER_OF_BONUS_DICE}})) * 2)
+);
+analyze total;
+"#;
+    let formatted = format_source(long).unwrap();
+    assert_eq!(formatted, expected);
+    assert_eq!(format_source(&formatted).unwrap(), formatted);
+    assert!(formatted.lines().all(|line| line.len() <= WIDTH));
+
+    assert!(format_source("analyze (1 + ;").is_err());
+
+    // attributes are kept upon the analysis
+    let allowed = "#[allow(random_length)]\nanalyze sum(roll_d6(sum(roll_d3(1))));\n";
+    assert_eq!(format_source(allowed).unwrap(), allowed);
+
+    // comments within an expression are rejected, rather than dropped
+    let errors = format_source("analyze (1 + // why\n 2);\n").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].get_labels()[0].span.start, 13);
+    assert!(errors[0].get_message().contains("between items & statements"));
 }
@@ -1,6 +1,8 @@
-use super::super::cfgbuilder::{CallStack, ExpressionCollection, HashedExpression};
+use super::super::cfgbuilder::{CallStack, ExpressionCollection};
+use super::super::dump::Dump;
 use std::collections::btree_map::Iter;
 use std::collections::BTreeMap;
+use std::fmt;
 
 use super::expr::InlinedExpression;
 
@@ -16,17 +18,15 @@ impl InlinedCollection {
         let mut coll = InlinedCollection::default();
         let return_expr = match arg.get_return() {
             Option::None => _unreachable_panic!(),
-            Option::Some(ref expr) => {
-                InlinedExpression::new(expr, &mut stack, &mut coll).get_hash()
-            }
+            Option::Some(expr) => InlinedExpression::new(expr, &mut stack, &mut coll).get_hash(),
         };
-        ::std::mem::replace(&mut coll.ret, Some(return_expr));
+        coll.ret.replace(return_expr);
         coll
     }
 
     /// provides the return expression value
     pub fn get_return(&self) -> Option<u64> {
-        self.ret.clone()
+        self.ret
     }
 
     /// returns an expression based on its hashed identifier
@@ -42,6 +42,24 @@ impl InlinedCollection {
     /// inserts a hashed expression, and its InlinedExpression counter part.
     pub fn insert_hash(&mut self, inlined: &InlinedExpression) {
         let inlined_hashed = inlined.get_hash();
-        self.expr.insert(inlined_hashed.clone(), inlined.clone());
+        self.expr.insert(inlined_hashed, inlined.clone());
+    }
+
+    /// the graph as it is written, expressions are numbered in
+    /// the order they are evaluated.
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::default();
+        for (id, expr) in self.expr.iter() {
+            dump.insert(*id, expr.to_string(), expr.get_type(), expr.get_sources());
+        }
+        if let Option::Some(ret) = self.ret {
+            dump.set_return(ret);
+        }
+        dump
+    }
+}
+impl fmt::Display for InlinedCollection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.dump())
     }
 }
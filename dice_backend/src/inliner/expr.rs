@@ -1,10 +1,10 @@
-#[allow(non_camel_case_types)]
+use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::str::FromStr;
 
-use super::super::cfgbuilder::{CallStack, ExpressionCollection, HashedExpression, Identifier};
-use super::super::parser_output::{Literal, Operation, TypeData};
+use super::super::cfgbuilder::{CallStack, HashedExpression, Identifier};
+use super::super::parser_output::{Literal, Operation, Rational, TypeData};
 use super::super::seahash::SeaHasher;
+use super::super::stdlib::StdFunc;
 
 use super::coll::InlinedCollection;
 
@@ -41,6 +41,50 @@ impl Op {
             &Op::And(ref a) => a.get_args(),
         }
     }
+
+    /// the type the operation results in, it is a collection when
+    /// either argument is.
+    pub fn get_type(&self) -> TypeData {
+        match self {
+            &Op::Add(ref a) | &Op::Sub(ref a) | &Op::Mul(ref a) | &Op::Div(ref a) => {
+                if a.is_collection() {
+                    TypeData::CollectionOfInt
+                } else {
+                    TypeData::Int
+                }
+            }
+            &Op::GreaterThan(ref a)
+            | &Op::GreaterThanEqual(ref a)
+            | &Op::LessThan(ref a)
+            | &Op::LessThanEqual(ref a)
+                if a.is_collection() =>
+            {
+                TypeData::CollectionOfBool
+            }
+            &Op::Equal(ref a) | &Op::NotEqual(ref a) if a.is_collection() => {
+                TypeData::CollectionOfBool
+            }
+            &Op::Or(ref a) | &Op::And(ref a) if a.is_collection() => TypeData::CollectionOfBool,
+            _ => TypeData::Bool,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            &Op::Add(_) => "add",
+            &Op::Sub(_) => "sub",
+            &Op::Mul(_) => "mul",
+            &Op::Div(_) => "div",
+            &Op::Equal(_) => "eq",
+            &Op::NotEqual(_) => "ne",
+            &Op::GreaterThan(_) => "gt",
+            &Op::GreaterThanEqual(_) => "ge",
+            &Op::LessThan(_) => "lt",
+            &Op::LessThanEqual(_) => "le",
+            &Op::Or(_) => "or",
+            &Op::And(_) => "and",
+        }
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
@@ -56,8 +100,17 @@ impl BoolOrInt {
             Self::Int(ref x) => x.get_args(),
         }
     }
+
+    pub fn is_collection(&self) -> bool {
+        match self {
+            Self::Bool(ref b) => b.is_collection(),
+            Self::Int(ref x) => x.is_collection(),
+        }
+    }
 }
 
+/// variants are named for the type of each argument
+#[allow(non_camel_case_types)]
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum IntArg {
     Int_Int(u64, u64),
@@ -73,8 +126,17 @@ impl IntArg {
             Self::CollectionOfInt_Int(ref a, ref b) => (*a, *b),
         }
     }
+
+    /// is either argument a collection
+    pub fn is_collection(&self) -> bool {
+        match self {
+            Self::Int_Int(_, _) => false,
+            _ => true,
+        }
+    }
 }
 
+#[allow(non_camel_case_types)]
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum BoolArg {
     Bool_Bool(u64, u64),
@@ -90,42 +152,66 @@ impl BoolArg {
             Self::CollectionOfBool_Bool(ref a, ref b) => (*a, *b),
         }
     }
+
+    /// is either argument a collection
+    pub fn is_collection(&self) -> bool {
+        match self {
+            Self::Bool_Bool(_, _) => false,
+            _ => true,
+        }
+    }
 }
 
 /// Inlined Expression contains the very base values
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum InlinedExpression {
-    /// standard library function roll_d6
-    D6(u64),
-
-    /// standard library function roll_d3
-    D3(u64),
-
-    /// standard library function filter
-    Filter(u64, u64),
+    /// a standard library function, its arguments, and the site
+    /// which invoked it when the function is random
+    StdLib(StdFunc, Box<[u64]>, u64),
 
-    /// standard library function count
-    Count(u64),
+    /// a deck of cards, and the site which declared it
+    Deck(Box<[i8]>, u64),
 
-    /// length of a collection
-    Len(u64),
+    /// a boolean which is `true` with the given probability, and
+    /// the site which declared it
+    Bernoulli(Rational, u64),
 
-    /// join 2 collections
-    Join(u64, u64),
+    /// an integer drawn from weighted values, and the site which
+    /// declared it
+    Categorical(Box<[(i8, Rational)]>, u64),
 
-    /// sum the items in a collection
-    Sum(u64),
-
-    /// return the maximum in a collection
-    Max(u64),
-
-    /// return the minimum in a collection
-    Min(u64),
+    /// a parameter, whose value is bound when the program is evaluated
+    Parameter(String, TypeData),
 
     ConstantInt(i8),
     ConstantBool(bool),
     Op(Op),
 }
+impl fmt::Display for InlinedExpression {
+    /// writes the operation & any immediate values, arguments
+    /// are written by the collection.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            &InlinedExpression::StdLib(ref func, _, _) => write!(f, "{}", func.name()),
+            &InlinedExpression::Deck(ref cards, _) => write!(f, "deck {:?}", cards),
+            &InlinedExpression::Bernoulli(ref prob, _) => write!(f, "bernoulli {}", prob),
+            &InlinedExpression::Categorical(ref weights, _) => {
+                write!(f, "categorical {{")?;
+                for (index, (value, weight)) in weights.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", value, weight)?;
+                }
+                write!(f, "}}")
+            }
+            &InlinedExpression::Parameter(ref name, _) => write!(f, "param {}", name),
+            &InlinedExpression::ConstantInt(ref i) => write!(f, "const {}", i),
+            &InlinedExpression::ConstantBool(ref b) => write!(f, "const {}", b),
+            &InlinedExpression::Op(ref op) => write!(f, "{}", op.get_name()),
+        }
+    }
+}
 impl<'a> InlinedExpression {
     /// returns the hash of the expression
     pub fn get_hash(&self) -> u64 {
@@ -133,6 +219,35 @@ impl<'a> InlinedExpression {
         self.hash(&mut hasher);
         hasher.finish()
     }
+
+    /// returns the expressions this expression reads, in argument order
+    pub fn get_sources(&self) -> Vec<u64> {
+        match self {
+            &InlinedExpression::StdLib(_, ref args, _) => args.to_vec(),
+            &InlinedExpression::Op(ref op) => {
+                let (a, b) = op.get_args();
+                vec![a, b]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// returns the type of the expression's result
+    pub fn get_type(&self) -> TypeData {
+        match self {
+            &InlinedExpression::StdLib(ref func, _, _) => func.ret(),
+            &InlinedExpression::Deck(_, _) => TypeData::Deck,
+            &InlinedExpression::Bernoulli(_, _) | &InlinedExpression::ConstantBool(_) => {
+                TypeData::Bool
+            }
+            &InlinedExpression::Categorical(_, _) | &InlinedExpression::ConstantInt(_) => {
+                TypeData::Int
+            }
+            &InlinedExpression::Parameter(_, kind) => kind,
+            &InlinedExpression::Op(ref op) => op.get_type(),
+        }
+    }
+
     /// Build an inlined expression from context
     pub fn new<'b>(
         expr: &'b HashedExpression<'a>,
@@ -141,33 +256,28 @@ impl<'a> InlinedExpression {
     ) -> InlinedExpression {
         let hash = expr.get_hash();
         let output = match expr {
-            &HashedExpression::ConstantValue(Literal::EnvirBool(ref envir_name), _) => {
-                let b = ::std::env::vars()
-                    .filter(|(name, _)| envir_name == name)
-                    .flat_map(|(_, var)| bool::from_str(&var).ok())
-                    .next()
-                    .expect(&format!(
-                        "could not fine value {} in environment",
-                        envir_name
-                    ));
+            &HashedExpression::ConstantValue(Literal::Number(i), _, _) => {
+                InlinedExpression::ConstantInt(i)
+            }
+            &HashedExpression::ConstantValue(Literal::Boolean(b), _, _) => {
                 InlinedExpression::ConstantBool(b)
             }
-            &HashedExpression::ConstantValue(Literal::EnvirNumber(ref envir_name), _) => {
-                let i = ::std::env::vars()
-                    .filter(|(name, _)| envir_name == name)
-                    .flat_map(|(_, var)| i8::from_str(&var).ok())
-                    .next()
-                    .expect(&format!(
-                        "could not fine value {} in environment",
-                        envir_name
-                    ));
-                InlinedExpression::ConstantInt(i)
+            &HashedExpression::ConstantValue(Literal::Bernoulli(ref prob), _, _) => {
+                InlinedExpression::Bernoulli(*prob, stack.get_site(&hash))
             }
-            &HashedExpression::ConstantValue(Literal::Number(i), _) => {
-                InlinedExpression::ConstantInt(i as i8)
+            &HashedExpression::ConstantValue(Literal::Categorical(ref weights), _, _) => {
+                InlinedExpression::Categorical(weights.clone(), stack.get_site(&hash))
             }
-            &HashedExpression::ConstantValue(Literal::Boolean(b), _) => {
-                InlinedExpression::ConstantBool(b)
+            &HashedExpression::ConstantValue(Literal::Deck(ref cards), _, _) => {
+                InlinedExpression::Deck(cards.clone(), stack.get_site(&hash))
+            }
+            &HashedExpression::ConstantValue(Literal::EnvirBool(ref name), _, _)
+            | &HashedExpression::ConstantValue(Literal::EnvirNumber(ref name), _, _) => {
+                InlinedExpression::Parameter(name.to_string(), expr.get_type())
+            }
+            &HashedExpression::ConstantValue(Literal::LoadDistribution(_), _, _) => {
+                // the namespace resolves this, & replaces it
+                _unreachable_panic!()
             }
             &HashedExpression::ExternalConstant(ref id, _) | &HashedExpression::Var(ref id, _) => {
                 // resolve the expression that defines the variable
@@ -178,7 +288,7 @@ impl<'a> InlinedExpression {
                 // compose a function argument into
                 InlinedExpression::func_arg(index, stack, coll)
             }
-            &HashedExpression::Func(ref id, ref args, ref kind) => {
+            &HashedExpression::Func(ref id, ref args, ref kind, _) => {
                 InlinedExpression::func(id, args.as_ref(), &hash, stack, coll, kind)
             }
             &HashedExpression::Op(ref left_hashed, op, ref right_hashed, out) => {
@@ -187,61 +297,73 @@ impl<'a> InlinedExpression {
                     InlinedExpression::new(stack.get_expr(left_hashed).unwrap(), stack, coll);
                 let right =
                     InlinedExpression::new(stack.get_expr(right_hashed).unwrap(), stack, coll);
-                match (left, right) {
-                    (InlinedExpression::ConstantBool(l), InlinedExpression::ConstantBool(r)) => {
+                // operations which would fail, such as division by zero
+                // or overflow, are left for the runtime to report
+                let folded = match (&left, &right) {
+                    (&InlinedExpression::ConstantBool(l), &InlinedExpression::ConstantBool(r)) => {
                         match (out, op) {
                             (TypeData::Bool, Operation::And) => {
-                                InlinedExpression::ConstantBool(l & r)
+                                Some(InlinedExpression::ConstantBool(l & r))
                             }
                             (TypeData::Bool, Operation::Or) => {
-                                InlinedExpression::ConstantBool(l | r)
+                                Some(InlinedExpression::ConstantBool(l | r))
                             }
-                            _ => panic!("other boolean expressions are not possible"),
+                            (TypeData::Bool, Operation::Equal) => {
+                                Some(InlinedExpression::ConstantBool(l == r))
+                            }
+                            (TypeData::Bool, Operation::NotEqual) => {
+                                Some(InlinedExpression::ConstantBool(l != r))
+                            }
+                            _ => None,
                         }
                     }
                     (
-                        InlinedExpression::ConstantInt(left),
-                        InlinedExpression::ConstantInt(right),
+                        &InlinedExpression::ConstantInt(l),
+                        &InlinedExpression::ConstantInt(r),
                     ) => match (out, op) {
                         (TypeData::Int, Operation::Add) => {
-                            InlinedExpression::ConstantInt(left + right)
+                            l.checked_add(r).map(InlinedExpression::ConstantInt)
                         }
                         (TypeData::Int, Operation::Sub) => {
-                            InlinedExpression::ConstantInt(left - right)
+                            l.checked_sub(r).map(InlinedExpression::ConstantInt)
                         }
                         (TypeData::Int, Operation::Mul) => {
-                            InlinedExpression::ConstantInt(left * right)
+                            l.checked_mul(r).map(InlinedExpression::ConstantInt)
                         }
                         (TypeData::Int, Operation::Div) => {
-                            InlinedExpression::ConstantInt(left / right)
+                            l.checked_div(r).map(InlinedExpression::ConstantInt)
                         }
                         (TypeData::Int, Operation::Or) => {
-                            InlinedExpression::ConstantInt(left | right)
+                            Some(InlinedExpression::ConstantInt(l | r))
                         }
                         (TypeData::Int, Operation::And) => {
-                            InlinedExpression::ConstantInt(left & right)
+                            Some(InlinedExpression::ConstantInt(l & r))
                         }
                         (TypeData::Bool, Operation::Equal) => {
-                            InlinedExpression::ConstantBool(left == right)
+                            Some(InlinedExpression::ConstantBool(l == r))
                         }
                         (TypeData::Bool, Operation::NotEqual) => {
-                            InlinedExpression::ConstantBool(left != right)
+                            Some(InlinedExpression::ConstantBool(l != r))
                         }
                         (TypeData::Bool, Operation::GreaterThan) => {
-                            InlinedExpression::ConstantBool(left > right)
+                            Some(InlinedExpression::ConstantBool(l > r))
                         }
                         (TypeData::Bool, Operation::LessThan) => {
-                            InlinedExpression::ConstantBool(left < right)
+                            Some(InlinedExpression::ConstantBool(l < r))
                         }
                         (TypeData::Bool, Operation::GreaterThanEqual) => {
-                            InlinedExpression::ConstantBool(left >= right)
+                            Some(InlinedExpression::ConstantBool(l >= r))
                         }
                         (TypeData::Bool, Operation::LessThanEqual) => {
-                            InlinedExpression::ConstantBool(left <= right)
+                            Some(InlinedExpression::ConstantBool(l <= r))
                         }
-                        _ => panic!("illegal interger operation"),
+                        _ => None,
                     },
-                    (left, right) => {
+                    _ => None,
+                };
+                match folded {
+                    Option::Some(folded) => folded,
+                    Option::None => {
                         let left_kind = stack.get_expr(left_hashed).unwrap().get_type();
                         let right_kind = stack.get_expr(right_hashed).unwrap().get_type();
                         InlinedExpression::build_inline_op(
@@ -270,12 +392,15 @@ impl<'a> InlinedExpression {
         stack: &mut CallStack<'a, 'b>,
         coll: &mut InlinedCollection,
     ) -> InlinedExpression {
-        stack
+        stack.push_binding(id);
+        let output = stack
             .get_var(id)
             .into_iter()
             .map(|expr| InlinedExpression::new(expr, stack, coll))
             .next()
-            .unwrap()
+            .unwrap();
+        stack.pop_binding();
+        output
     }
 
     #[inline(always)]
@@ -284,13 +409,11 @@ impl<'a> InlinedExpression {
         stack: &mut CallStack<'a, 'b>,
         coll: &mut InlinedCollection,
     ) -> InlinedExpression {
-        let context = stack.get_context().unwrap();
-        let func_expr = stack.get_ctx_expr().unwrap();
+        // arguments are resolved within the caller's context
         let arg_expr = stack.get_arg_index(*arg_index).unwrap();
-        stack.pop();
-        let out = InlinedExpression::new(stack.get_expr(&arg_expr).unwrap(), stack, coll);
-        stack.push(&context, &func_expr);
-        out
+        let mut caller = stack.clone();
+        caller.pop();
+        InlinedExpression::new(caller.get_expr(&arg_expr).unwrap(), &mut caller, coll)
     }
 
     #[inline(always)]
@@ -302,100 +425,24 @@ impl<'a> InlinedExpression {
         coll: &mut InlinedCollection,
         kind: &TypeData,
     ) -> InlinedExpression {
-        if stack.is_stdlib(id) {
-            match stack.get_function_name(id).unwrap() {
-                "max" => {
-                    debug_assert_eq!(*kind, TypeData::Int);
-                    debug_assert_eq!(args.len(), 1);
-                    let expr = stack.get_expr(&args[0]).unwrap();
-                    debug_assert_eq!(expr.get_type(), TypeData::CollectionOfInt);
-                    let arg = InlinedExpression::new(expr, stack, coll);
-                    InlinedExpression::Max(arg.get_hash())
-                }
-                "min" => {
-                    debug_assert_eq!(*kind, TypeData::Int);
-                    debug_assert_eq!(args.len(), 1);
-                    let expr = stack.get_expr(&args[0]).unwrap();
-                    debug_assert_eq!(expr.get_type(), TypeData::CollectionOfInt);
-                    let arg = InlinedExpression::new(expr, stack, coll);
-                    InlinedExpression::Min(arg.get_hash())
-                }
-                "roll_d6" => {
-                    debug_assert_eq!(*kind, TypeData::CollectionOfInt);
-                    debug_assert_eq!(args.len(), 1);
-                    let expr = stack.get_expr(&args[0]).unwrap();
-                    debug_assert_eq!(expr.get_type(), TypeData::Int);
-                    let arg = InlinedExpression::new(expr, stack, coll);
-                    InlinedExpression::D6(arg.get_hash())
-                }
-                "roll_d3" => {
-                    debug_assert_eq!(*kind, TypeData::CollectionOfInt);
-                    debug_assert_eq!(args.len(), 1);
-                    let expr = stack.get_expr(&args[0]).unwrap();
-                    debug_assert_eq!(expr.get_type(), TypeData::Int);
-                    let arg = InlinedExpression::new(expr, stack, coll);
-                    InlinedExpression::D3(arg.get_hash())
-                }
-                "filter" => {
-                    debug_assert_eq!(*kind, TypeData::CollectionOfInt);
-                    debug_assert_eq!(args.len(), 2);
-
-                    // filter arg 1
-                    let expr1 = stack.get_expr(&args[0]).unwrap();
-                    debug_assert_eq!(expr1.get_type(), TypeData::CollectionOfBool);
-                    let arg1 = InlinedExpression::new(expr1, stack, coll);
-
-                    // filter arg 2
-                    let expr2 = stack.get_expr(&args[1]).unwrap();
-                    debug_assert_eq!(expr2.get_type(), TypeData::CollectionOfInt);
-                    let arg2 = InlinedExpression::new(expr2, stack, coll);
-
-                    InlinedExpression::Filter(arg1.get_hash(), arg2.get_hash())
-                }
-                "count" => {
-                    debug_assert_eq!(*kind, TypeData::Int);
-                    debug_assert_eq!(args.len(), 1);
-                    let expr = stack.get_expr(&args[0]).unwrap();
-                    debug_assert_eq!(expr.get_type(), TypeData::CollectionOfBool);
-                    let arg = InlinedExpression::new(expr, stack, coll);
-                    InlinedExpression::Count(arg.get_hash())
-                }
-                "len" => {
-                    debug_assert_eq!(*kind, TypeData::Int);
-                    debug_assert_eq!(args.len(), 1);
-                    let expr = stack.get_expr(&args[0]).unwrap();
-                    debug_assert_eq!(expr.get_type(), TypeData::CollectionOfInt);
-                    let arg = InlinedExpression::new(expr, stack, coll);
-                    InlinedExpression::Len(arg.get_hash())
-                }
-                "join" => {
-                    debug_assert_eq!(*kind, TypeData::CollectionOfInt);
-                    debug_assert_eq!(args.len(), 2);
-
-                    // join arg 1
-                    let expr1 = stack.get_expr(&args[0]).unwrap();
-                    debug_assert_eq!(expr1.get_type(), TypeData::CollectionOfInt);
-                    let arg1 = InlinedExpression::new(expr1, stack, coll);
-
-                    // join arg 2
-                    let expr2 = stack.get_expr(&args[1]).unwrap();
-                    debug_assert_eq!(expr2.get_type(), TypeData::CollectionOfInt);
-                    let arg2 = InlinedExpression::new(expr2, stack, coll);
-
-                    InlinedExpression::Join(arg1.get_hash(), arg2.get_hash())
-                }
-                "sum" => {
-                    debug_assert_eq!(*kind, TypeData::Int);
-                    debug_assert_eq!(args.len(), 1);
-                    let expr = stack.get_expr(&args[0]).unwrap();
-                    debug_assert_eq!(expr.get_type(), TypeData::CollectionOfInt);
-                    let arg = InlinedExpression::new(expr, stack, coll);
-                    InlinedExpression::Sum(arg.get_hash())
-                }
-                _ => panic!("item is not a part of the standard library"),
-            }
+        if let Option::Some(func) = stack.get_std_function(id) {
+            debug_assert_eq!(*kind, func.ret());
+            debug_assert_eq!(args.len(), func.args().len());
+            let mut arg_hashes = Vec::with_capacity(args.len());
+            for (arg, &(_, arg_kind)) in args.iter().zip(func.args()) {
+                let expr = stack.get_expr(arg).unwrap();
+                debug_assert_eq!(expr.get_type(), arg_kind);
+                arg_hashes.push(InlinedExpression::new(expr, stack, coll).get_hash());
+            }
+            // each invocation of a random function is distinct
+            let site = if func.is_random() || func.consumes() {
+                stack.get_site(hash)
+            } else {
+                0
+            };
+            InlinedExpression::StdLib(func, arg_hashes.into_boxed_slice(), site)
         } else {
-            stack.push(id, &hash);
+            stack.push(id, hash);
             let output = stack
                 .get_return()
                 .into_iter()
@@ -5,9 +5,9 @@ pub use self::expr::{BoolArg, BoolOrInt, InlinedExpression, IntArg, Op};
 
 #[test]
 fn test_trivial_program() {
-    use super::cfgbuilder::{ExpressionCollection, HashedExpression};
+    use super::cfgbuilder::ExpressionCollection;
     use super::namespace::Namespace;
-    use super::parser_output::{AbstractSyntaxTree, Literal, Operation, TypeData};
+    use super::parser_output::AbstractSyntaxTree;
 
     let trivial_program = r#"
 const A: int = 5;
@@ -44,9 +44,9 @@ analyze ((A + B) + C);
 
 #[test]
 fn test_inlining_proper() {
-    use super::cfgbuilder::{ExpressionCollection, HashedExpression};
+    use super::cfgbuilder::ExpressionCollection;
     use super::namespace::Namespace;
-    use super::parser_output::{AbstractSyntaxTree, Literal, Operation, TypeData};
+    use super::parser_output::AbstractSyntaxTree;
 
     let trivial_program = r#"
 fn lol_add(x: int, y: int) -> int {
@@ -78,16 +78,16 @@ analyze lol_add(5,lol_add(5,5));
     match coll.get_expr(&return_id) {
         Option::Some(InlinedExpression::ConstantInt(35)) => {}
         anything_else => {
-            panic!("expected a constant value, found '{:?}'");
+            panic!("expected a constant value, found '{:?}'", anything_else);
         }
     };
 }
 
 #[test]
 fn complex_inling() {
-    use super::cfgbuilder::{ExpressionCollection, HashedExpression};
+    use super::cfgbuilder::ExpressionCollection;
     use super::namespace::Namespace;
-    use super::parser_output::{AbstractSyntaxTree, Literal, Operation, TypeData};
+    use super::parser_output::AbstractSyntaxTree;
 
     let trivial_program = r#"
 
@@ -133,3 +133,35 @@ analyze lol_add(4,0);
         }
     };
 }
+
+#[test]
+fn test_failing_operations_are_not_folded() {
+    use super::runtime::create_report;
+
+    // arguments only become constant once the function is inlined
+    let program = |body: &str| {
+        format!(
+            "fn f(x: int, b: bool) -> bool {{\n    return {};\n}}\nanalyze f(100, false);",
+            body
+        )
+    };
+    let errors = [
+        ("((x / 0) == 1)", "division by zero"),
+        ("((x + x) == 1)", "overflow"),
+        ("((x * x) == 1)", "overflow"),
+        ("(((0 - x) - x) == 1)", "overflow"),
+        ("(((-128 + (x - x)) / -1) == 1)", "overflow"),
+    ];
+    for &(body, error) in errors.iter() {
+        match create_report(&program(body)) {
+            Ok(_) => panic!("{} should not evaluate", body),
+            Err(e) => assert!(e.contains(error), "{}", e),
+        };
+    }
+
+    // operations which succeed are still folded
+    assert!(create_report(&program("((x / 3) == 33)")).is_ok());
+    assert!(create_report(&program("(b == false)")).is_ok());
+    assert!(create_report("analyze (1 / 0);").is_err());
+    assert!(create_report("analyze (100 + 100);").is_err());
+}
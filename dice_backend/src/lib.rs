@@ -0,0 +1,68 @@
+//! dice_backend computes the exact distribution of dice programs.
+//!
+//! Source code is checked with `compile`, the resulting `Program`
+//! may then be evaluated with any parameter bindings. The items
+//! exported from the crate root are the stable interface.
+//!
+//! Each stage of the compiler is exposed as a module, for tools
+//! which need the intermediate representations. These follow the
+//! compiler, and change with it.
+// the crate matches upon references, `&Expr::Op(ref op)`, names
+// lifetimes & fields in full, and uses `match` where another
+// arm may later be added.
+#![allow(
+    clippy::match_ref_pats,
+    clippy::needless_borrowed_reference,
+    clippy::needless_lifetimes,
+    clippy::redundant_field_names,
+    clippy::redundant_static_lifetimes,
+    clippy::single_match,
+    clippy::match_like_matches_macro,
+    clippy::module_inception,
+    clippy::new_ret_no_self,
+    clippy::too_many_arguments
+)]
+
+extern crate smallvec;
+#[macro_use]
+extern crate lazy_static;
+extern crate itertools;
+extern crate lalrpop_util;
+extern crate rand;
+extern crate regex;
+extern crate seahash;
+#[macro_use]
+extern crate serde_json;
+
+#[macro_use]
+mod macros;
+
+mod artifact;
+pub mod cfgbuilder;
+pub mod diagnostic;
+pub mod doc;
+pub mod dump;
+pub mod formatter;
+pub mod inliner;
+pub mod lsp;
+pub mod namespace;
+pub mod ordering;
+pub mod parser_output;
+pub mod peephole;
+mod program;
+pub mod repl;
+pub mod runtime;
+mod seahasher;
+pub mod stdlib;
+mod syntaxhelper;
+// generated by lalrpop from value.lalrpop
+#[allow(clippy::all, dead_code, non_snake_case, unused_imports, unused_parens)]
+mod value;
+
+pub use self::artifact::Artifact;
+pub use self::diagnostic::{render_all, Code, Diagnostic, ErrorFormat, Severity};
+pub use self::namespace::Bindings;
+pub use self::parser_output::{Rational, TypeData};
+pub use self::program::{check, compile, read_path, CompileOptions, Diagnostics, Program, Stage};
+pub use self::runtime::{Datum, Element, Report, Sweep, SweepResults};
+pub use self::stdlib::{Lowering, Registry, StdFunction};
@@ -0,0 +1,458 @@
+use std::path::Path;
+
+use super::super::namespace::{BasicBlock, Bindings, BlockExpression, Namespace};
+use super::super::parser_output::{
+    AbstractSyntaxTree, ConstantDeclaration, Expression, FunctionDeclaration, Span, Statement,
+    Structures, TypeData, VariableDeclaration,
+};
+use super::super::stdlib::Registry;
+
+/// Declaration is anything a name can refer to
+#[derive(Clone, Copy)]
+pub enum Declaration<'a, 'b> {
+    Constant(&'b ConstantDeclaration<'a>),
+    Function(&'b FunctionDeclaration<'a>),
+    /// the function, and the `let` binding within it
+    Variable(&'b FunctionDeclaration<'a>, &'b VariableDeclaration<'a>),
+    /// the function, and the index of the parameter
+    Parameter(&'b FunctionDeclaration<'a>, usize),
+}
+impl<'a, 'b> Declaration<'a, 'b> {
+    /// the declaration as it would be written, such as
+    /// `fn sum(collection_to_sum_with: vec<int>) -> int`. Types
+    /// which are not annotated are taken from the namespace.
+    pub fn get_signature(&self, namespace: Option<&Namespace>) -> String {
+        match self {
+            &Declaration::Constant(constant) => {
+                constant.get_signature(namespace.and_then(|n| n.get_constant_type(constant.name)))
+            }
+            &Declaration::Function(func) => func.get_signature(),
+            &Declaration::Variable(func, var) => {
+                let inferred = namespace
+                    .and_then(|n| n.get_function_block(func.name))
+                    .and_then(|block| block.get_var(var.name))
+                    .map(|expr| expr.get_kind());
+                match var.kind.or(inferred) {
+                    Option::Some(kind) => format!("let {}: {}", var.name, kind),
+                    Option::None => format!("let {}", var.name),
+                }
+            }
+            &Declaration::Parameter(func, index) => {
+                let (name, kind) = func.args[index];
+                format!("{}: {} (parameter of {})", name, kind, func.name)
+            }
+        }
+    }
+
+    /// the `///` documentation, which is empty for `let` bindings
+    /// & parameters.
+    pub fn get_doc(&self) -> String {
+        match self {
+            &Declaration::Constant(constant) => constant.get_doc(),
+            &Declaration::Function(func) => func.get_doc(),
+            _ => String::new(),
+        }
+    }
+
+    /// the text shown when hovering, the signature & documentation
+    pub fn get_hover(&self, namespace: Option<&Namespace>) -> String {
+        let signature = code_block(&self.get_signature(namespace));
+        let doc = self.get_doc();
+        if doc.is_empty() {
+            signature
+        } else {
+            format!("{}\n\n{}", signature, doc)
+        }
+    }
+
+    /// where the declaration's name is written, standard library
+    /// functions are not written anywhere.
+    pub fn get_name_span(&self, source: &str) -> Option<Span> {
+        match self {
+            &Declaration::Constant(constant) => {
+                Some(name_span(source, constant.span, constant.name))
+            }
+            &Declaration::Function(func) if func.stdlib => None,
+            &Declaration::Function(func) => Some(name_span(source, func.span, func.name)),
+            &Declaration::Variable(_, var) => Some(name_span(source, var.span, var.name)),
+            &Declaration::Parameter(func, index) => {
+                parameter_spans(source, func).get(index).cloned()
+            }
+        }
+    }
+}
+
+/// Symbol is the name under the cursor
+pub struct Symbol<'a, 'b> {
+    /// where the name is written
+    pub span: Span,
+    /// what the name refers to, `None` for unknown names
+    pub declaration: Option<Declaration<'a, 'b>>,
+}
+
+/// finds the name written at `offset`, and what it refers to
+pub fn symbol_at<'a, 'b>(
+    source: &str,
+    tree: &'b AbstractSyntaxTree<'a>,
+    stdlib: &'b Namespace<'static>,
+    offset: usize,
+) -> Option<Symbol<'a, 'b>> {
+    for item in tree.ast.iter() {
+        let found = match item {
+            &Structures::Constant(ref constant) => {
+                let span = name_span(source, constant.span, constant.name);
+                if contains(span, offset) {
+                    return Some(Symbol {
+                        span,
+                        declaration: Some(Declaration::Constant(constant)),
+                    });
+                }
+                expression_at(&constant.expr, offset).map(|found| (found, None))
+            }
+            &Structures::Analyze(ref analysis) => {
+                expression_at(&analysis.expr, offset).map(|found| (found, None))
+            }
+            &Structures::Func(ref func) => {
+                let span = name_span(source, func.span, func.name);
+                if contains(span, offset) {
+                    return Some(Symbol {
+                        span,
+                        declaration: Some(Declaration::Function(func)),
+                    });
+                }
+                let params = parameter_spans(source, func);
+                match params.iter().position(|span| contains(*span, offset)) {
+                    Option::Some(index) => {
+                        return Some(Symbol {
+                            span: params[index],
+                            declaration: Some(Declaration::Parameter(func, index)),
+                        })
+                    }
+                    Option::None => {}
+                };
+                let mut found = None;
+                for stmt in func.body.data.iter() {
+                    found = match stmt {
+                        &Statement::Variable(ref var) => {
+                            let span = name_span(source, var.span, var.name);
+                            if contains(span, offset) {
+                                return Some(Symbol {
+                                    span,
+                                    declaration: Some(Declaration::Variable(func, var)),
+                                });
+                            }
+                            expression_at(&var.expr, offset)
+                        }
+                        &Statement::Return(ref term) => expression_at(&term.expr, offset),
+                    };
+                    if found.is_some() {
+                        break;
+                    }
+                }
+                found.map(|found| (found, Some(func)))
+            }
+        };
+        match found {
+            Option::Some(((name, span, is_function), scope)) => {
+                let declaration = if is_function {
+                    lookup_function(tree, stdlib, name)
+                } else {
+                    lookup_value(tree, scope, name)
+                };
+                return Some(Symbol { span, declaration });
+            }
+            Option::None => {}
+        };
+    }
+    None
+}
+
+/// resolves a function name, user functions are found before the
+/// standard library.
+pub fn lookup_function<'a, 'b>(
+    tree: &'b AbstractSyntaxTree<'a>,
+    stdlib: &'b Namespace<'static>,
+    name: &str,
+) -> Option<Declaration<'a, 'b>> {
+    tree.ast
+        .iter()
+        .filter_map(Structures::to_func)
+        .find(|func| func.name == name)
+        .or_else(|| stdlib.get_function(name))
+        .map(Declaration::Function)
+}
+
+/// resolves a variable name, in the same order as the namespace:
+/// `let` bindings shadow constants, which shadow parameters.
+fn lookup_value<'a, 'b>(
+    tree: &'b AbstractSyntaxTree<'a>,
+    scope: Option<&'b FunctionDeclaration<'a>>,
+    name: &str,
+) -> Option<Declaration<'a, 'b>> {
+    let var = scope.and_then(|func| {
+        func.body.data.iter().find_map(|stmt| match stmt {
+            &Statement::Variable(ref var) if var.name == name => {
+                Some(Declaration::Variable(func, var))
+            }
+            _ => None,
+        })
+    });
+    let constant = || {
+        tree.ast
+            .iter()
+            .filter_map(Structures::to_const)
+            .find(|constant| constant.name == name)
+            .map(Declaration::Constant)
+    };
+    let param = || {
+        scope.and_then(|func| {
+            func.args
+                .iter()
+                .position(|(arg, _)| *arg == name)
+                .map(|index| Declaration::Parameter(func, index))
+        })
+    };
+    var.or_else(constant).or_else(param)
+}
+
+/// the innermost name within an expression, returns the name,
+/// where it is written & if it names a function.
+fn expression_at<'a>(expr: &Expression<'a>, offset: usize) -> Option<(&'a str, Span, bool)> {
+    if !contains(expr.get_span(), offset) {
+        return None;
+    }
+    match expr {
+        &Expression::Variable(ref var) => Some((var.name, var.span, false)),
+        &Expression::Func(ref func) => {
+            let span = Span::new(func.span.start, func.span.start + func.name.len());
+            if contains(span, offset) {
+                return Some((func.name, span, true));
+            }
+            func.args
+                .iter()
+                .filter_map(|arg| expression_at(arg, offset))
+                .next()
+        }
+        &Expression::Operation(ref op) => {
+            expression_at(&op.left, offset).or_else(|| expression_at(&op.right, offset))
+        }
+        &Expression::Literal(_) => None,
+    }
+}
+
+/// the type of the innermost expression at `offset`
+pub fn type_at(namespace: &Namespace, offset: usize) -> Option<(Span, TypeData)> {
+    fn visit(expr: &BlockExpression, offset: usize, best: &mut Option<(Span, TypeData)>) {
+        let span = expr.get_span();
+        if !contains(span, offset) {
+            return;
+        }
+        let narrower = match best {
+            &mut Option::Some((ref best, _)) => span.end - span.start < best.end - best.start,
+            &mut Option::None => true,
+        };
+        if narrower {
+            *best = Some((span, expr.get_kind()));
+        }
+        match expr {
+            &BlockExpression::Func(_, ref args, _, _, _) => {
+                for arg in args.iter() {
+                    visit(arg, offset, best);
+                }
+            }
+            &BlockExpression::Op(ref left, _, ref right, _, _) => {
+                visit(left, offset, best);
+                visit(right, offset, best);
+            }
+            _ => {}
+        };
+    }
+    fn visit_block(block: &BasicBlock, offset: usize, best: &mut Option<(Span, TypeData)>) {
+        for (_, expr) in block.get_vars() {
+            visit(expr, offset, best);
+        }
+        for expr in block.get_return().iter() {
+            visit(expr, offset, best);
+        }
+    }
+
+    let mut best = None;
+    for block in namespace.get_own_block().iter() {
+        visit_block(block, offset, &mut best);
+    }
+    for (_, block) in namespace.get_all_function_blocks() {
+        visit_block(block, offset, &mut best);
+    }
+    best
+}
+
+/// the text to display when hovering over `offset`
+pub fn hover(source: &str, source_dir: &Path, offset: usize) -> Option<(Span, String)> {
+    let tree = AbstractSyntaxTree::parse(source).ok()?;
+    let stdlib = Namespace::standard_library();
+    let namespace = Namespace::build(
+        &tree,
+        source_dir,
+        &Bindings::default(),
+        &Registry::standard(),
+    )
+    .ok();
+    match symbol_at(source, &tree, &stdlib, offset) {
+        Option::Some(Symbol {
+            span,
+            declaration: Option::Some(declaration),
+        }) => return Some((span, declaration.get_hover(namespace.as_ref()))),
+        _ => {}
+    };
+    type_at(namespace.as_ref()?, offset).map(|(span, kind)| (span, code_block(&kind.to_string())))
+}
+
+/// where the name at `offset` was declared
+pub fn definition(source: &str, offset: usize) -> Option<Span> {
+    let tree = AbstractSyntaxTree::parse(source).ok()?;
+    let stdlib = Namespace::standard_library();
+    symbol_at(source, &tree, &stdlib, offset)?
+        .declaration?
+        .get_name_span(source)
+}
+
+/// CompletionKind is what a completion inserts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompletionKind {
+    Function,
+    Constant,
+    Variable,
+}
+
+/// Completion is a name which may be written at the cursor
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    pub detail: String,
+}
+impl Completion {
+    fn new(name: &str, declaration: &Declaration) -> Completion {
+        let kind = match declaration {
+            &Declaration::Function(_) => CompletionKind::Function,
+            &Declaration::Constant(_) => CompletionKind::Constant,
+            _ => CompletionKind::Variable,
+        };
+        Completion {
+            label: name.to_string(),
+            kind,
+            detail: declaration.get_signature(None),
+        }
+    }
+}
+
+/// the names which may be written at `offset`.
+///
+/// the standard library is always offered. When the program
+/// parses its constants, functions and the `let` bindings &
+/// parameters of the function being edited are also offered.
+pub fn completions(source: &str, offset: usize) -> Vec<Completion> {
+    let stdlib = Namespace::standard_library();
+    let mut output: Vec<Completion> = stdlib
+        .get_all_functions()
+        .map(|(name, func)| Completion::new(name, &Declaration::Function(func)))
+        .collect();
+    match AbstractSyntaxTree::parse(source) {
+        Ok(tree) => {
+            let mut starts: Vec<usize> = tree.ast.iter().map(item_start).collect();
+            starts.sort();
+            for item in tree.ast.iter() {
+                match item {
+                    &Structures::Constant(ref constant) => output.push(Completion::new(
+                        constant.name,
+                        &Declaration::Constant(constant),
+                    )),
+                    &Structures::Func(ref func) => {
+                        output.push(Completion::new(func.name, &Declaration::Function(func)));
+                        // a function extends until the next item
+                        let end = starts
+                            .iter()
+                            .cloned()
+                            .find(|start| *start > func.span.start)
+                            .unwrap_or(source.len());
+                        if func.span.start <= offset && offset <= end {
+                            for (index, (name, _)) in func.args.iter().enumerate() {
+                                let param = Declaration::Parameter(func, index);
+                                output.push(Completion::new(name, &param));
+                            }
+                            for stmt in func.body.data.iter() {
+                                match stmt {
+                                    &Statement::Variable(ref var) => output.push(Completion::new(
+                                        var.name,
+                                        &Declaration::Variable(func, var),
+                                    )),
+                                    _ => {}
+                                };
+                            }
+                        }
+                    }
+                    _ => {}
+                };
+            }
+        }
+        Err(_) => {}
+    };
+    output.sort_by(|a, b| a.label.cmp(&b.label));
+    output
+}
+
+fn item_start(item: &Structures) -> usize {
+    match item {
+        &Structures::Constant(ref constant) => constant.span.start,
+        &Structures::Analyze(ref analysis) => analysis.span.start,
+        &Structures::Func(ref func) => func.span.start,
+    }
+}
+
+fn code_block(text: &str) -> String {
+    format!("```dice\n{}\n```", text)
+}
+
+/// the end of a span is inclusive, so the cursor directly after
+/// a name is still upon it.
+fn contains(span: Span, offset: usize) -> bool {
+    span.start <= offset && offset <= span.end
+}
+
+/// the name of a declaration is the first word after its keyword
+fn name_span(source: &str, decl: Span, name: &str) -> Span {
+    let text = source.get(decl.start..decl.end).unwrap_or("");
+    let keyword = text.find(char::is_whitespace).unwrap_or(0);
+    let rest = &text[keyword..];
+    let start = decl.start + keyword + (rest.len() - rest.trim_start().len());
+    Span::new(start, start + name.len())
+}
+
+/// where each parameter's name is written within a signature
+fn parameter_spans(source: &str, func: &FunctionDeclaration) -> Vec<Span> {
+    let text = source.get(func.span.start..func.span.end).unwrap_or("");
+    let mut cursor = text.find('(').unwrap_or(text.len());
+    let mut output = Vec::with_capacity(func.args.len());
+    for (name, _) in func.args.iter() {
+        let start = match find_word(&text[cursor..], name) {
+            Option::Some(start) => cursor + start,
+            Option::None => break,
+        };
+        cursor = start + name.len();
+        output.push(Span::new(
+            func.span.start + start,
+            func.span.start + cursor,
+        ));
+    }
+    output
+}
+
+/// finds `word` where it is not part of a longer name
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    let is_name = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(word).map(|(index, _)| index).find(|index| {
+        let before = text[..*index].chars().next_back();
+        let after = text[*index + word.len()..].chars().next();
+        !before.map(is_name).unwrap_or(false) && !after.map(is_name).unwrap_or(false)
+    })
+}
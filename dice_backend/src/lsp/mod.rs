@@ -0,0 +1,379 @@
+//! A language server for dice programs, spoken over stdio.
+//!
+//! Documents are re-parsed upon every request, programs are
+//! small enough that nothing needs to be cached between edits.
+use std::collections::HashMap;
+use std::io::{stdin, stdout};
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use super::diagnostic::{Diagnostic, Severity};
+use super::namespace::Bindings;
+use super::parser_output::Span;
+use super::runtime::check_in;
+
+mod analysis;
+use self::analysis::{completions, definition, hover, CompletionKind};
+mod transport;
+use self::transport::{read_message, write_message};
+
+/// JSON-RPC error for requests the server does not understand
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// JSON-RPC error for requests sent after `shutdown`
+const INVALID_REQUEST: i64 = -32600;
+
+/// serves requests from stdin until the client exits
+pub fn serve() -> Result<(), String> {
+    let stdin = stdin();
+    let stdout = stdout();
+    let mut input = stdin.lock();
+    let mut output = stdout.lock();
+    let mut server = Server::default();
+    while let Option::Some(message) = read_message(&mut input)? {
+        for reply in server.handle(&message) {
+            write_message(&mut output, &reply)?;
+        }
+        match server.exit {
+            Option::Some(true) => return Ok(()),
+            Option::Some(false) => return Err("exit was sent before shutdown".to_string()),
+            Option::None => {}
+        };
+    }
+    Ok(())
+}
+
+/// Server holds the documents the client has open
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, String>,
+    shutdown: bool,
+    /// set once `exit` is received, true if the exit is clean
+    exit: Option<bool>,
+}
+impl Server {
+    /// handles a single message, returns the replies & notifications to send
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+        let id = match message.get("id") {
+            Option::Some(id) => id.clone(),
+            // notifications are never answered
+            Option::None => return self.notify(method, params),
+        };
+        if self.shutdown {
+            return vec![error(id, INVALID_REQUEST, "the server is shutting down")];
+        }
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": {},
+                },
+                "serverInfo": { "name": "dice_backend", "version": "0.1.0" },
+            }),
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            }
+            "textDocument/hover" => self.hover(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/completion" => self.completion(params),
+            _ => {
+                return vec![error(
+                    id,
+                    METHOD_NOT_FOUND,
+                    &format!("method '{}' is not supported", method),
+                )]
+            }
+        };
+        vec![json!({"jsonrpc": "2.0", "id": id, "result": result})]
+    }
+
+    fn notify(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or("");
+                self.documents.insert(uri.clone(), text.to_string());
+                vec![self.publish_diagnostics(&uri)]
+            }
+            "textDocument/didChange" => {
+                // documents are always synchronized in full, the
+                // last change holds the entire text
+                let changes = params["contentChanges"].as_array();
+                match changes.and_then(|changes| changes.last()) {
+                    Option::Some(change) => {
+                        let text = change["text"].as_str().unwrap_or("");
+                        self.documents.insert(uri.clone(), text.to_string());
+                    }
+                    Option::None => {}
+                };
+                vec![self.publish_diagnostics(&uri)]
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                vec![json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": { "uri": uri, "diagnostics": [] },
+                })]
+            }
+            "exit" => {
+                self.exit = Some(self.shutdown);
+                vec![]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// parses & lints a document, errors & warnings are both reported
+    fn publish_diagnostics(&self, uri: &str) -> Value {
+        let source = self.get_document(uri);
+        let diagnostics = match check_in(source, &source_dir(uri), &Bindings::default()) {
+            Ok(warnings) => warnings,
+            Err(errors) => errors,
+        };
+        let diagnostics: Vec<Value> = diagnostics
+            .iter()
+            .map(|diagnostic| to_lsp_diagnostic(source, diagnostic))
+            .collect();
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        })
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let (uri, source, offset) = self.get_cursor(params);
+        match hover(source, &source_dir(uri), offset) {
+            Option::Some((span, text)) => json!({
+                "contents": { "kind": "markdown", "value": text },
+                "range": to_range(source, span),
+            }),
+            Option::None => Value::Null,
+        }
+    }
+
+    fn definition(&self, params: &Value) -> Value {
+        let (uri, source, offset) = self.get_cursor(params);
+        match definition(source, offset) {
+            Option::Some(span) => json!({ "uri": uri, "range": to_range(source, span) }),
+            Option::None => Value::Null,
+        }
+    }
+
+    fn completion(&self, params: &Value) -> Value {
+        let (_, source, offset) = self.get_cursor(params);
+        let items: Vec<Value> = completions(source, offset)
+            .into_iter()
+            .map(|completion| {
+                // the CompletionItemKind of the protocol
+                let kind = match completion.kind {
+                    CompletionKind::Function => 3,
+                    CompletionKind::Variable => 6,
+                    CompletionKind::Constant => 21,
+                };
+                json!({
+                    "label": completion.label,
+                    "kind": kind,
+                    "detail": completion.detail,
+                })
+            })
+            .collect();
+        Value::Array(items)
+    }
+
+    fn get_document(&self, uri: &str) -> &str {
+        self.documents.get(uri).map(|x| x.as_str()).unwrap_or("")
+    }
+
+    /// the document & byte offset of a `TextDocumentPositionParams`
+    fn get_cursor<'a>(&'a self, params: &'a Value) -> (&'a str, &'a str, usize) {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+        let source = self.get_document(uri);
+        let line = params["position"]["line"].as_u64().unwrap_or(0) as usize;
+        let character = params["position"]["character"].as_u64().unwrap_or(0) as usize;
+        (uri, source, to_offset(source, line, character))
+    }
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn to_lsp_diagnostic(source: &str, diagnostic: &Diagnostic) -> Value {
+    let labels = diagnostic.get_labels();
+    let span = labels
+        .iter()
+        .find(|label| label.primary)
+        .or_else(|| labels.first())
+        .map(|label| label.span)
+        .unwrap_or_default();
+    let mut message = diagnostic.get_message().to_string();
+    for note in diagnostic.get_notes() {
+        message.push_str(&format!("\nnote: {}", note));
+    }
+    for help in diagnostic.get_help() {
+        message.push_str(&format!("\nhelp: {}", help));
+    }
+    let severity = match diagnostic.get_severity() {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+    };
+    json!({
+        "range": to_range(source, span),
+        "severity": severity,
+        "code": diagnostic.get_code().as_str(),
+        "source": "dice",
+        "message": message,
+    })
+}
+
+/// files a document loads are relative to the document
+fn source_dir(uri: &str) -> PathBuf {
+    let path = uri.trim_start_matches("file://").replace("%20", " ");
+    PathBuf::from(path)
+        .parent()
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_default()
+}
+
+/// positions count lines, and UTF-16 code units within the line
+fn to_offset(source: &str, line: usize, character: usize) -> usize {
+    let line_start = match line {
+        0 => 0,
+        n => match source.match_indices('\n').nth(n - 1) {
+            Option::Some((index, _)) => index + 1,
+            Option::None => return source.len(),
+        },
+    };
+    let mut units = 0;
+    for (index, c) in source[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return line_start + index;
+        }
+        units += c.len_utf16();
+    }
+    source.len()
+}
+
+fn to_position(source: &str, offset: usize) -> Value {
+    let prefix = source.get(..offset).unwrap_or(source);
+    let line = prefix.matches('\n').count();
+    let line_start = prefix.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    let character: usize = prefix[line_start..].chars().map(char::len_utf16).sum();
+    json!({ "line": line, "character": character })
+}
+
+fn to_range(source: &str, span: Span) -> Value {
+    json!({ "start": to_position(source, span.start), "end": to_position(source, span.end) })
+}
+
+#[test]
+fn test_language_server() {
+    let source = "const dice: vec<int> = roll_d6(3);\nfn best(x: vec<int>) -> int {\n    let top = max(x);\n    return top;\n}\nanalyze (best(dice) + 1);\n";
+    let uri = "file:///tmp/test.dice";
+    let position = |line: usize, character: usize| {
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character },
+        })
+    };
+    let request = |id: u64, method: &str, params: Value| {
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+    };
+
+    let mut server = Server::default();
+    let init = server.handle(&request(1, "initialize", json!({})));
+    assert_eq!(init[0]["result"]["capabilities"]["hoverProvider"], true);
+
+    let opened = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": { "textDocument": { "uri": uri, "text": source } },
+    }));
+    assert_eq!(opened[0]["params"]["diagnostics"], json!([]));
+
+    // hovering a standard library function shows its argument names
+    let reply = server.handle(&request(2, "textDocument/hover", position(0, 25)));
+    let text = reply[0]["result"]["contents"]["value"].as_str().unwrap();
+    assert!(text.contains("fn roll_d6(number_of_d6_to_roll: int) -> vec<int>"));
+    assert!(text.contains("six sided dice"));
+
+    // hovering an operator shows the type of the operation
+    let reply = server.handle(&request(3, "textDocument/hover", position(5, 20)));
+    let text = reply[0]["result"]["contents"]["value"].as_str().unwrap();
+    assert!(text.contains("int"));
+
+    // hovering a binding without an annotation shows its inferred type
+    let reply = server.handle(&request(8, "textDocument/hover", position(3, 12)));
+    let text = reply[0]["result"]["contents"]["value"].as_str().unwrap();
+    assert!(text.contains("let top: int"));
+
+    // `top` is declared upon line 2
+    let reply = server.handle(&request(4, "textDocument/definition", position(3, 12)));
+    assert_eq!(
+        reply[0]["result"]["range"]["start"],
+        json!({"line": 2, "character": 8})
+    );
+    // `dice` is declared upon line 0
+    let reply = server.handle(&request(5, "textDocument/definition", position(5, 15)));
+    assert_eq!(
+        reply[0]["result"]["range"]["start"],
+        json!({"line": 0, "character": 6})
+    );
+    // `x` is a parameter
+    let reply = server.handle(&request(6, "textDocument/definition", position(2, 18)));
+    assert_eq!(
+        reply[0]["result"]["range"]["start"],
+        json!({"line": 1, "character": 8})
+    );
+
+    let reply = server.handle(&request(7, "textDocument/completion", position(3, 11)));
+    let labels: Vec<&str> = reply[0]["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    assert!(labels.contains(&"sum"));
+    assert!(labels.contains(&"best"));
+    assert!(labels.contains(&"top"));
+    assert!(labels.contains(&"x"));
+
+    // errors are published with the range of their primary label
+    let changed = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": uri },
+            "contentChanges": [ { "text": "analyze (roll_d6(3) + tru);" } ],
+        },
+    }));
+    let diagnostic = &changed[0]["params"]["diagnostics"][0];
+    assert_eq!(diagnostic["code"], "E0001");
+    assert_eq!(diagnostic["severity"], 1);
+    assert_eq!(diagnostic["range"]["start"], json!({"line": 0, "character": 22}));
+
+    let reply = server.handle(&request(8, "shutdown", Value::Null));
+    assert_eq!(reply[0]["result"], Value::Null);
+    server.handle(&json!({"jsonrpc": "2.0", "method": "exit"}));
+    assert_eq!(server.exit, Some(true));
+}
+
+#[test]
+fn test_utf16_positions() {
+    let source = "// é\nanalyze 1;";
+    assert_eq!(to_offset(source, 0, 4), source.len() - 11);
+    assert_eq!(to_offset(source, 1, 8), source.len() - 2);
+    assert_eq!(to_position(source, source.len() - 2), json!({"line": 1, "character": 8}));
+}
@@ -0,0 +1,73 @@
+use std::io::{BufRead, Write};
+
+use serde_json::Value;
+
+/// reads one message, messages are a `Content-Length` header
+/// followed by a JSON body. Returns `None` once the input closes.
+pub fn read_message<R: BufRead>(input: &mut R) -> Result<Option<Value>, String> {
+    let mut length: Option<usize> = None;
+    loop {
+        let mut line = String::new();
+        match input.read_line(&mut line) {
+            Ok(0) => return Ok(None),
+            Ok(_) => {}
+            Err(e) => return Err(format!("failed to read message header with error={:?}", e)),
+        };
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let mut header = line.splitn(2, ':');
+        match (header.next(), header.next()) {
+            (Option::Some(name), Option::Some(value))
+                if name.trim().eq_ignore_ascii_case("content-length") =>
+            {
+                length = match value.trim().parse::<usize>() {
+                    Ok(length) => Some(length),
+                    Err(_) => return Err(format!("invalid Content-Length header: '{}'", line)),
+                };
+            }
+            // Content-Type is the only other header, and it has one value
+            _ => {}
+        };
+    }
+    let length = match length {
+        Option::Some(length) => length,
+        Option::None => return Err("message is missing its Content-Length header".to_string()),
+    };
+    let mut body = vec![0u8; length];
+    match input.read_exact(&mut body) {
+        Ok(()) => {}
+        Err(e) => return Err(format!("failed to read message body with error={:?}", e)),
+    };
+    match serde_json::from_slice(&body) {
+        Ok(message) => Ok(Some(message)),
+        Err(e) => Err(format!("message is not valid JSON: {}", e)),
+    }
+}
+
+/// writes one message, with its `Content-Length` header
+pub fn write_message<W: Write>(output: &mut W, message: &Value) -> Result<(), String> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)
+        .and_then(|()| output.flush())
+        .map_err(|e| format!("failed to write message with error={:?}", e))
+}
+
+#[test]
+fn test_transport() {
+    let message = json!({"jsonrpc": "2.0", "id": 1, "method": "shutdown"});
+    let mut buffer = Vec::new();
+    write_message(&mut buffer, &message).unwrap();
+    write_message(&mut buffer, &message).unwrap();
+    let header = format!("Content-Length: {}\r\n\r\n{{", message.to_string().len());
+    assert!(buffer.starts_with(header.as_bytes()));
+
+    let mut input = &buffer[..];
+    assert_eq!(read_message(&mut input).unwrap(), Some(message.clone()));
+    assert_eq!(read_message(&mut input).unwrap(), Some(message));
+    assert_eq!(read_message(&mut input).unwrap(), None);
+
+    let mut missing = &b"Content-Type: utf-8\r\n\r\n{}"[..];
+    assert!(read_message(&mut missing).is_err());
+}
@@ -0,0 +1,133 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+
+/// Bindings supply the values of `%d{{NAME}}` and `%b{{NAME}}`
+/// parameters.
+///
+/// Values which are defined explicitly take priority. When no
+/// definition exists, the process environment is consulted,
+/// unless that has been forbidden, which keeps results
+/// reproducible.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bindings {
+    values: BTreeMap<String, String>,
+    read_environment: bool,
+}
+impl Default for Bindings {
+    fn default() -> Bindings {
+        Bindings {
+            values: BTreeMap::new(),
+            read_environment: true,
+        }
+    }
+}
+impl Bindings {
+    /// defines a parameter, replacing any previous definition
+    pub fn define<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    /// parses a `NAME=VALUE` definition, as given to `--define`.
+    /// A name which is already defined is an error, rather than
+    /// silently replacing the first value.
+    pub fn define_from_str(&mut self, arg: &str) -> Result<(), String> {
+        let mut iter = arg.splitn(2, '=');
+        match (iter.next().map(|x| x.trim()), iter.next()) {
+            (Option::Some(name), Option::Some(value)) if !name.is_empty() => {
+                let value = value.trim();
+                if let Option::Some(first) = self.values.get(name) {
+                    return Err(format!(
+                        "parameter '{}' is defined twice, as '{}' and '{}'",
+                        name, first, value
+                    ));
+                }
+                self.define(name, value);
+                Ok(())
+            }
+            _ => Err(format!(
+                "parameter definition '{}' is not of the form NAME=VALUE",
+                arg
+            )),
+        }
+    }
+
+    /// controls if the process environment may be read
+    pub fn read_environment(&mut self, allowed: bool) {
+        self.read_environment = allowed;
+    }
+
+    /// resolves an int parameter
+    pub fn get_int(&self, name: &str) -> Result<i8, String> {
+        let value = self.get(name)?;
+        value.trim().parse::<i8>().map_err(|_| {
+            format!(
+                "parameter '{}' has value '{}' which is not an int",
+                name, value
+            )
+        })
+    }
+
+    /// resolves a bool parameter
+    pub fn get_bool(&self, name: &str) -> Result<bool, String> {
+        let value = self.get(name)?;
+        value.trim().parse::<bool>().map_err(|_| {
+            format!(
+                "parameter '{}' has value '{}' which is not a bool",
+                name, value
+            )
+        })
+    }
+
+    /// checks if every parameter within `names` has the same
+    /// value in both bindings
+    pub fn agrees_with(&self, other: &Bindings, names: &BTreeSet<String>) -> bool {
+        names
+            .iter()
+            .all(|name| self.get(name).ok() == other.get(name).ok())
+    }
+
+    /// resolves a parameter's value, as it was defined
+    pub fn get(&self, name: &str) -> Result<String, String> {
+        if let Option::Some(value) = self.values.get(name) {
+            return Ok(value.clone());
+        }
+        if self.read_environment {
+            if let Ok(value) = env::var(name) {
+                return Ok(value);
+            }
+        }
+        Err(format!(
+            "parameter '{}' is not defined, use --define {}=VALUE",
+            name, name
+        ))
+    }
+}
+
+#[test]
+fn test_bindings() {
+    let mut bindings = Bindings::default();
+    bindings.read_environment(false);
+    bindings.define_from_str("TOUGHNESS=4").unwrap();
+    bindings.define_from_str("REROLL = true").unwrap();
+    bindings.define("HUGE", "300");
+    assert_eq!(bindings.get_int("TOUGHNESS"), Ok(4));
+    assert_eq!(bindings.get_bool("REROLL"), Ok(true));
+    assert!(bindings.get_int("HUGE").is_err());
+    assert!(bindings.get_bool("TOUGHNESS").is_err());
+    assert!(bindings.get_int("PATH").is_err());
+    assert!(bindings.define_from_str("=4").is_err());
+    assert!(bindings.define_from_str("TOUGHNESS").is_err());
+    assert_eq!(
+        bindings.define_from_str("TOUGHNESS=5"),
+        Err("parameter 'TOUGHNESS' is defined twice, as '4' and '5'".to_string())
+    );
+    assert_eq!(bindings.get_int("TOUGHNESS"), Ok(4));
+
+    // only the parameters which were named are compared
+    let mut other = bindings.clone();
+    other.define("HUGE", "301");
+    let names: BTreeSet<String> = vec!["TOUGHNESS".to_string()].into_iter().collect();
+    assert!(bindings.agrees_with(&other, &names));
+    other.define("TOUGHNESS", "5");
+    assert!(!bindings.agrees_with(&other, &names));
+}
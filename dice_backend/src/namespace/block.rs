@@ -1,16 +1,18 @@
 use std::collections::hash_map::Iter;
 use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
-use std::mem::replace;
+use std::fmt;
 
+use super::super::diagnostic::{Code, Diagnostic};
 use super::super::parser_output::{
-    Expression, FunctionDeclaration, GetType, Statement, Statements, TerminalExpression, TypeData,
-    VariableDeclaration,
+    ConstantDeclaration, Expression, FunctionDeclaration, Span, Statement, TerminalExpression,
+    TypeData, VariableDeclaration,
 };
 use super::super::seahasher::DefaultSeaHasher;
 
 use super::blockexpression::BlockExpression;
+use super::determinism::Determinism;
 use super::namespace::Namespace;
+use super::suggest::{conversion, did_you_mean};
 
 /// BasicBlock is in essence a function's body.
 /// It can also be used for control structures
@@ -24,13 +26,30 @@ pub struct BasicBlock<'a> {
     vars: HashMap<&'a str, VariableDeclaration<'a>, DefaultSeaHasher>,
     populated_vars: HashMap<&'a str, BlockExpression<'a>, DefaultSeaHasher>,
     populated_return_expresion: Option<BlockExpression<'a>>,
+    /// parameters which must be constant, and the argument of the
+    /// standard library which requires it
+    requirements: Vec<(usize, Span)>,
+}
+impl<'a> fmt::Display for BasicBlock<'a> {
+    /// writes each variable sorted by name, then the return
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut vars: Vec<(&&'a str, &BlockExpression<'a>)> = self.populated_vars.iter().collect();
+        vars.sort_by_key(|(name, _)| **name);
+        for (name, expr) in vars {
+            writeln!(f, "    {}: {} = {}", name, expr.get_kind(), expr)?;
+        }
+        match self.populated_return_expresion {
+            Option::Some(ref expr) => writeln!(f, "    return: {} = {}", expr.get_kind(), expr),
+            Option::None => Ok(()),
+        }
+    }
 }
 impl<'a> BasicBlock<'a> {
     /// new constructs a basic block from a function's declaration within a namespace
     pub fn from_func(
         names: &Namespace<'a>,
         func: &FunctionDeclaration<'a>,
-    ) -> Result<BasicBlock<'a>, String> {
+    ) -> Result<BasicBlock<'a>, Diagnostic> {
         let mut bb = BasicBlock::default();
         for (index, (name, kind)) in func.args.iter().enumerate() {
             bb.add_function_vars(func, names, index, name, *kind)?;
@@ -42,34 +61,51 @@ impl<'a> BasicBlock<'a> {
     }
 
     /// handles only converting the root expression not individual functions
-    pub fn from_root(names: &Namespace<'a>) -> Result<BasicBlock<'a>, String> {
-        let mut bb = BasicBlock {
-            vars: HashMap::default(),
-            populated_vars: HashMap::default(),
-            populated_return_expresion: None,
-        };
-        for (name, value) in names.get_all_constants() {
-            let expr = bb.convert_expression(names, &value.expr)?;
-            let expr_type = expr.get_type()?;
-            if value.kind != expr_type {
-                return Err(format!("constant declaration: '{}' is in error. Expression: '{}' yeilds type: '{}' but we are binding type: '{}'", value, &value.expr, expr_type, value.kind));
-            }
+    pub fn from_root(names: &Namespace<'a>) -> Result<BasicBlock<'a>, Diagnostic> {
+        let mut bb = BasicBlock::default();
+        for (name, _) in names.get_all_constants() {
+            let expr = match (names.get_constant_value(name), names.get_constant(name)) {
+                (Option::Some(expr), _) => expr.clone(),
+                (Option::None, Option::Some(value)) => BasicBlock::lower_constant(names, value)?,
+                (Option::None, Option::None) => continue,
+            };
             bb.populated_vars.insert(name, expr);
         }
 
         let analysis = match names.get_analysis() {
             Option::Some(analysis) => analysis,
             Option::None => {
-                return Err(format!(
-                    "program contains no analysis directive. How do we report?"
-                ));
+                return Err(Diagnostic::error(
+                    Code::MissingAnalyze,
+                    "program contains no analysis directive. How do we report?",
+                )
+                .with_help("add a statement such as `analyze sum(roll_d6(3));`"));
             }
         };
         let expr = bb.convert_expression(names, &analysis.expr)?;
-        replace(&mut bb.populated_return_expresion, Some(expr));
+        bb.populated_return_expresion.replace(expr);
         Ok(bb)
     }
 
+    /// lowers a constant's expression, checking it against the
+    /// constant's annotation.
+    pub fn lower_constant(
+        names: &Namespace<'a>,
+        value: &ConstantDeclaration<'a>,
+    ) -> Result<BlockExpression<'a>, Diagnostic> {
+        let expr = BasicBlock::default().convert_expression(names, &value.expr)?;
+        let expr_type = expr.get_kind();
+        match value.kind {
+            Option::Some(kind) if kind != expr_type => {
+                let diagnostic = Diagnostic::error(Code::TypeMismatch, format!("constant declaration: '{}' is in error. Expression: '{}' yeilds type: '{}' but we are binding type: '{}'", value, &value.expr, expr_type, kind))
+                    .with_label(value.expr.get_span(), format!("this is {}", expr_type))
+                    .with_secondary(value.span, format!("declared as {} here", kind));
+                Err(conversion(diagnostic, expr_type, kind))
+            }
+            _ => Ok(expr),
+        }
+    }
+
     pub fn get_return<'b>(&'b self) -> &'b Option<BlockExpression<'a>> {
         &self.populated_return_expresion
     }
@@ -78,15 +114,26 @@ impl<'a> BasicBlock<'a> {
         self.populated_vars.iter()
     }
 
+    /// returns the parameters which must be constant, and where the
+    /// standard library requires each
+    pub fn get_requirements(&self) -> &[(usize, Span)] {
+        &self.requirements
+    }
+
+    /// returns the lowered expression of a variable, parameter or constant
+    pub fn get_var<'b>(&'b self, name: &str) -> Option<&'b BlockExpression<'a>> {
+        self.populated_vars.get(name)
+    }
+
     fn add_statement(
         &mut self,
         func: &FunctionDeclaration<'a>,
         names: &Namespace<'a>,
         stmt: &Statement<'a>,
-    ) -> Result<(), String> {
+    ) -> Result<(), Diagnostic> {
         match stmt {
             Statement::Variable(ref var) => {
-                self.insert_variable(func, names, var)?;
+                self.insert_variable(names, var)?;
             }
             Statement::Return(ref term) => {
                 self.insert_return(func, names, term)?;
@@ -96,18 +143,29 @@ impl<'a> BasicBlock<'a> {
     }
 
     fn convert_expression(
-        &self,
+        &mut self,
         n: &Namespace<'a>,
         expr: &Expression<'a>,
-    ) -> Result<BlockExpression<'a>, String> {
+    ) -> Result<BlockExpression<'a>, Diagnostic> {
         match expr {
-            Expression::Literal(ref lit) => Ok(BlockExpression::lit(&lit.lit)?),
-            Expression::Variable(ref var) => Ok(self.convert_expr_var_name(n, var.name)?),
+            Expression::Literal(ref lit) => {
+                let resolved = match n.resolve_literal(&lit.lit) {
+                    Ok(resolved) => resolved,
+                    Err(e) => {
+                        return Err(Diagnostic::error(Code::InvalidLiteral, e).with_label(lit.span, ""))
+                    }
+                };
+                Ok(BlockExpression::lit(&resolved, lit.span)?)
+            }
+            Expression::Variable(ref var) => Ok(self.convert_expr_var_name(n, var.name, var.span)?),
             Expression::Func(ref func) => {
                 // lookup function in namespace
                 let func_data = match n.get_function(func.name) {
                     Option::None => {
-                        return Err(format!("function invocation: '{}' cannot be resolved. no function of that name is defined", func));
+                        let diagnostic = Diagnostic::error(Code::UnknownFunction, format!("function invocation: '{}' cannot be resolved. no function of that name is defined", func))
+                            .with_label(func.span, "not defined");
+                        let functions = n.get_all_functions().map(|(name, _)| *name);
+                        return Err(did_you_mean(diagnostic, func.name, functions));
                     }
                     Option::Some(func_data) => func_data,
                 };
@@ -115,7 +173,9 @@ impl<'a> BasicBlock<'a> {
                 let declared_args_count: usize = func_data.args.len();
                 let referenced_args_count: usize = func.args.len();
                 if declared_args_count != referenced_args_count {
-                    return Err(format!("function invocation: '{}' has the name of function: '{}' but incorrect argument count. Expected: {} Found: {}", func, func_data, declared_args_count, referenced_args_count));
+                    return Err(Diagnostic::error(Code::ArgumentCount, format!("function invocation: '{}' has the name of function: '{}' but incorrect argument count. Expected: {} Found: {}", func, func_data, declared_args_count, referenced_args_count))
+                        .with_label(func.span, format!("expected {} arguments", declared_args_count))
+                        .with_secondary(func_data.span, "declared here"));
                 }
 
                 // iterate over the arguments & convert their expressions recursively
@@ -123,23 +183,105 @@ impl<'a> BasicBlock<'a> {
                 let mut arg_vec = Vec::with_capacity(func.args.len());
                 for (index, arg) in func.args.iter().enumerate() {
                     let block_expr = self.convert_expression(n, arg)?;
-                    let expected_type = func_data.args[index].1.clone();
-                    let found_type = block_expr.get_type()?;
+                    let expected_type = func_data.args[index].1;
+                    let found_type = block_expr.get_kind();
                     if found_type != expected_type {
-                        return Err(format!("expression: '{}' has an error the {} argument to function '{}' is of the incorrect type. Expected type:{} Found type:{}", expr, index, func_data, expected_type, found_type));
+                        let diagnostic = Diagnostic::error(Code::ArgumentType, format!("expression: '{}' has an error the {} argument to function '{}' is of the incorrect type. Expected type:{} Found type:{}", expr, index, func_data, expected_type, found_type))
+                            .with_label(arg.get_span(), format!("expected {} found {}", expected_type, found_type))
+                            .with_secondary(func_data.span, "declared here");
+                        return Err(conversion(diagnostic, found_type, expected_type));
                     }
                     arg_vec.push(block_expr);
                 }
-                Ok(BlockExpression::func(func.name, arg_vec, func_data.ret)?)
+                let determinism = self.check_determinism(n, func_data, &arg_vec)?;
+                Ok(BlockExpression::func(
+                    func.name,
+                    arg_vec,
+                    func_data.ret,
+                    determinism,
+                    func.span,
+                )?)
             }
             Expression::Operation(ref op) => {
                 let left = self.convert_expression(n, op.left.as_ref())?;
                 let right = self.convert_expression(n, op.right.as_ref())?;
-                Ok(BlockExpression::op(left, op.op.clone(), right)?)
+                Ok(BlockExpression::op(left, op.op, right, op.span)?)
             }
         }
     }
 
+    /// checks the arguments which must be constant, returning the
+    /// determinism of the invocation.
+    ///
+    /// The standard library lists the arguments it requires be
+    /// constant, such as the faces of `roll`. A function which
+    /// passes its parameters on to such an argument requires its
+    /// own arguments be constant.
+    fn check_determinism(
+        &mut self,
+        n: &Namespace<'a>,
+        func: &FunctionDeclaration<'a>,
+        args: &[BlockExpression<'a>],
+    ) -> Result<Determinism, Diagnostic> {
+        let stdlib = n.get_std_function(func.name);
+        let requirements: Vec<(usize, Span)> = match stdlib {
+            Option::Some(std) => std
+                .constant_args()
+                .iter()
+                .map(|index| (*index, args[*index].get_span()))
+                .collect(),
+            Option::None => n
+                .get_function_block(func.name)
+                .map(|bb| bb.requirements.clone())
+                .unwrap_or_default(),
+        };
+        for (index, origin) in requirements {
+            let arg = &args[index];
+            match arg.get_determinism() {
+                Determinism::Constant => {}
+                Determinism::Parameters(params) => {
+                    self.requirements
+                        .extend(params.into_iter().map(|param| (param, origin)));
+                }
+                Determinism::Random => {
+                    let mut diagnostic = Diagnostic::error(
+                        Code::RandomArgument,
+                        format!(
+                            "argument {} '{}' to function '{}' must be constant, but expression: '{}' is random",
+                            index + 1,
+                            func.args[index].0,
+                            func.name,
+                            arg
+                        ),
+                    )
+                    .with_label(arg.get_span(), "this is random");
+                    if stdlib.is_none() {
+                        diagnostic =
+                            diagnostic.with_secondary(origin, "a constant is required here");
+                    }
+                    return Err(diagnostic.with_note(
+                        "a constant is known before the program is evaluated, a random value is not",
+                    ));
+                }
+            };
+        }
+
+        let args: Vec<Determinism> = args.iter().map(|arg| arg.get_determinism()).collect();
+        match stdlib {
+            Option::Some(std) if std.is_random() => return Ok(Determinism::Random),
+            Option::Some(_) => {
+                return Ok(args
+                    .iter()
+                    .fold(Determinism::Constant, |acc, arg| acc.join(arg)))
+            }
+            Option::None => {}
+        };
+        Ok(match n.get_function_block(func.name).and_then(|bb| bb.get_return().as_ref()) {
+            Option::Some(ret) => ret.get_determinism().substitute(&args),
+            Option::None => Determinism::Random,
+        })
+    }
+
     /// inserts a function's variable into the local block.
     fn add_function_vars(
         &mut self,
@@ -148,38 +290,57 @@ impl<'a> BasicBlock<'a> {
         index: usize,
         name: &'a str,
         kind: TypeData,
-    ) -> Result<(), String> {
+    ) -> Result<(), Diagnostic> {
         if self.is_name_defined(n, name) {
-            return Err(format!("within function declaration: '{}' argument: '{}: {}' its name collides with an external variable", f, name, kind));
+            return Err(Diagnostic::error(Code::DuplicateDeclaration, format!("within function declaration: '{}' argument: '{}: {}' its name collides with an external variable", f, name, kind))
+                .with_label(f.span, format!("argument '{}' collides", name))
+                .with_secondary(n.get_declaration_span(name), "declared here"));
         }
         self.populated_vars
-            .insert(name, BlockExpression::FunctionArg(name, index, kind));
+            .insert(name, BlockExpression::FunctionArg(name, index, kind, f.span));
         Ok(())
     }
 
     /// inserts a variable into the local block
+    ///
+    /// variables may shadow constants, but not functions or
+    /// other variables.
     fn insert_variable(
         &mut self,
-        f: &FunctionDeclaration<'a>,
         n: &Namespace<'a>,
         var: &VariableDeclaration<'a>,
-    ) -> Result<(), String> {
-        if self.is_name_defined(n, var.name) {
-            return Err(format!(
-                "variable: '{}' collides with an existing name",
+    ) -> Result<(), Diagnostic> {
+        if self.populated_vars.contains_key(var.name) || n.get_function(var.name).is_some() {
+            let previous = self
+                .populated_vars
+                .get(var.name)
+                .map(|expr| expr.get_span())
+                .unwrap_or_else(|| n.get_declaration_span(var.name));
+            return Err(Diagnostic::error(
+                Code::DuplicateDeclaration,
+                format!("variable: '{}' collides with an existing name",
                 var
-            ));
+            ))
+            .with_label(var.span, "collides")
+            .with_secondary(previous, "previously declared here"));
         }
 
         // do a typecheck
         let expr = self.convert_expression(n, &var.expr)?;
-        let expr_type = expr.get_type()?;
-        if expr_type != var.kind {
-            return Err(format!("in variable defination: '{}' the expression: '{}' returns type: '{}' while the variable is declared '{}'", var, var.expr, expr_type, var.kind));
-        }
+        let expr_type = expr.get_kind();
+        match var.kind {
+            Option::Some(kind) if kind != expr_type => {
+                let diagnostic = Diagnostic::error(Code::TypeMismatch, format!("in variable defination: '{}' the expression: '{}' returns type: '{}' while the variable is declared '{}'", var, var.expr, expr_type, kind))
+                    .with_label(var.expr.get_span(), format!("this is {}", expr_type))
+                    .with_secondary(var.span, format!("declared as {} here", kind));
+                return Err(conversion(diagnostic, expr_type, kind));
+            }
+            _ => {}
+        };
 
         // populate the hash tables
         self.populated_vars.insert(var.name, expr);
+        self.vars.insert(var.name, var.clone());
         Ok(())
     }
 
@@ -188,29 +349,36 @@ impl<'a> BasicBlock<'a> {
         f: &FunctionDeclaration<'a>,
         n: &Namespace<'a>,
         term: &TerminalExpression<'a>,
-    ) -> Result<(), String> {
+    ) -> Result<(), Diagnostic> {
         // type check against function declaration
         let return_expr = self.convert_expression(n, &term.expr)?;
-        let return_type = return_expr.get_type()?;
+        let return_type = return_expr.get_kind();
         if return_type != f.ret {
-            return Err(format!("return expression: 'return {};' has type of '{}' while the function we are returning: '{}' expects: '{}'", term.expr, return_type, f, f.ret));
+            let diagnostic = Diagnostic::error(Code::TypeMismatch, format!("return expression: 'return {};' has type of '{}' while the function we are returning: '{}' expects: '{}'", term.expr, return_type, f, f.ret))
+                .with_label(term.expr.get_span(), format!("this is {}", return_type))
+                .with_secondary(f.span, format!("declared to return {} here", f.ret));
+            return Err(conversion(diagnostic, return_type, f.ret));
         }
 
         // update return field
-        match replace(&mut self.populated_return_expresion, Some(return_expr)) {
+        match self.populated_return_expresion.replace(return_expr) {
             Option::None => {}
             Option::Some(old_term) => {
-                return Err(format!(
+                return Err(Diagnostic::error(
+                    Code::MultipleReturns,
+                    format!(
                     "multiple return statements\nold:\n{}\nnew:\n{}\n",
                     old_term, term
                 ))
+                .with_label(term.span, "second return")
+                .with_secondary(old_term.get_span(), "first return"))
             }
         };
         Ok(())
     }
 
     fn is_name_defined(&self, n: &Namespace<'a>, name: &str) -> bool {
-        n.is_name_defined(name) || self.populated_vars.get(name).is_some()
+        n.is_name_defined(name) || self.populated_vars.contains_key(name)
     }
 
     // convert_expr_var_name handles the messiness of determining _what kind of variable_
@@ -219,29 +387,59 @@ impl<'a> BasicBlock<'a> {
         &self,
         namespace: &Namespace<'a>,
         name: &'a str,
-    ) -> Result<BlockExpression<'a>, String> {
-        match namespace.get_constant(name) {
+        span: Span,
+    ) -> Result<BlockExpression<'a>, Diagnostic> {
+        // a local variable shadows a constant of the same name
+        match (self.vars.get(name), self.populated_vars.get(name)) {
+            (Option::Some(_), Option::Some(expr)) => {
+                return Ok(BlockExpression::Var(
+                    name,
+                    expr.get_kind(),
+                    expr.get_determinism(),
+                    span,
+                ))
+            }
+            _ => {}
+        };
+        match namespace.get_constant_type(name) {
             Option::None => {}
-            Option::Some(ref constant_dec) => {
+            Option::Some(kind) => {
                 return Ok(BlockExpression::ExternalConstant(
                     name,
-                    constant_dec.kind.clone(),
+                    kind,
+                    namespace.get_constant_determinism(name),
+                    span,
                 ));
             }
         };
         match self.populated_vars.get(name) {
-            Option::Some(&BlockExpression::FunctionArg(_, ref index, ref kind)) => {
+            Option::Some(&BlockExpression::FunctionArg(_, ref index, ref kind, _)) => {
                 return Ok(BlockExpression::FunctionArg(
                     name,
-                    index.clone(),
-                    kind.clone(),
+                    *index,
+                    *kind,
+                    span,
                 ));
             }
-            Option::Some(ref block) => {
-                return Ok(BlockExpression::Var(name, block.get_type()?));
+            Option::Some(block) => {
+                return Ok(BlockExpression::Var(
+                    name,
+                    block.get_kind(),
+                    block.get_determinism(),
+                    span,
+                ));
             }
             Option::None => {}
         };
-        Err(format!("variable name:'{}' is not defined", name))
+        let diagnostic = Diagnostic::error(
+            Code::UnknownIdentifier,
+            format!("variable name:'{}' is not defined", name),
+        )
+        .with_label(span, "not defined");
+        let in_scope = namespace
+            .get_all_constants()
+            .map(|(name, _)| *name)
+            .chain(self.populated_vars.keys().cloned());
+        Err(did_you_mean(diagnostic, name, in_scope))
     }
 }
@@ -1,30 +1,35 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::mem::replace;
 
-use super::super::parser_output::{
-    Expression, FunctionInvocation, GetType, Literal, Operation, TypeData,
-};
+use super::super::diagnostic::{Code, Diagnostic};
+use super::super::parser_output::{GetType, Literal, Operation, Span, TypeData};
 use super::super::seahash::SeaHasher;
 
-use super::namespace::Namespace;
+use super::determinism::Determinism;
 
 /// BlockExpression is the result of expresion lowering.
 /// when preformed it. Block Expressions, unlike normal
 /// expressions are not a recrusive data type.
+///
+/// Each expression carries the span of source it was lowered
+/// from, so later errors can point at it.
+///
+/// The determinism of literals, parameters & operations follows
+/// from the expression itself. Constants, variables & invocations
+/// carry the determinism of the expression they stand for.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum BlockExpression<'a> {
-    FunctionArg(&'a str, usize, TypeData),
-    ConstantValue(Literal<'a>, TypeData),
-    ExternalConstant(&'a str, TypeData),
-    Func(&'a str, Box<[BlockExpression<'a>]>, TypeData),
-    Var(&'a str, TypeData),
+    FunctionArg(&'a str, usize, TypeData, Span),
+    ConstantValue(Literal<'a>, TypeData, Span),
+    ExternalConstant(&'a str, TypeData, Determinism, Span),
+    Func(&'a str, Box<[BlockExpression<'a>]>, TypeData, Determinism, Span),
+    Var(&'a str, TypeData, Determinism, Span),
     Op(
         Box<BlockExpression<'a>>,
         Operation,
         Box<BlockExpression<'a>>,
         TypeData,
+        Span,
     ),
 }
 impl<'a> BlockExpression<'a> {
@@ -34,21 +39,87 @@ impl<'a> BlockExpression<'a> {
         hasher.finish()
     }
 
+    /// returns where the expression was written
+    pub fn get_span(&self) -> Span {
+        match self {
+            &BlockExpression::FunctionArg(_, _, _, span)
+            | &BlockExpression::ConstantValue(_, _, span)
+            | &BlockExpression::ExternalConstant(_, _, _, span)
+            | &BlockExpression::Func(_, _, _, _, span)
+            | &BlockExpression::Var(_, _, _, span)
+            | &BlockExpression::Op(_, _, _, _, span) => span,
+        }
+    }
+
+    /// the type of the expression, which is always known once lowered
+    pub fn get_kind(&self) -> TypeData {
+        match self {
+            &BlockExpression::FunctionArg(_, _, ref kind, _)
+            | &BlockExpression::ConstantValue(_, ref kind, _)
+            | &BlockExpression::ExternalConstant(_, ref kind, _, _)
+            | &BlockExpression::Func(_, _, ref kind, _, _)
+            | &BlockExpression::Var(_, ref kind, _, _)
+            | &BlockExpression::Op(_, _, _, ref kind, _) => *kind,
+        }
+    }
+
+    /// if the expression is constant, or random
+    pub fn get_determinism(&self) -> Determinism {
+        match self {
+            &BlockExpression::FunctionArg(_, index, _, _) => Determinism::parameter(index),
+            &BlockExpression::ConstantValue(ref lit, _, _) => match lit {
+                &Literal::Bernoulli(_)
+                | &Literal::Categorical(_)
+                | &Literal::LoadDistribution(_) => Determinism::Random,
+                _ => Determinism::Constant,
+            },
+            &BlockExpression::ExternalConstant(_, _, ref determinism, _)
+            | &BlockExpression::Func(_, _, _, ref determinism, _)
+            | &BlockExpression::Var(_, _, ref determinism, _) => determinism.clone(),
+            &BlockExpression::Op(ref left, _, ref right, _, _) => {
+                left.get_determinism().join(&right.get_determinism())
+            }
+        }
+    }
+
+    /// returns the literal value of a constant scalar, these may be
+    /// folded into the expressions which read them.
+    pub fn get_scalar(&self) -> Option<&Literal<'a>> {
+        match self {
+            &BlockExpression::ConstantValue(ref lit @ Literal::Number(_), _, _)
+            | &BlockExpression::ConstantValue(ref lit @ Literal::Boolean(_), _, _) => Some(lit),
+            _ => None,
+        }
+    }
+
     /// constructs a new constant value from the block expression
     #[inline(always)]
-    pub fn lit(arg: &Literal<'a>) -> Result<BlockExpression<'a>, String> {
-        Ok(BlockExpression::ConstantValue(arg.clone(), arg.get_type()?))
+    pub fn lit(arg: &Literal<'a>, span: Span) -> Result<BlockExpression<'a>, Diagnostic> {
+        match arg.get_type() {
+            Ok(kind) => Ok(BlockExpression::ConstantValue(arg.clone(), kind, span)),
+            Err(e) => Err(Diagnostic::error(Code::InvalidLiteral, e).with_label(span, "invalid literal")),
+        }
     }
 
     /// constructs a new Var value from the arguments
     #[inline(always)]
-    pub fn var(arg: &'a str, kind: TypeData) -> Result<BlockExpression<'a>, String> {
-        Ok(BlockExpression::Var(arg, kind))
+    pub fn var(
+        arg: &'a str,
+        kind: TypeData,
+        determinism: Determinism,
+        span: Span,
+    ) -> Result<BlockExpression<'a>, Diagnostic> {
+        Ok(BlockExpression::Var(arg, kind, determinism, span))
     }
 
     #[inline(always)]
-    pub fn cons(arg: &'a str, kind: TypeData) -> Result<BlockExpression<'a>, String> {
-        Ok(BlockExpression::ExternalConstant(arg, kind))
+    pub fn cons(
+        arg: &'a str,
+        kind: TypeData,
+        determinism: Determinism,
+        span: Span,
+    ) -> Result<BlockExpression<'a>, Diagnostic> {
+        Ok(BlockExpression::ExternalConstant(arg, kind, determinism, span))
     }
 
     /// func builds a new instance of the function variant
@@ -57,30 +128,36 @@ impl<'a> BlockExpression<'a> {
         name: &'a str,
         args: Vec<BlockExpression<'a>>,
         ret: TypeData,
-    ) -> Result<BlockExpression<'a>, String> {
-        Ok(BlockExpression::Func(name, args.into_boxed_slice(), ret))
+        determinism: Determinism,
+        span: Span,
+    ) -> Result<BlockExpression<'a>, Diagnostic> {
+        Ok(BlockExpression::Func(
+            name,
+            args.into_boxed_slice(),
+            ret,
+            determinism,
+            span,
+        ))
     }
 
     pub fn op(
         left: BlockExpression<'a>,
         op: Operation,
         right: BlockExpression<'a>,
-    ) -> Result<BlockExpression<'a>, String> {
+        span: Span,
+    ) -> Result<BlockExpression<'a>, Diagnostic> {
         let typedata = match op {
             Operation::Sub | Operation::Mul | Operation::Div | Operation::Add => {
-                match (left.get_type()?, right.get_type()?) {
+                match (left.get_kind(), right.get_kind()) {
                     (TypeData::Int, TypeData::Int) => TypeData::Int,
                     (TypeData::Int, TypeData::CollectionOfInt) => TypeData::CollectionOfInt,
                     (TypeData::CollectionOfInt, TypeData::Int) => TypeData::CollectionOfInt,
                     (left_type, right_type) => {
-                        return Err(format!(
-                            "Type Error. Expression: ({} {} {}) is illegal. {} cannot {} with {}",
-                            left, op, right, left_type, op, right_type
-                        ))
+                        return Err(type_error(&left, op, &right, left_type, right_type, span))
                     }
                 }
             }
-            Operation::Equal | Operation::NotEqual => match (left.get_type()?, right.get_type()?) {
+            Operation::Equal | Operation::NotEqual => match (left.get_kind(), right.get_kind()) {
                 (TypeData::Int, TypeData::Int) => TypeData::Bool,
                 (TypeData::Bool, TypeData::Bool) => TypeData::Bool,
                 (TypeData::CollectionOfInt, TypeData::Int)
@@ -88,60 +165,115 @@ impl<'a> BlockExpression<'a> {
                 | (TypeData::Bool, TypeData::CollectionOfBool)
                 | (TypeData::CollectionOfBool, TypeData::Bool) => TypeData::CollectionOfBool,
                 (left_type, right_type) => {
-                    return Err(format!(
-                        "Type Error. Expression: ({} {} {}) is illegal. {} cannot {} with {}",
-                        left, op, right, left_type, op, right_type
-                    ))
+                    return Err(type_error(&left, op, &right, left_type, right_type, span))
                 }
             },
             Operation::GreaterThan
             | Operation::LessThan
             | Operation::GreaterThanEqual
-            | Operation::LessThanEqual => match (left.get_type()?, right.get_type()?) {
+            | Operation::LessThanEqual => match (left.get_kind(), right.get_kind()) {
                 (TypeData::Int, TypeData::Int) => TypeData::Bool,
                 (TypeData::Int, TypeData::CollectionOfInt) => TypeData::CollectionOfBool,
                 (TypeData::CollectionOfInt, TypeData::Int) => TypeData::CollectionOfBool,
                 (left_type, right_type) => {
-                    return Err(format!(
-                        "Type Error. Expression: ({} {} {}) is illegal. {} cannot {} with {}",
-                        left, op, right, left_type, op, right_type
-                    ))
+                    return Err(type_error(&left, op, &right, left_type, right_type, span))
                 }
             },
             Operation::Or | Operation::And => {
-                match (left.get_type()?, right.get_type()?) {
+                match (left.get_kind(), right.get_kind()) {
                     (TypeData::Bool, TypeData::Bool) => TypeData::Bool,
                     (TypeData::Bool, TypeData::CollectionOfBool) => TypeData::CollectionOfBool,
                     (TypeData::CollectionOfBool, TypeData::Bool) => TypeData::CollectionOfBool,
                     // TODO this sucks
                     (left_type, right_type) => {
-                        return Err(format!(
-                            "Type Error. Expression: ({} {} {}) is illegal. {} cannot {} with {}",
-                            left, op, right, left_type, op, right_type
-                        ))
+                        return Err(type_error(&left, op, &right, left_type, right_type, span))
                     }
                 }
             }
         };
-        Ok(BlockExpression::Op(
-            Box::new(left),
-            op,
-            Box::new(right),
-            typedata,
-        ))
+
+        // operations upon constant scalars are evaluated now, random
+        // literals such as `bernoulli` are never scalars.
+        let folded = match (left.get_scalar(), right.get_scalar()) {
+            (Option::Some(l), Option::Some(r)) => fold(l, op, r),
+            _ => None,
+        };
+        match folded {
+            Option::Some(lit) => Ok(BlockExpression::ConstantValue(lit, typedata, span)),
+            Option::None => Ok(BlockExpression::Op(
+                Box::new(left),
+                op,
+                Box::new(right),
+                typedata,
+                span,
+            )),
+        }
     }
 }
+
+/// evaluates an operation upon two scalars. Operations which
+/// would fail, such as division by zero, are left for the
+/// runtime to report.
+fn fold<'a>(left: &Literal<'a>, op: Operation, right: &Literal<'a>) -> Option<Literal<'a>> {
+    match (left, right) {
+        (&Literal::Number(l), &Literal::Number(r)) => match op {
+            Operation::Add => l.checked_add(r).map(Literal::Number),
+            Operation::Sub => l.checked_sub(r).map(Literal::Number),
+            Operation::Mul => l.checked_mul(r).map(Literal::Number),
+            Operation::Div if r != 0 => l.checked_div(r).map(Literal::Number),
+            Operation::Div => None,
+            Operation::Equal => Some(Literal::Boolean(l == r)),
+            Operation::NotEqual => Some(Literal::Boolean(l != r)),
+            Operation::GreaterThan => Some(Literal::Boolean(l > r)),
+            Operation::GreaterThanEqual => Some(Literal::Boolean(l >= r)),
+            Operation::LessThan => Some(Literal::Boolean(l < r)),
+            Operation::LessThanEqual => Some(Literal::Boolean(l <= r)),
+            Operation::And | Operation::Or => None,
+        },
+        (&Literal::Boolean(l), &Literal::Boolean(r)) => match op {
+            Operation::And => Some(Literal::Boolean(l & r)),
+            Operation::Or => Some(Literal::Boolean(l | r)),
+            Operation::Equal => Some(Literal::Boolean(l == r)),
+            Operation::NotEqual => Some(Literal::Boolean(l != r)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// builds the error for an operation whose arguments have the wrong types
+fn type_error<'a>(
+    left: &BlockExpression<'a>,
+    op: Operation,
+    right: &BlockExpression<'a>,
+    left_type: TypeData,
+    right_type: TypeData,
+    span: Span,
+) -> Diagnostic {
+    Diagnostic::error(
+        Code::InvalidOperation,
+        format!(
+        "Type Error. Expression: ({} {} {}) is illegal. {} cannot {} with {}",
+        left, op, right, left_type, op, right_type
+    ))
+    .with_label(
+        span,
+        format!("{} cannot {} with {}", left_type, op, right_type),
+    )
+    .with_secondary(left.get_span(), format!("this is {}", left_type))
+    .with_secondary(right.get_span(), format!("this is {}", right_type))
+}
 impl<'a> fmt::Display for BlockExpression<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            BlockExpression::ConstantValue(ref lit, _) => write!(f, "{}", lit),
-            BlockExpression::ExternalConstant(ref name, _) => write!(f, "{}", name),
-            BlockExpression::FunctionArg(ref name, _, _) => write!(f, "{}", name),
-            BlockExpression::Var(ref name, _) => write!(f, "{}", name),
-            BlockExpression::Op(ref left, ref op, ref right, _) => {
+            BlockExpression::ConstantValue(ref lit, _, _) => write!(f, "{}", lit),
+            BlockExpression::ExternalConstant(ref name, _, _, _) => write!(f, "{}", name),
+            BlockExpression::FunctionArg(ref name, _, _, _) => write!(f, "{}", name),
+            BlockExpression::Var(ref name, _, _, _) => write!(f, "{}", name),
+            BlockExpression::Op(ref left, ref op, ref right, _, _) => {
                 write!(f, "( {} {} {} )", left, op, right)
             }
-            BlockExpression::Func(ref name, ref args, _) => {
+            BlockExpression::Func(ref name, ref args, _, _, _) => {
                 write!(f, "{}(", name)?;
                 let last_arg = args.len() - 1;
                 for (index, arg) in args.iter().enumerate() {
@@ -163,64 +295,57 @@ impl<'a> GetType for BlockExpression<'a> {
 
     /// resolving the typing data for the block expression
     fn get_type(&self) -> Result<TypeData, String> {
-        match self {
-            BlockExpression::ConstantValue(_, kind) => Ok(kind.clone()),
-            BlockExpression::ExternalConstant(_, kind) => Ok(kind.clone()),
-            BlockExpression::FunctionArg(_, _, kind) => Ok(kind.clone()),
-            BlockExpression::Func(_, _, kind) => Ok(kind.clone()),
-            BlockExpression::Var(_, kind) => Ok(kind.clone()),
-            BlockExpression::Op(_, _, _, kind) => Ok(kind.clone()),
-        }
+        Ok(self.get_kind())
     }
 }
 
 #[test]
 fn test_type_assertions() {
     assert!(
-        BlockExpression::FunctionArg("foo", 0, TypeData::Int)
+        BlockExpression::FunctionArg("foo", 0, TypeData::Int, Span::default())
             .get_type()
             .unwrap()
             == TypeData::Int
     );
     assert!(
-        BlockExpression::FunctionArg("foo", 1, TypeData::Bool)
+        BlockExpression::FunctionArg("foo", 1, TypeData::Bool, Span::default())
             .get_type()
             .unwrap()
             == TypeData::Bool
     );
     assert!(
-        BlockExpression::FunctionArg("foo", 2, TypeData::CollectionOfInt)
+        BlockExpression::FunctionArg("foo", 2, TypeData::CollectionOfInt, Span::default())
             .get_type()
             .unwrap()
             == TypeData::CollectionOfInt
     );
     assert!(
-        BlockExpression::FunctionArg("foo", 3, TypeData::CollectionOfBool)
+        BlockExpression::FunctionArg("foo", 3, TypeData::CollectionOfBool, Span::default())
             .get_type()
             .unwrap()
             == TypeData::CollectionOfBool
     );
 
     assert!(
-        BlockExpression::ExternalConstant("foo", TypeData::Int)
+        BlockExpression::ExternalConstant("foo", TypeData::Int, Determinism::Constant, Span::default())
             .get_type()
             .unwrap()
             == TypeData::Int
     );
     assert!(
-        BlockExpression::ExternalConstant("foo", TypeData::Bool)
+        BlockExpression::ExternalConstant("foo", TypeData::Bool, Determinism::Constant, Span::default())
             .get_type()
             .unwrap()
             == TypeData::Bool
     );
     assert!(
-        BlockExpression::ExternalConstant("foo", TypeData::CollectionOfInt)
+        BlockExpression::ExternalConstant("foo", TypeData::CollectionOfInt, Determinism::Constant, Span::default())
             .get_type()
             .unwrap()
             == TypeData::CollectionOfInt
     );
     assert!(
-        BlockExpression::ExternalConstant("foo", TypeData::CollectionOfBool)
+        BlockExpression::ExternalConstant("foo", TypeData::CollectionOfBool, Determinism::Constant, Span::default())
             .get_type()
             .unwrap()
             == TypeData::CollectionOfBool
@@ -228,50 +353,50 @@ fn test_type_assertions() {
 
     // we only check the `TypeData` field. Creating these types outside of this is relatively illegal otherwise
     assert!(
-        BlockExpression::ConstantValue(Literal::Number(20), TypeData::Int)
+        BlockExpression::ConstantValue(Literal::Number(20), TypeData::Int, Span::default())
             .get_type()
             .unwrap()
             == TypeData::Int
     );
     assert!(
-        BlockExpression::ConstantValue(Literal::Number(20), TypeData::Bool)
+        BlockExpression::ConstantValue(Literal::Number(20), TypeData::Bool, Span::default())
             .get_type()
             .unwrap()
             == TypeData::Bool
     );
     assert!(
-        BlockExpression::ConstantValue(Literal::Number(20), TypeData::CollectionOfInt)
+        BlockExpression::ConstantValue(Literal::Number(20), TypeData::CollectionOfInt, Span::default())
             .get_type()
             .unwrap()
             == TypeData::CollectionOfInt
     );
     assert!(
-        BlockExpression::ConstantValue(Literal::Number(20), TypeData::CollectionOfBool)
+        BlockExpression::ConstantValue(Literal::Number(20), TypeData::CollectionOfBool, Span::default())
             .get_type()
             .unwrap()
             == TypeData::CollectionOfBool
     );
 
     assert!(
-        BlockExpression::Var("foo", TypeData::Int)
+        BlockExpression::Var("foo", TypeData::Int, Determinism::Constant, Span::default())
             .get_type()
             .unwrap()
             == TypeData::Int
     );
     assert!(
-        BlockExpression::Var("foo", TypeData::Bool)
+        BlockExpression::Var("foo", TypeData::Bool, Determinism::Constant, Span::default())
             .get_type()
             .unwrap()
             == TypeData::Bool
     );
     assert!(
-        BlockExpression::Var("foo", TypeData::CollectionOfInt)
+        BlockExpression::Var("foo", TypeData::CollectionOfInt, Determinism::Constant, Span::default())
             .get_type()
             .unwrap()
             == TypeData::CollectionOfInt
     );
     assert!(
-        BlockExpression::Var("foo", TypeData::CollectionOfBool)
+        BlockExpression::Var("foo", TypeData::CollectionOfBool, Determinism::Constant, Span::default())
             .get_type()
             .unwrap()
             == TypeData::CollectionOfBool
@@ -279,25 +404,25 @@ fn test_type_assertions() {
 
     let args = Vec::<_>::new().into_boxed_slice();
     assert!(
-        BlockExpression::Func("foo", args.clone(), TypeData::Int)
+        BlockExpression::Func("foo", args.clone(), TypeData::Int, Determinism::Random, Span::default())
             .get_type()
             .unwrap()
             == TypeData::Int
     );
     assert!(
-        BlockExpression::Func("foo", args.clone(), TypeData::Bool)
+        BlockExpression::Func("foo", args.clone(), TypeData::Bool, Determinism::Random, Span::default())
             .get_type()
             .unwrap()
             == TypeData::Bool
     );
     assert!(
-        BlockExpression::Func("foo", args.clone(), TypeData::CollectionOfInt)
+        BlockExpression::Func("foo", args.clone(), TypeData::CollectionOfInt, Determinism::Random, Span::default())
             .get_type()
             .unwrap()
             == TypeData::CollectionOfInt
     );
     assert!(
-        BlockExpression::Func("foo", args.clone(), TypeData::CollectionOfBool)
+        BlockExpression::Func("foo", args.clone(), TypeData::CollectionOfBool, Determinism::Random, Span::default())
             .get_type()
             .unwrap()
             == TypeData::CollectionOfBool
@@ -307,28 +432,29 @@ fn test_type_assertions() {
     let expr = Box::new(BlockExpression::ConstantValue(
         Literal::Number(6),
         TypeData::Int,
+        Span::default(),
     ));
     let op = Operation::Add;
     assert!(
-        BlockExpression::Op(expr.clone(), op, expr.clone(), TypeData::Int)
+        BlockExpression::Op(expr.clone(), op, expr.clone(), TypeData::Int, Span::default())
             .get_type()
             .unwrap()
             == TypeData::Int
     );
     assert!(
-        BlockExpression::Op(expr.clone(), op, expr.clone(), TypeData::Bool)
+        BlockExpression::Op(expr.clone(), op, expr.clone(), TypeData::Bool, Span::default())
             .get_type()
             .unwrap()
             == TypeData::Bool
     );
     assert!(
-        BlockExpression::Op(expr.clone(), op, expr.clone(), TypeData::CollectionOfInt)
+        BlockExpression::Op(expr.clone(), op, expr.clone(), TypeData::CollectionOfInt, Span::default())
             .get_type()
             .unwrap()
             == TypeData::CollectionOfInt
     );
     assert!(
-        BlockExpression::Op(expr.clone(), op, expr.clone(), TypeData::CollectionOfBool)
+        BlockExpression::Op(expr.clone(), op, expr.clone(), TypeData::CollectionOfBool, Span::default())
             .get_type()
             .unwrap()
             == TypeData::CollectionOfBool
@@ -0,0 +1,246 @@
+use std::collections::BTreeSet;
+
+use super::super::diagnostic::{Code, Diagnostic};
+use super::super::parser_output::{Expression, FunctionDeclaration, Span, Statement};
+
+use super::namespace::Namespace;
+
+/// Edge is a constant or function reading another
+#[derive(Clone, Copy)]
+struct Edge<'a> {
+    from: &'a str,
+    to: &'a str,
+    /// where `to` is written within `from`
+    span: Span,
+}
+
+/// definition_order returns the constants & user functions so each
+/// follows every definition it reads, or reports every constant &
+/// function which is defined in terms of itself, directly or through
+/// other definitions.
+///
+/// Each function is inlined where it is invoked, and each constant
+/// where it is read, so recursion could never terminate. Every
+/// cycle is reported as a chain, such as `a -> b -> a`.
+pub fn definition_order<'a>(namespace: &Namespace<'a>) -> Result<Vec<&'a str>, Vec<Diagnostic>> {
+    let mut nodes: Vec<(&'a str, Span)> = namespace
+        .get_all_constants()
+        .map(|(name, constant)| (*name, constant.span))
+        .chain(
+            namespace
+                .get_all_functions()
+                .filter(|(_, func)| !func.stdlib)
+                .map(|(name, func)| (*name, func.span)),
+        )
+        .collect();
+    nodes.sort_by_key(|(_, span)| span.start);
+
+    let mut search = Search {
+        namespace,
+        finished: BTreeSet::new(),
+        stack: Vec::new(),
+        order: Vec::new(),
+        output: Vec::new(),
+    };
+    for (name, _) in nodes {
+        search.visit(name);
+    }
+    if search.output.is_empty() {
+        Ok(search.order)
+    } else {
+        Err(search.output)
+    }
+}
+
+/// a depth first search, a cycle is an edge to a definition
+/// which is still upon the stack.
+struct Search<'a, 'b> {
+    namespace: &'b Namespace<'a>,
+    finished: BTreeSet<&'a str>,
+    /// the definitions being visited, and the edge which is
+    /// being followed out of each
+    stack: Vec<(&'a str, Option<Edge<'a>>)>,
+    /// definitions in the order they were finished
+    order: Vec<&'a str>,
+    output: Vec<Diagnostic>,
+}
+impl<'a, 'b> Search<'a, 'b> {
+    fn visit(&mut self, name: &'a str) {
+        if self.finished.contains(name) {
+            return;
+        }
+        self.stack.push((name, None));
+        for edge in edges(self.namespace, name) {
+            let top = self.stack.len() - 1;
+            self.stack[top].1 = Some(edge);
+            match self.stack.iter().position(|(on_stack, _)| *on_stack == edge.to) {
+                Option::Some(index) => {
+                    let chain: Vec<Edge<'a>> = self.stack[index..]
+                        .iter()
+                        .filter_map(|(_, edge)| *edge)
+                        .collect();
+                    self.output.push(report(self.namespace, &chain));
+                }
+                Option::None => self.visit(edge.to),
+            };
+        }
+        self.stack.pop();
+        self.finished.insert(name);
+        self.order.push(name);
+    }
+}
+
+/// the constants & user functions a definition reads, in the
+/// order they are written.
+fn edges<'a>(namespace: &Namespace<'a>, name: &'a str) -> Vec<Edge<'a>> {
+    let mut refs = Vec::new();
+    match (namespace.get_constant(name), namespace.get_function(name)) {
+        (Option::Some(constant), _) => references(&constant.expr, &mut refs),
+        (_, Option::Some(func)) => function_references(func, &mut refs),
+        _ => {}
+    };
+    refs.into_iter()
+        .filter(|(to, _)| match namespace.get_function(to) {
+            Option::Some(func) => !func.stdlib,
+            Option::None => namespace.get_constant(to).is_some(),
+        })
+        .map(|(to, span)| Edge {
+            from: name,
+            to,
+            span,
+        })
+        .collect()
+}
+
+/// the names a function reads which are not its parameters or
+/// `let` bindings.
+fn function_references<'a>(func: &FunctionDeclaration<'a>, refs: &mut Vec<(&'a str, Span)>) {
+    let mut declared = Vec::new();
+    for stmt in func.body.data.iter() {
+        let mut found = Vec::new();
+        match stmt {
+            &Statement::Variable(ref var) => references(&var.expr, &mut found),
+            &Statement::Return(ref term) => references(&term.expr, &mut found),
+        };
+        refs.extend(found.into_iter().filter(|(name, _)| {
+            !declared.contains(name) && !func.args.iter().any(|(arg, _)| arg == name)
+        }));
+        if let &Statement::Variable(ref var) = stmt {
+            declared.push(var.name);
+        }
+    }
+}
+
+/// collects the names an expression reads, and where each is written
+fn references<'a>(expr: &Expression<'a>, refs: &mut Vec<(&'a str, Span)>) {
+    match expr {
+        &Expression::Literal(_) => {}
+        &Expression::Variable(ref var) => refs.push((var.name, var.span)),
+        &Expression::Func(ref func) => {
+            let span = Span::new(func.span.start, func.span.start + func.name.len());
+            refs.push((func.name, span));
+            for arg in func.args.iter() {
+                references(arg, refs);
+            }
+        }
+        &Expression::Operation(ref op) => {
+            references(&op.left, refs);
+            references(&op.right, refs);
+        }
+    }
+}
+
+fn report<'a>(namespace: &Namespace<'a>, chain: &[Edge<'a>]) -> Diagnostic {
+    let mut names: Vec<&str> = chain.iter().map(|edge| edge.from).collect();
+    names.push(chain[0].from);
+    let path = names.join(" -> ");
+    let is_function = |name: &str| namespace.get_function(name).is_some();
+
+    let first = chain[0];
+    let message = if chain.len() == 1 && is_function(first.from) {
+        format!("function '{}' invokes itself: {}", first.from, path)
+    } else if chain.len() == 1 {
+        format!("constant '{}' is defined in terms of itself: {}", first.from, path)
+    } else {
+        format!("'{}' is defined in terms of itself: {}", first.from, path)
+    };
+    let mut diagnostic = Diagnostic::error(Code::RecursiveDefinition, message)
+        .with_label(first.span, uses(first, is_function(first.to)));
+    for edge in chain[1..].iter() {
+        diagnostic = diagnostic.with_secondary(edge.span, uses(*edge, is_function(edge.to)));
+    }
+    if chain.iter().any(|edge| is_function(edge.from)) {
+        diagnostic.with_note(
+            "functions are inlined where they are invoked, so recursion is not supported",
+        )
+    } else {
+        diagnostic.with_note("a constant cannot depend upon its own value")
+    }
+}
+
+fn uses(edge: Edge, is_function: bool) -> String {
+    if is_function {
+        format!("'{}' invokes '{}'", edge.from, edge.to)
+    } else {
+        format!("'{}' reads '{}'", edge.from, edge.to)
+    }
+}
+
+#[test]
+fn test_cycles() {
+    use super::super::parser_output::AbstractSyntaxTree;
+
+    let error = |source: &str| -> Diagnostic {
+        let ast = AbstractSyntaxTree::parse(source).unwrap();
+        match Namespace::new(&ast) {
+            Ok(_) => panic!("{} should not build", source),
+            Err(e) => e,
+        }
+    };
+
+    let e = error("fn f(x: int) -> int {\n    return f((x - 1));\n}\nanalyze f(3);");
+    assert_eq!(e.get_code(), Code::RecursiveDefinition);
+    assert_eq!(e.get_message(), "function 'f' invokes itself: f -> f");
+    assert_eq!(e.get_labels()[0].span.start, 33);
+
+    let source = "
+fn even(x: int) -> int {
+    let y = odd(x);
+    return y;
+}
+fn odd(x: int) -> int {
+    return even(x);
+}
+analyze even(2);
+";
+    let e = error(source);
+    assert_eq!(e.get_message(), "'even' is defined in terms of itself: even -> odd -> even");
+    assert_eq!(e.get_labels().len(), 2);
+    assert_eq!(e.get_labels()[0].span.start, source.find("odd(x)").unwrap());
+    assert_eq!(e.get_labels()[1].span.start, source.find("even(x)").unwrap());
+
+    let source = "const a: int = (b + 1);\nconst b = (a * 2);\nanalyze a;";
+    let e = error(source);
+    assert_eq!(e.get_message(), "'a' is defined in terms of itself: a -> b -> a");
+    assert_eq!(e.get_labels()[0].span.start, 16);
+    let e = error("const a = (a + 1);\nanalyze a;");
+    assert_eq!(e.get_message(), "constant 'a' is defined in terms of itself: a -> a");
+
+    // a constant & a function may form a cycle
+    let e = error("const a = f();\nfn f() -> int {\n    return a;\n}\nanalyze a;");
+    assert_eq!(e.get_message(), "'a' is defined in terms of itself: a -> f -> a");
+
+    // a binding shadows the constant it names
+    let source = "
+const total = g();
+fn g() -> int {
+    let total = 3;
+    return total;
+}
+analyze total;
+";
+    let ast = AbstractSyntaxTree::parse(source).unwrap();
+    let namespace = Namespace::new(&ast).unwrap();
+    // definitions follow the definitions they read
+    assert_eq!(definition_order(&namespace), Ok(vec!["g", "total"]));
+}
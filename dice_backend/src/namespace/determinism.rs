@@ -0,0 +1,90 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Determinism qualifies the type of an expression. A constant
+/// has the same value every time the program is evaluated, while
+/// a random value is a variable of a distribution.
+///
+/// Within a function an expression may be constant only when the
+/// arguments it reads are, which is decided where it is invoked.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Default)]
+pub enum Determinism {
+    #[default]
+    Constant,
+    /// constant when every one of these parameters is constant
+    Parameters(BTreeSet<usize>),
+    Random,
+}
+impl Determinism {
+    /// the determinism of reading a function's parameter
+    pub fn parameter(index: usize) -> Determinism {
+        let mut set = BTreeSet::new();
+        set.insert(index);
+        Determinism::Parameters(set)
+    }
+
+    /// the determinism of a value computed from both `self` & `other`
+    pub fn join(&self, other: &Determinism) -> Determinism {
+        match (self, other) {
+            (&Determinism::Random, _) | (_, &Determinism::Random) => Determinism::Random,
+            (&Determinism::Constant, x) | (x, &Determinism::Constant) => x.clone(),
+            (&Determinism::Parameters(ref a), &Determinism::Parameters(ref b)) => {
+                Determinism::Parameters(a.union(b).cloned().collect())
+            }
+        }
+    }
+
+    /// the determinism of a function's result, given its arguments
+    pub fn substitute(&self, args: &[Determinism]) -> Determinism {
+        match self {
+            &Determinism::Parameters(ref set) => set
+                .iter()
+                .filter_map(|index| args.get(*index))
+                .fold(Determinism::Constant, |acc, arg| acc.join(arg)),
+            x => x.clone(),
+        }
+    }
+
+    pub fn is_constant(&self) -> bool {
+        *self == Determinism::Constant
+    }
+
+    pub fn is_random(&self) -> bool {
+        *self == Determinism::Random
+    }
+}
+impl fmt::Display for Determinism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            &Determinism::Constant => write!(f, "constant"),
+            &Determinism::Parameters(_) => write!(f, "constant when its arguments are"),
+            &Determinism::Random => write!(f, "random"),
+        }
+    }
+}
+
+#[test]
+fn test_determinism() {
+    let a = Determinism::parameter(0);
+    let b = Determinism::parameter(2);
+    assert_eq!(Determinism::Constant.join(&a), a);
+    assert_eq!(a.join(&Determinism::Random), Determinism::Random);
+    let both = a.join(&b);
+    assert_eq!(
+        both,
+        Determinism::Parameters(vec![0, 2].into_iter().collect())
+    );
+
+    let args = [
+        Determinism::Constant,
+        Determinism::Random,
+        Determinism::Constant,
+    ];
+    assert_eq!(both.substitute(&args), Determinism::Constant);
+    assert_eq!(
+        Determinism::parameter(1).substitute(&args),
+        Determinism::Random
+    );
+    assert_eq!(Determinism::Random.substitute(&[]), Determinism::Random);
+}
@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::path::Path;
+
+use super::super::parser_output::Rational;
+
+/// reads a distribution from a CSV file.
+///
+/// Each line holds a `value,probability` pair. Blank lines, lines
+/// starting with `#`, and a header on the first line are ignored.
+pub fn load_distribution(path: &Path) -> Result<Box<[(i8, Rational)]>, String> {
+    let mut file = match OpenOptions::new().read(true).open(path) {
+        Ok(file) => file,
+        Err(e) => {
+            return Err(format!(
+                "failed to open distribution file={:?} with error={:?}",
+                path, e
+            ))
+        }
+    };
+    let mut data = String::with_capacity(4096);
+    match file.read_to_string(&mut data) {
+        Ok(_) => {}
+        Err(e) => {
+            return Err(format!(
+                "failed to read distribution file={:?} with error={:?}",
+                path, e
+            ))
+        }
+    };
+    parse_distribution(&path.display().to_string(), &data)
+}
+
+/// parses the contents of a distribution file, `name` is only
+/// used to report errors.
+pub fn parse_distribution(name: &str, data: &str) -> Result<Box<[(i8, Rational)]>, String> {
+    let mut seen = BTreeMap::<i8, usize>::new();
+    let mut output = Vec::new();
+    let mut total = Rational::zero();
+    for (index, line) in data.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+        if fields.len() != 2 {
+            return Err(format!(
+                "{}:{}: expected 'value,probability' found '{}'",
+                name, line_number, line
+            ));
+        }
+        let value = match fields[0].parse::<i8>() {
+            Ok(value) => value,
+            Err(_) if line_number == 1 => {
+                // allow a header
+                continue;
+            }
+            Err(_) => {
+                return Err(format!(
+                    "{}:{}: value '{}' is not an int",
+                    name, line_number, fields[0]
+                ))
+            }
+        };
+        let prob = match Rational::parse(fields[1]) {
+            Ok(prob) => prob,
+            Err(_) => {
+                return Err(format!(
+                    "{}:{}: probability '{}' is not a non-negative number",
+                    name, line_number, fields[1]
+                ))
+            }
+        };
+        match seen.insert(value, line_number) {
+            Option::Some(previous) => {
+                return Err(format!(
+                    "{}:{}: value {} was already listed on line {}",
+                    name, line_number, value, previous
+                ))
+            }
+            Option::None => {}
+        };
+        total = match total.checked_add(&prob) {
+            Option::Some(total) => total,
+            Option::None => {
+                return Err(format!(
+                    "{}:{}: probability '{}' is too precise to sum exactly",
+                    name, line_number, fields[1]
+                ))
+            }
+        };
+        if total > Rational::one() {
+            return Err(format!(
+                "{}:{}: probabilities sum to {}, which is greater than 1",
+                name, line_number, total
+            ));
+        }
+        output.push((value, prob));
+    }
+    if !total.is_one() {
+        return Err(format!(
+            "{}: probabilities sum to {}, expected 1",
+            name, total
+        ));
+    }
+    Ok(output.into_boxed_slice())
+}
+
+#[test]
+fn test_parse_distribution() {
+    let dist = parse_distribution(
+        "hits.csv",
+        "value,probability\n# measured at regionals\n0, 0.5\n1, 1/4\n\n2, 0.25\n",
+    )
+    .unwrap();
+    assert_eq!(
+        dist.as_ref(),
+        &[
+            (0, Rational::new(1, 2)),
+            (1, Rational::new(1, 4)),
+            (2, Rational::new(1, 4))
+        ]
+    );
+
+    // errors point at the line in question
+    let err = parse_distribution("hits.csv", "0,0.5\n1,half\n").unwrap_err();
+    assert!(err.starts_with("hits.csv:2:"), "{}", err);
+    let err = parse_distribution("hits.csv", "0,0.5\n0,0.5\n").unwrap_err();
+    assert!(err.starts_with("hits.csv:2:"), "{}", err);
+    let err = parse_distribution("hits.csv", "0,0.5\n1,0.25\n2,0.5\n").unwrap_err();
+    assert!(err.starts_with("hits.csv:3:"), "{}", err);
+    let err = parse_distribution("hits.csv", "0,0.5\n1,0.25\n").unwrap_err();
+    assert!(err.contains("sum to 3/4"), "{}", err);
+}
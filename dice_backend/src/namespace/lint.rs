@@ -0,0 +1,455 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::super::diagnostic::{Code, Diagnostic};
+use super::super::parser_output::{allows, Expression, FunctionDeclaration, Span, Statement};
+
+use super::blockexpression::BlockExpression;
+use super::namespace::Namespace;
+use super::suggest::did_you_mean;
+
+/// the groups `#[allow(..)]` accepts, besides the name of each lint
+const LINT_GROUPS: &[&str] = &["unused", "warnings"];
+
+/// lint reports items which never affect the `analyze` result,
+/// and collections whose length is random.
+///
+/// Each warning can be suppressed by an `#[allow(..)]` attribute
+/// upon the item, attributes upon a function also apply to its
+/// parameters & variables.
+pub fn lint<'a>(namespace: &Namespace<'a>) -> Vec<Diagnostic> {
+    let mut used = Used::default();
+    if let Option::Some(analysis) = namespace.get_analysis() {
+        used.visit_global(namespace, &analysis.expr);
+    }
+
+    let mut warnings = Vec::new();
+    let mut constants: Vec<_> = namespace.get_all_constants().map(|(_, c)| c).collect();
+    constants.sort_by_key(|constant| constant.span.start);
+    for constant in constants {
+        unknown_lints(&constant.allow, constant.span, &mut warnings);
+        if !constant.allows("random_length") {
+            for expr in namespace.get_constant_value(constant.name).iter() {
+                random_length(namespace, expr, &mut warnings);
+            }
+        }
+        if !used.constants.contains(constant.name) && !constant.allows("unused_const") {
+            warnings.push(
+                Diagnostic::warning(
+                    Code::UnusedConstant,
+                    format!("constant '{}' is never used", constant.name),
+                )
+                .with_label(constant.span, "does not affect the analysis")
+                .with_help("remove it, or add `#[allow(unused_const)]`"),
+            );
+        }
+    }
+
+    let mut functions: Vec<_> = namespace
+        .get_all_functions()
+        .map(|(_, f)| f)
+        .filter(|f| !f.stdlib)
+        .collect();
+    functions.sort_by_key(|func| func.span.start);
+    for func in functions {
+        unknown_lints(&func.allow, func.span, &mut warnings);
+        if !used.functions.contains(func.name) && !func.allows("unused_function") {
+            warnings.push(
+                Diagnostic::warning(
+                    Code::UnusedFunction,
+                    format!("function '{}' is never used", func.name),
+                )
+                .with_label(func.span, "does not affect the analysis")
+                .with_help("remove it, or add `#[allow(unused_function)]`"),
+            );
+        }
+        lint_function(namespace, func, &mut warnings);
+    }
+    let allowed = match namespace.get_analysis() {
+        &Option::Some(ref analysis) => {
+            unknown_lints(&analysis.allow, analysis.span, &mut warnings);
+            analysis.allows("random_length")
+        }
+        &Option::None => false,
+    };
+    if let Option::Some(block) = namespace.get_own_block() {
+        for expr in block.get_return().iter().filter(|_| !allowed) {
+            random_length(namespace, expr, &mut warnings);
+        }
+    }
+    warnings
+}
+
+/// reports the names within an `#[allow(..)]` which are neither a
+/// lint nor a group.
+fn unknown_lints(allow: &[&str], span: Span, warnings: &mut Vec<Diagnostic>) {
+    if allows(allow, "unknown_lint") {
+        return;
+    }
+    let known: Vec<&str> = Code::warnings()
+        .iter()
+        .filter_map(|code| code.get_lint_name())
+        .chain(LINT_GROUPS.iter().cloned())
+        .collect();
+    for name in allow.iter().filter(|name| !known.contains(name)) {
+        let diagnostic = Diagnostic::warning(Code::UnknownLint, format!("unknown lint '{}'", name))
+            .with_label(span, "within the attributes of this item")
+            .with_note(format!("the known lints are {}", known.join(", ")));
+        warnings.push(did_you_mean(diagnostic, name, known.iter().cloned()));
+    }
+}
+
+/// reports the dice & cards drawn a random number of times, the
+/// result is a distribution over collections of every length.
+fn random_length<'a>(
+    namespace: &Namespace<'a>,
+    expr: &BlockExpression<'a>,
+    warnings: &mut Vec<Diagnostic>,
+) {
+    match expr {
+        &BlockExpression::Func(name, ref args, _, _, _) => {
+            let length = namespace
+                .get_std_function(name)
+                .and_then(|func| func.length_arg());
+            match length.and_then(|index| args.get(index)) {
+                Option::Some(arg) if arg.get_determinism().is_random() => {
+                    warnings.push(
+                        Diagnostic::warning(
+                            Code::RandomLength,
+                            format!("'{}' produces a collection of random length", expr),
+                        )
+                        .with_label(arg.get_span(), "this number is random")
+                        .with_help("add `#[allow(random_length)]` if this is intended"),
+                    );
+                }
+                _ => {}
+            };
+            for arg in args.iter() {
+                random_length(namespace, arg, warnings);
+            }
+        }
+        &BlockExpression::Op(ref left, _, ref right, _, _) => {
+            random_length(namespace, left, warnings);
+            random_length(namespace, right, warnings);
+        }
+        _ => {}
+    }
+}
+
+/// reports unused parameters & variables, and variables which
+/// shadow constants.
+fn lint_function<'a>(
+    namespace: &Namespace<'a>,
+    func: &FunctionDeclaration<'a>,
+    warnings: &mut Vec<Diagnostic>,
+) {
+    let live = Liveness::new(func);
+    for (index, stmt) in func.body.data.iter().enumerate() {
+        let var = match stmt {
+            &Statement::Variable(ref var) => var,
+            &Statement::Return(_) => continue,
+        };
+        if !func.allows("unknown_lint") {
+            unknown_lints(&var.allow, var.span, warnings);
+        }
+        let allows = |lint: &str| var.allows(lint) || func.allows(lint);
+        if !live.variables.contains(&index) && !allows("unused_variable") {
+            warnings.push(
+                Diagnostic::warning(
+                    Code::UnusedVariable,
+                    format!("variable '{}' is never used", var.name),
+                )
+                .with_label(var.span, "does not affect the return value")
+                .with_help("remove it, or add `#[allow(unused_variable)]`"),
+            );
+        }
+        if let Option::Some(constant) = namespace.get_constant(var.name) {
+            if !allows("shadowed_const") {
+                warnings.push(
+                    Diagnostic::warning(
+                        Code::ShadowedConstant,
+                        format!("variable '{}' shadows a constant", var.name),
+                    )
+                    .with_label(var.span, "shadows the constant")
+                    .with_secondary(constant.span, "constant declared here")
+                    .with_help("rename the variable, or add `#[allow(shadowed_const)]`"),
+                );
+            }
+        }
+    }
+    if !func.allows("random_length") {
+        let block = namespace.get_function_block(func.name);
+        let mut exprs: Vec<&BlockExpression<'a>> = block
+            .iter()
+            .flat_map(|block| block.get_vars().map(|(_, expr)| expr).chain(block.get_return()))
+            .collect();
+        exprs.sort_by_key(|expr| expr.get_span().start);
+        for expr in exprs {
+            random_length(namespace, expr, warnings);
+        }
+    }
+    for (name, _) in func.args.iter() {
+        if !live.parameters.contains(name) && !func.allows("unused_parameter") {
+            warnings.push(
+                Diagnostic::warning(
+                    Code::UnusedParameter,
+                    format!("parameter '{}' of function '{}' is never used", name, func.name),
+                )
+                .with_label(func.span, format!("'{}' is never read", name))
+                .with_help("remove it, or add `#[allow(unused_parameter)]`"),
+            );
+        }
+    }
+}
+
+/// every constant & function which affects the analysis
+#[derive(Default)]
+struct Used<'a> {
+    constants: BTreeSet<&'a str>,
+    functions: BTreeSet<&'a str>,
+}
+impl<'a> Used<'a> {
+    /// visits an expression outside of any function
+    fn visit_global(&mut self, namespace: &Namespace<'a>, expr: &Expression<'a>) {
+        let mut names = Vec::new();
+        let mut calls = Vec::new();
+        references(expr, &mut names, &mut calls);
+        for name in names {
+            self.visit_constant(namespace, name);
+        }
+        for name in calls {
+            self.visit_function(namespace, name);
+        }
+    }
+
+    fn visit_constant(&mut self, namespace: &Namespace<'a>, name: &'a str) {
+        if let Option::Some(constant) = namespace.get_constant(name) {
+            if self.constants.insert(name) {
+                self.visit_global(namespace, &constant.expr);
+            }
+        }
+    }
+
+    fn visit_function(&mut self, namespace: &Namespace<'a>, name: &'a str) {
+        let func = match namespace.get_function(name) {
+            Option::Some(func) if !func.stdlib => func,
+            _ => return,
+        };
+        if !self.functions.insert(name) {
+            return;
+        }
+        let live = Liveness::new(func);
+        for name in live.globals {
+            self.visit_constant(namespace, name);
+        }
+        for name in live.calls {
+            self.visit_function(namespace, name);
+        }
+    }
+}
+
+/// which parts of a function affect its return value
+struct Liveness<'a> {
+    /// indexes of the `let` statements which are read
+    variables: BTreeSet<usize>,
+    parameters: BTreeSet<&'a str>,
+    /// names which are neither parameters nor variables
+    globals: BTreeSet<&'a str>,
+    calls: BTreeSet<&'a str>,
+}
+impl<'a> Liveness<'a> {
+    fn new(func: &FunctionDeclaration<'a>) -> Liveness<'a> {
+        let mut live = Liveness {
+            variables: BTreeSet::new(),
+            parameters: BTreeSet::new(),
+            globals: BTreeSet::new(),
+            calls: BTreeSet::new(),
+        };
+        let mut declared = BTreeMap::<&'a str, usize>::new();
+        for (index, stmt) in func.body.data.iter().enumerate() {
+            if let &Statement::Variable(ref var) = stmt {
+                declared.insert(var.name, index);
+            }
+        }
+
+        // a variable is only visible to the statements after it
+        let mut pending: Vec<usize> = func
+            .body
+            .data
+            .iter()
+            .enumerate()
+            .filter(|(_, stmt)| match stmt {
+                &&Statement::Return(_) => true,
+                _ => false,
+            })
+            .map(|(index, _)| index)
+            .collect();
+        while let Option::Some(index) = pending.pop() {
+            let expr = match &func.body.data[index] {
+                &Statement::Variable(ref var) => &var.expr,
+                &Statement::Return(ref term) => &term.expr,
+            };
+            let mut names = Vec::new();
+            let mut calls = Vec::new();
+            references(expr, &mut names, &mut calls);
+            live.calls.extend(calls);
+            for name in names {
+                match declared.get(name) {
+                    Option::Some(var_index) if *var_index < index => {
+                        if live.variables.insert(*var_index) {
+                            pending.push(*var_index);
+                        }
+                    }
+                    _ => {
+                        if func.args.iter().any(|(arg, _)| *arg == name) {
+                            live.parameters.insert(name);
+                        } else {
+                            live.globals.insert(name);
+                        }
+                    }
+                };
+            }
+        }
+        live
+    }
+}
+
+/// collects the names of the variables & functions an expression reads
+fn references<'a>(expr: &Expression<'a>, names: &mut Vec<&'a str>, calls: &mut Vec<&'a str>) {
+    match expr {
+        &Expression::Literal(_) => {}
+        &Expression::Variable(ref var) => names.push(var.name),
+        &Expression::Func(ref func) => {
+            calls.push(func.name);
+            for arg in func.args.iter() {
+                references(arg, names, calls);
+            }
+        }
+        &Expression::Operation(ref op) => {
+            references(&op.left, names, calls);
+            references(&op.right, names, calls);
+        }
+    }
+}
+
+#[test]
+fn test_lints() {
+    use super::super::parser_output::AbstractSyntaxTree;
+
+    let codes = |source: &str| -> Vec<(Code, String)> {
+        let ast = AbstractSyntaxTree::parse(source).unwrap();
+        let namespace = Namespace::new(&ast).unwrap();
+        lint(&namespace)
+            .into_iter()
+            .map(|w| (w.get_code(), w.get_message().to_string()))
+            .collect()
+    };
+
+    let source = r#"
+const USED: int = 1;
+const UNUSED: int = 2;
+const ONLY_IN_DEAD_LET: int = 3;
+fn unused_fn(x: int) -> int {
+    return x;
+}
+fn half(x: int, ignored: int) -> int {
+    let dead: int = ONLY_IN_DEAD_LET;
+    let USED: int = (x / 2);
+    return USED;
+}
+analyze (half(4, 0) + USED);
+"#;
+    let warnings = codes(source);
+    let found: Vec<Code> = warnings.iter().map(|(code, _)| *code).collect();
+    assert_eq!(
+        found,
+        vec![
+            Code::UnusedConstant,
+            Code::UnusedConstant,
+            Code::UnusedFunction,
+            Code::UnusedVariable,
+            Code::ShadowedConstant,
+            Code::UnusedParameter,
+        ],
+        "{:?}",
+        warnings
+    );
+    assert!(warnings[0].1.contains("UNUSED"));
+    assert!(warnings[1].1.contains("ONLY_IN_DEAD_LET"));
+    assert!(warnings[5].1.contains("ignored"));
+
+    // every warning can be allowed
+    let source = r#"
+#[allow(unused_const)]
+const UNUSED: int = 2;
+#[allow(unused_function, unused_parameter)]
+fn unused_fn(x: int) -> int {
+    #[allow(unused)]
+    let dead: int = 1;
+    return 1;
+}
+const USED: int = 1;
+fn shadow() -> int {
+    #[allow(shadowed_const)]
+    let USED: int = 2;
+    return USED;
+}
+analyze (USED + shadow());
+"#;
+    assert_eq!(codes(source), vec![]);
+
+    // a pool whose size is itself rolled
+    let warnings = codes("analyze sum(roll_d6(sum(roll_d3(1))));");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].0, Code::RandomLength);
+    assert!(warnings[0].1.starts_with("'roll_d6(sum(roll_d3(1)))'"));
+    let source = r#"
+fn pool(n: int) -> int {
+    return sum(roll_d6(n));
+}
+#[allow(random_length)]
+const RANDOM: int = sum(roll_d6(sum(roll_d3(1))));
+analyze (pool(3) + RANDOM);
+"#;
+    assert_eq!(codes(source), vec![]);
+    let source = "#[allow(random_length)]\nanalyze sum(roll_d6(sum(roll_d3(1))));";
+    assert_eq!(codes(source), vec![]);
+
+    // names which are not a lint are reported, unless allowed
+    let source = r#"
+#[allow(unused_cosnt)]
+const UNUSED: int = 2;
+fn double(x: int) -> int {
+    #[allow(frobnicate)]
+    let y: int = (x * 2);
+    return y;
+}
+#[allow(unknown_lint)]
+fn triple(x: int) -> int {
+    #[allow(frobnicate)]
+    let y: int = (x * 3);
+    return y;
+}
+#[allow(random_lenght)]
+analyze (double(1) + triple(1));
+"#;
+    let ast = AbstractSyntaxTree::parse(source).unwrap();
+    let warnings = lint(&Namespace::new(&ast).unwrap());
+    let found: Vec<Code> = warnings.iter().map(|w| w.get_code()).collect();
+    assert_eq!(
+        found,
+        vec![
+            Code::UnknownLint,
+            Code::UnusedConstant,
+            Code::UnknownLint,
+            Code::UnknownLint
+        ],
+        "{:?}",
+        warnings
+    );
+    assert_eq!(warnings[0].get_message(), "unknown lint 'unused_cosnt'");
+    assert_eq!(warnings[0].get_help(), &["did you mean `unused_const`?".to_string()]);
+    assert!(warnings[2].get_message().contains("frobnicate"));
+    assert!(warnings[2].get_help().is_empty());
+    let note = &warnings[3].get_notes()[0];
+    assert!(note.contains("random_length, unknown_lint, unused, warnings"), "{}", note);
+}
@@ -5,12 +5,21 @@ mod block;
 pub use self::block::BasicBlock;
 mod blockexpression;
 pub use self::blockexpression::BlockExpression;
+mod cycles;
+mod determinism;
+pub use self::determinism::Determinism;
+mod distribution;
+mod bindings;
+pub use self::bindings::Bindings;
 mod namespace;
 pub use self::namespace::Namespace;
+mod lint;
+pub use self::lint::lint;
+mod suggest;
 
 #[test]
 fn test_complex_example() {
-    use super::parser_output::{AbstractSyntaxTree, Expression, FunctionInvocation, TypeData};
+    use super::parser_output::{AbstractSyntaxTree, Expression};
     let trivial_program = r#"
 
 const FIVE: int = 5;
@@ -63,8 +72,8 @@ fn test_simple_stuff() {
         Ok(ast) => ast,
         Err(e) => panic!("ast error: {:?}", e),
     };
-    let namespace = match Namespace::new(&ast) {
-        Ok(namespace) => namespace,
+    match Namespace::new(&ast) {
+        Ok(_) => {}
         Err(e) => panic!("namespace error: {:?}", e),
     };
 }
@@ -127,3 +136,124 @@ analyze roll_d6(value);
     // assert an analysis exists
     assert!(namespace.get_analysis().is_some());
 }
+
+#[test]
+fn test_type_inference() {
+    use super::diagnostic::Code;
+    use super::parser_output::{AbstractSyntaxTree, TypeData};
+    let build = |source: &str| -> Result<TypeData, Code> {
+        let ast = AbstractSyntaxTree::parse(source).unwrap();
+        let namespace = Namespace::new(&ast).map_err(|e| e.get_code())?;
+        Ok(namespace.get_constant_type("total").unwrap())
+    };
+
+    // a constant may be inferred from a constant declared after it
+    let source = "
+const total = sum(dice);
+const dice = roll_d6(number);
+const number: int = 3;
+fn double(x: int) -> int {
+    let y = (x * 2);
+    return y;
+}
+analyze double(total);
+";
+    assert_eq!(build(source), Ok(TypeData::Int));
+    assert_eq!(
+        build("const total = (roll_d6(2) > 3);\nanalyze count(total);"),
+        Ok(TypeData::CollectionOfBool)
+    );
+
+    // annotations are still checked
+    assert_eq!(
+        build("const total: int = roll_d6(2);\nanalyze total;"),
+        Err(Code::TypeMismatch)
+    );
+    assert_eq!(build("fn f() -> int {\n    let y: bool = 1;\n    return 1;\n}\nconst total = f();\nanalyze total;"), Err(Code::TypeMismatch));
+    // inferred types are checked where they are used
+    assert_eq!(
+        build("const total = roll_d6(2);\nanalyze (total & true);"),
+        Err(Code::InvalidOperation)
+    );
+    assert_eq!(
+        build("const total = (total + 1);\nanalyze total;"),
+        Err(Code::RecursiveDefinition)
+    );
+}
+
+#[test]
+fn test_determinism_inference() {
+    use super::diagnostic::Code;
+    use super::parser_output::AbstractSyntaxTree;
+    let build = |source: &str| -> Result<Determinism, Code> {
+        let ast = AbstractSyntaxTree::parse(source).unwrap();
+        let namespace = Namespace::new(&ast).map_err(|e| e.get_code())?;
+        Ok(namespace.get_constant_determinism("total"))
+    };
+
+    assert_eq!(build("const total = (2 * 3);\nanalyze total;"), Ok(Determinism::Constant));
+    assert_eq!(build("const total = sum(roll_d6(2));\nanalyze total;"), Ok(Determinism::Random));
+    assert_eq!(build("const total = bernoulli(1/2);\nanalyze total;"), Ok(Determinism::Random));
+
+    // a function is only random when its arguments or body are
+    let source = "
+fn double(x: int) -> int {
+    let y = (x * 2);
+    return y;
+}
+const total = double(FACES);
+const FACES = 4;
+analyze sum(roll(total, 1, 2));
+";
+    assert_eq!(build(source), Ok(Determinism::Constant));
+    assert_eq!(
+        build("fn d() -> int {\n    return sum(roll_d3(1));\n}\nconst total = (d() + 1);\nanalyze total;"),
+        Ok(Determinism::Random)
+    );
+
+    // the faces of a die must be constant
+    assert_eq!(
+        build("const total = sum(roll(sum(roll_d6(1)), 1, 2));\nanalyze total;"),
+        Err(Code::RandomArgument)
+    );
+    let source = "
+fn die(faces: int) -> int {
+    return sum(roll(faces, 1, 1));
+}
+fn wrapper(x: int) -> int {
+    return die(x);
+}
+const total = wrapper(sum(roll_d3(1)));
+analyze total;
+";
+    let ast = AbstractSyntaxTree::parse(source).unwrap();
+    let e = match Namespace::new(&ast) {
+        Ok(_) => panic!("random faces should not build"),
+        Err(e) => e,
+    };
+    assert_eq!(e.get_code(), Code::RandomArgument);
+    assert!(e.get_message().starts_with("argument 1 'x' to function 'wrapper'"), "{}", e);
+    assert_eq!(e.get_labels()[0].span.start, source.find("sum(roll_d3").unwrap());
+    assert_eq!(e.get_labels()[1].span.start, source.find("faces, 1").unwrap());
+}
+
+#[test]
+fn test_constant_folding() {
+    use super::parser_output::AbstractSyntaxTree;
+    // the analysis, when it was folded into a literal
+    let folded = |source: &str| -> Option<String> {
+        let ast = AbstractSyntaxTree::parse(source).unwrap();
+        let namespace = Namespace::new(&ast).unwrap();
+        match namespace.get_own_block().as_ref().unwrap().get_return() {
+            &Option::Some(ref lit @ BlockExpression::ConstantValue(_, _, _)) => Some(lit.to_string()),
+            _ => None,
+        }
+    };
+    assert_eq!(folded("analyze ((1 + 2) * 4);"), Some("12".to_string()));
+    assert_eq!(folded("analyze ((1 + 2) > 4);"), Some("false".to_string()));
+
+    // random literals, division by zero & overflow are left alone
+    assert_eq!(folded("analyze (bernoulli(1/2) & true);"), None);
+    assert_eq!(folded("analyze (1 / 0);"), None);
+    assert_eq!(folded("analyze (100 * 100);"), None);
+}
@@ -1,15 +1,23 @@
+use std::cell::RefCell;
 use std::collections::hash_map::Iter;
-use std::collections::{BTreeMap, HashMap, HashSet};
-use std::hash::{Hash, Hasher};
-use std::mem::replace;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
 
+use super::super::diagnostic::{Code, Diagnostic};
 use super::super::parser_output::{
-    AbstractSyntaxTree, AnalysisDeclaration, ConstantDeclaration, FunctionDeclaration, Statements,
-    Structures, TypeData,
+    AbstractSyntaxTree, AnalysisDeclaration, ConstantDeclaration, FunctionDeclaration, Literal,
+    Span, Statements, Structures, TypeData,
 };
 use super::super::seahasher::DefaultSeaHasher;
+use super::super::stdlib::{Registry, StdFunc};
 
+use super::bindings::Bindings;
 use super::block::BasicBlock;
+use super::blockexpression::BlockExpression;
+use super::cycles::definition_order;
+use super::determinism::Determinism;
+use super::distribution::load_distribution;
 
 /// Namespace represents the pre-parsing of the of the AST.
 /// It will attempt to ensure there are no collisions with
@@ -17,15 +25,67 @@ use super::block::BasicBlock;
 #[derive(Default)]
 pub struct Namespace<'a> {
     constants: HashMap<&'a str, ConstantDeclaration<'a>, DefaultSeaHasher>,
+    /// the lowered expression of each constant
+    constant_values: HashMap<&'a str, BlockExpression<'a>, DefaultSeaHasher>,
     functions: HashMap<&'a str, FunctionDeclaration<'a>, DefaultSeaHasher>,
     function_body: HashMap<&'a str, BasicBlock<'a>, DefaultSeaHasher>,
     owndata: Option<BasicBlock<'a>>,
     analysis: Option<AnalysisDeclaration<'a>>,
+    source_dir: PathBuf,
+    bindings: Bindings,
+    /// the name of every parameter which was resolved
+    parameters: RefCell<BTreeSet<String>>,
+    registry: Registry,
+}
+impl<'a> fmt::Display for Namespace<'a> {
+    /// writes the block of the analysis, then the block of each
+    /// function sorted by name. The standard library is omitted.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Option::Some(ref block) = self.owndata {
+            write!(f, "analyze:\n{}", block)?;
+        }
+        let mut functions: Vec<(&&'a str, &BasicBlock<'a>)> = self
+            .function_body
+            .iter()
+            .filter(|(name, _)| self.functions.get(*name).map(|func| !func.stdlib) == Some(true))
+            .collect();
+        functions.sort_by_key(|(name, _)| **name);
+        for (name, block) in functions {
+            write!(f, "\nfn {}:\n{}", name, block)?;
+        }
+        Ok(())
+    }
 }
 impl<'a> Namespace<'a> {
     /// new constructs an instance of namespace.
-    pub fn new(ast: &AbstractSyntaxTree<'a>) -> Result<Namespace<'a>, String> {
-        let mut analysis = Namespace::default();
+    ///
+    /// files the program loads are resolved relative to the
+    /// current working directory.
+    pub fn new(ast: &AbstractSyntaxTree<'a>) -> Result<Namespace<'a>, Diagnostic> {
+        Namespace::build(
+            ast,
+            Path::new(""),
+            &Bindings::default(),
+            &Registry::standard(),
+        )
+    }
+
+    /// constructs an instance of namespace, for a program whose
+    /// source file is within `source_dir`. Parameters are
+    /// resolved with `bindings`, and the standard library is
+    /// the functions within `registry`.
+    pub fn build(
+        ast: &AbstractSyntaxTree<'a>,
+        source_dir: &Path,
+        bindings: &Bindings,
+        registry: &Registry,
+    ) -> Result<Namespace<'a>, Diagnostic> {
+        let mut analysis = Namespace {
+            source_dir: source_dir.to_path_buf(),
+            bindings: bindings.clone(),
+            registry: registry.clone(),
+            ..Namespace::default()
+        };
         analysis.populate_std();
         for item in ast.ast.iter() {
             // actions do nothing unless items is of
@@ -35,15 +95,56 @@ impl<'a> Namespace<'a> {
             analysis.add_function(item)?;
             analysis.add_analysis(item)?;
         }
-        for (name, func) in analysis.functions.iter() {
-            let bb = BasicBlock::from_func(&analysis, func)?;
-            analysis.function_body.insert(name, bb);
+        // recursion would never finish inlining
+        let order = match definition_order(&analysis) {
+            Ok(order) => order,
+            Err(cycles) => {
+                let mut found = cycles.into_iter();
+                let first = found.next().unwrap();
+                return Err(found.fold(first, |first, other| {
+                    first.with_note(format!("another cycle: {}", other.get_message()))
+                }));
+            }
+        };
+        // each definition is lowered after those it reads, so the
+        // types & determinism of constants and return values are
+        // known where they are used.
+        let stdlib: Vec<&'a str> = analysis
+            .functions
+            .iter()
+            .filter(|(_, func)| func.stdlib)
+            .map(|(name, _)| *name)
+            .collect();
+        for name in stdlib.into_iter().chain(order) {
+            match (analysis.constants.get(name), analysis.functions.get(name)) {
+                (Option::Some(constant), _) => {
+                    let expr = BasicBlock::lower_constant(&analysis, constant)?;
+                    analysis.constant_values.insert(name, expr);
+                }
+                (_, Option::Some(func)) => {
+                    let bb = BasicBlock::from_func(&analysis, func)?;
+                    analysis.function_body.insert(name, bb);
+                }
+                _ => {}
+            };
         }
         let rootblock = Some(BasicBlock::from_root(&analysis)?);
         analysis.owndata = rootblock;
         Ok(analysis)
     }
 
+    /// a namespace which holds only the standard library, this is
+    /// used to describe the standard library's functions when a
+    /// program does not build.
+    pub fn standard_library() -> Namespace<'static> {
+        let mut namespace = Namespace {
+            registry: Registry::standard(),
+            ..Namespace::default()
+        };
+        namespace.populate_std();
+        namespace
+    }
+
     /// returns a function declaration for a specific name to allow for argument &
     /// and type checking.
     pub fn get_function<'b>(&'b self, arg: &str) -> Option<&'b FunctionDeclaration<'a>> {
@@ -60,6 +161,16 @@ impl<'a> Namespace<'a> {
         self.constants.iter()
     }
 
+    /// return an iterator over all functions, including the standard library
+    pub fn get_all_functions<'b>(&'b self) -> Iter<'b, &'a str, FunctionDeclaration<'a>> {
+        self.functions.iter()
+    }
+
+    /// returns the lowered body of a function
+    pub fn get_function_block<'b>(&'b self, name: &str) -> Option<&'b BasicBlock<'a>> {
+        self.function_body.get(name)
+    }
+
     pub fn get_all_function_blocks<'b>(&'b self) -> Iter<'b, &'a str, BasicBlock<'a>> {
         self.function_body.iter()
     }
@@ -69,9 +180,37 @@ impl<'a> Namespace<'a> {
         &self.analysis
     }
 
-    /// returns the type for a constatn
+    /// returns the type for a constant, this is its annotation
+    /// until its type has been inferred.
     pub fn get_constant_type(&self, name: &str) -> Option<TypeData> {
-        self.constants.get(name).map(|constant| constant.kind)
+        self.constant_values
+            .get(name)
+            .map(|expr| expr.get_kind())
+            .or_else(|| self.constants.get(name).and_then(|constant| constant.kind))
+    }
+
+    /// returns if a constant is random, a constant is random until
+    /// it has been lowered.
+    pub fn get_constant_determinism(&self, name: &str) -> Determinism {
+        self.constant_values
+            .get(name)
+            .map(|expr| expr.get_determinism())
+            .unwrap_or(Determinism::Random)
+    }
+
+    /// returns the lowered expression of a constant
+    pub fn get_constant_value<'b>(&'b self, name: &str) -> Option<&'b BlockExpression<'a>> {
+        self.constant_values.get(name)
+    }
+
+    /// returns the functions of the standard library
+    pub fn get_registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// returns the implementation of a standard library function
+    pub fn get_std_function(&self, name: &str) -> Option<&StdFunc> {
+        self.registry.get(name)
     }
 
     /// checks if this name already exists
@@ -79,171 +218,99 @@ impl<'a> Namespace<'a> {
         self.get_constant(arg).is_some() || self.get_function(arg).is_some()
     }
 
+    /// resolves literals which depend on the world outside the
+    /// program. `load_distribution` is replaced by the categorical
+    /// its file describes.
+    ///
+    /// Parameters are kept, so the program is built once for every
+    /// value they may take. Their bound value is only checked.
+    pub fn resolve_literal(&self, lit: &Literal<'a>) -> Result<Literal<'a>, String> {
+        match lit {
+            &Literal::EnvirNumber(ref name) | &Literal::EnvirBool(ref name) => {
+                self.parameters.borrow_mut().insert(name.to_string());
+            }
+            _ => {}
+        };
+        match lit {
+            &Literal::EnvirNumber(name) => {
+                self.bindings.get_int(name)?;
+                Ok(lit.clone())
+            }
+            &Literal::EnvirBool(name) => {
+                self.bindings.get_bool(name)?;
+                Ok(lit.clone())
+            }
+            &Literal::LoadDistribution(ref path) => Ok(Literal::Categorical(load_distribution(
+                &self.source_dir.join(path),
+            )?)),
+            x => Ok(x.clone()),
+        }
+    }
+
+    /// returns the name of every parameter the program depends upon
+    pub fn get_parameters(&self) -> BTreeSet<String> {
+        self.parameters.borrow().clone()
+    }
+
+    /// returns where a constant or function was declared
+    pub fn get_declaration_span(&self, name: &str) -> Span {
+        self.get_constant(name)
+            .map(|constant| constant.span)
+            .or_else(|| self.get_function(name).map(|func| func.span))
+            .unwrap_or_default()
+    }
+
     pub fn get_own_block<'b>(&'b self) -> &'b Option<BasicBlock<'a>> {
         &self.owndata
     }
 
     fn populate_std(&mut self) {
-        self.functions.insert(
-            "min",
-            FunctionDeclaration {
-                stdlib: true,
-                name: "min",
-                args: vec![("minimum_of_dice_collection", TypeData::CollectionOfInt)]
-                    .into_boxed_slice(),
-                ret: TypeData::Int,
-                body: Statements {
-                    data: vec![].into_boxed_slice(),
-                },
-            },
-        );
-        self.functions.insert(
-            "max",
-            FunctionDeclaration {
-                stdlib: true,
-                name: "max",
-                args: vec![("maximum_of_dice_collection", TypeData::CollectionOfInt)]
-                    .into_boxed_slice(),
-                ret: TypeData::Int,
-                body: Statements {
-                    data: vec![].into_boxed_slice(),
-                },
-            },
-        );
-        self.functions.insert(
-            "roll_d6",
-            FunctionDeclaration {
-                stdlib: true,
-                name: "roll_d6",
-                args: vec![("number_of_d6_to_roll", TypeData::Int)].into_boxed_slice(),
-                ret: TypeData::CollectionOfInt,
-                body: Statements {
-                    data: vec![].into_boxed_slice(),
-                },
-            },
-        );
-        self.functions.insert(
-            "roll_d3",
-            FunctionDeclaration {
-                stdlib: true,
-                name: "roll_d3",
-                args: vec![("number_of_d3_to_roll", TypeData::Int)].into_boxed_slice(),
-                ret: TypeData::CollectionOfInt,
-                body: Statements {
-                    data: vec![].into_boxed_slice(),
-                },
-            },
-        );
-        self.functions.insert(
-            "roll",
-            FunctionDeclaration {
-                stdlib: true,
-                name: "roll",
-                args: vec![
-                    ("max_value_roll_can_return_inclusive", TypeData::Int),
-                    ("min_value_roll_can_return_inclusive", TypeData::Int),
-                    ("number_of_rolls_to_make", TypeData::Int),
-                ]
-                .into_boxed_slice(),
-                ret: TypeData::CollectionOfInt,
-                body: Statements {
-                    data: vec![].into_boxed_slice(),
+        for func in self.registry.iter() {
+            self.functions.insert(
+                func.name(),
+                FunctionDeclaration {
+                    stdlib: true,
+                    name: func.name(),
+                    args: func.args().to_vec().into_boxed_slice(),
+                    ret: func.ret(),
+                    body: Statements {
+                        data: vec![].into_boxed_slice(),
+                        comments: vec![].into_boxed_slice(),
+                    },
+                    span: Span::default(),
+                    allow: vec![].into_boxed_slice(),
+                    doc: func.doc().to_vec().into_boxed_slice(),
                 },
-            },
-        );
-        self.functions.insert(
-            "filter",
-            FunctionDeclaration {
-                stdlib: true,
-                name: "filter",
-                args: vec![
-                    (
-                        "collection_of_tests_to_filter_with",
-                        TypeData::CollectionOfBool,
-                    ),
-                    ("collection_of_data_to_filter", TypeData::CollectionOfInt),
-                ]
-                .into_boxed_slice(),
-                ret: TypeData::CollectionOfInt,
-                body: Statements {
-                    data: vec![].into_boxed_slice(),
-                },
-            },
-        );
-        self.functions.insert(
-            "count",
-            FunctionDeclaration {
-                stdlib: true,
-                name: "count",
-                args: vec![("collection_to_sum_over", TypeData::CollectionOfBool)]
-                    .into_boxed_slice(),
-                ret: TypeData::Int,
-                body: Statements {
-                    data: vec![].into_boxed_slice(),
-                },
-            },
-        );
-        self.functions.insert(
-            "len",
-            FunctionDeclaration {
-                stdlib: true,
-                name: "len",
-                args: vec![("length_of_collection", TypeData::CollectionOfInt)].into_boxed_slice(),
-                ret: TypeData::Int,
-                body: Statements {
-                    data: vec![].into_boxed_slice(),
-                },
-            },
-        );
-        self.functions.insert(
-            "join",
-            FunctionDeclaration {
-                stdlib: true,
-                name: "join",
-                args: vec![
-                    ("join_collection_over_a", TypeData::CollectionOfInt),
-                    ("join_collection_over_b", TypeData::CollectionOfInt),
-                ]
-                .into_boxed_slice(),
-                ret: TypeData::CollectionOfInt,
-                body: Statements {
-                    data: vec![].into_boxed_slice(),
-                },
-            },
-        );
-        self.functions.insert(
-            "sum",
-            FunctionDeclaration {
-                stdlib: true,
-                name: "sum",
-                args: vec![("collection_to_sum_with", TypeData::CollectionOfInt)]
-                    .into_boxed_slice(),
-                ret: TypeData::Int,
-                body: Statements {
-                    data: vec![].into_boxed_slice(),
-                },
-            },
-        );
+            );
+        }
     }
 
-    fn add_const<'b>(&mut self, arg: &'b Structures<'a>) -> Result<(), String> {
-        let lambda = |arg: &'b ConstantDeclaration<'a>| -> Result<(), String> {
+    fn add_const<'b>(&mut self, arg: &'b Structures<'a>) -> Result<(), Diagnostic> {
+        let lambda = |arg: &'b ConstantDeclaration<'a>| -> Result<(), Diagnostic> {
             // check functions first b/c no side effects
             match self.functions.get(arg.name) {
                 Option::Some(is_a_func) => {
-                    return Err(format!(
+                    return Err(Diagnostic::error(
+                        Code::DuplicateDeclaration,
+                        format!(
                         "constant named=\"{}\" cannot be declared, function=\"{}\" uses that name",
                         is_a_func.name, is_a_func.name
                     ))
+                    .with_label(arg.span, "name is already used")
+                    .with_secondary(is_a_func.span, "function declared here"))
                 }
                 Option::None => {}
             };
             match self.constants.insert(arg.name, arg.clone()) {
                 Option::Some(already_exists) => {
-                    return Err(format!(
+                    return Err(Diagnostic::error(
+                        Code::DuplicateDeclaration,
+                        format!(
                         "const named=\"{}\" already exists",
                         already_exists.name
                     ))
+                    .with_label(arg.span, "declared again")
+                    .with_secondary(already_exists.span, "first declared here"))
                 }
                 Option::None => {}
             };
@@ -255,10 +322,12 @@ impl<'a> Namespace<'a> {
             .next()
             .unwrap_or(Ok(()))
     }
-    fn add_analysis<'b>(&mut self, arg: &'b Structures<'a>) -> Result<(), String> {
-        let lambda = |arg: &'b AnalysisDeclaration<'a>| -> Result<(), String> {
-            match replace(&mut self.analysis, Some(arg.clone())) {
-                Option::Some(old) => Err(format!("analyze statement is already declared\n\n{}\n\nsecond declaration\n\n{}\n\n is error", old, arg)),
+    fn add_analysis<'b>(&mut self, arg: &'b Structures<'a>) -> Result<(), Diagnostic> {
+        let lambda = |arg: &'b AnalysisDeclaration<'a>| -> Result<(), Diagnostic> {
+            match self.analysis.replace(arg.clone()) {
+                Option::Some(old) => Err(Diagnostic::error(Code::DuplicateDeclaration, format!("analyze statement is already declared\n\n{}\n\nsecond declaration\n\n{}\n\n is error", old, arg))
+                    .with_label(arg.span, "second analyze statement")
+                    .with_secondary(old.span, "first declared here")),
                 Option::None => Ok(()),
             }
         };
@@ -268,23 +337,31 @@ impl<'a> Namespace<'a> {
             .next()
             .unwrap_or(Ok(()))
     }
-    fn add_function<'b>(&mut self, arg: &'b Structures<'a>) -> Result<(), String> {
-        let lambda = |arg: &'b FunctionDeclaration<'a>| -> Result<(), String> {
+    fn add_function<'b>(&mut self, arg: &'b Structures<'a>) -> Result<(), Diagnostic> {
+        let lambda = |arg: &'b FunctionDeclaration<'a>| -> Result<(), Diagnostic> {
             match self.constants.get(arg.name) {
                 Option::Some(is_a_const) => {
-                    return Err(format!(
+                    return Err(Diagnostic::error(
+                        Code::DuplicateDeclaration,
+                        format!(
                         "function named=\"{}\" cannot be declared, constant=\"{}\" uses that name",
                         is_a_const.name, is_a_const.name
                     ))
+                    .with_label(arg.span, "name is already used")
+                    .with_secondary(is_a_const.span, "constant declared here"))
                 }
                 Option::None => {}
             };
             match self.functions.insert(arg.name, arg.clone()) {
                 Option::Some(already_exists) => {
-                    return Err(format!(
+                    return Err(Diagnostic::error(
+                        Code::DuplicateDeclaration,
+                        format!(
                         "function named=\"{}\" already exists",
                         already_exists.name
                     ))
+                    .with_label(arg.span, "declared again")
+                    .with_secondary(already_exists.span, "first declared here"))
                 }
                 Option::None => {}
             };
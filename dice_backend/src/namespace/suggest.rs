@@ -0,0 +1,98 @@
+use super::super::diagnostic::Diagnostic;
+use super::super::parser_output::TypeData;
+
+/// the number of single character insertions, deletions,
+/// substitutions & transpositions needed to turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut table = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in table.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in table[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut best = (table[i - 1][j - 1] + cost)
+                .min(table[i - 1][j] + 1)
+                .min(table[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(table[i - 2][j - 2] + 1);
+            }
+            table[i][j] = best;
+        }
+    }
+    table[a.len()][b.len()]
+}
+
+/// returns the candidate closest to `name`, if any are close enough
+/// to plausibly be a typo.
+pub fn closest<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| {
+            // differences in case are more likely than other typos
+            let distance = if candidate.to_lowercase() == name.to_lowercase() {
+                0
+            } else {
+                edit_distance(name, candidate)
+            };
+            (distance, candidate)
+        })
+        .filter(|(distance, _)| *distance <= limit)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// adds "did you mean" help to an unknown name error
+pub fn did_you_mean<'a, I>(diagnostic: Diagnostic, name: &str, candidates: I) -> Diagnostic
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    match closest(name, candidates) {
+        Option::Some(candidate) => diagnostic.with_help(format!("did you mean `{}`?", candidate)),
+        Option::None => diagnostic,
+    }
+}
+
+/// adds help to a type error which a standard library function
+/// could fix.
+pub fn conversion(diagnostic: Diagnostic, found: TypeData, expected: TypeData) -> Diagnostic {
+    let help = match (found, expected) {
+        (TypeData::CollectionOfInt, TypeData::Int) => {
+            "use `sum(...)`, `max(...)` or `min(...)` to reduce the vec<int> to an int"
+        }
+        (TypeData::CollectionOfBool, TypeData::Int) => {
+            "use `count(...)` to count the true values in the vec<bool>"
+        }
+        (TypeData::Deck, TypeData::CollectionOfInt) => {
+            "use `draw(deck, n)` to draw n cards from the deck"
+        }
+        _ => return diagnostic,
+    };
+    diagnostic.with_help(help)
+}
+
+#[test]
+fn test_suggestions() {
+    assert_eq!(edit_distance("roll_d6", "roll_d6"), 0);
+    assert_eq!(edit_distance("rol_d6", "roll_d6"), 1);
+    assert_eq!(edit_distance("", "abc"), 3);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("smu", "sum"), 1);
+
+    let names = ["roll_d6", "roll_d3", "roll", "sum", "count"];
+    assert_eq!(closest("rol_d6", names.iter().cloned()), Some("roll_d6"));
+    assert_eq!(closest("smu", names.iter().cloned()), Some("sum"));
+    assert_eq!(closest("SUM", names.iter().cloned()), Some("sum"));
+    assert_eq!(closest("banana", names.iter().cloned()), None);
+    assert_eq!(closest("sum", names.iter().cloned()), None);
+}
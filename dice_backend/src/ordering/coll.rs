@@ -1,13 +1,15 @@
 use std::collections::BTreeMap;
-use std::ops::Index;
+use std::fmt;
 
+use super::super::dump::Dump;
 use super::super::inliner::InlinedCollection;
+use super::super::namespace::{Bindings, Determinism};
 use super::super::parser_output::TypeData;
 
 use super::super::peephole::graphs::{Graph, Match};
 
-use super::expr::OrderedExpression;
-use super::ord::{OrdTrait, OrdType};
+use super::expr::{ConstantValue, OrderedExpression};
+use super::ord::OrdTrait;
 
 /// OrderedCollection is the read-only collection of statements
 #[derive(Clone)]
@@ -33,15 +35,45 @@ impl OrderedCollection {
         }
     }
 
+    /// builds a collection from expressions which already record
+    /// their sources & sinks, such as a graph which was saved.
+    pub fn from_exprs<I>(exprs: I, ret: u64) -> OrderedCollection
+    where
+        I: IntoIterator<Item = OrderedExpression>,
+    {
+        OrderedCollection {
+            data: exprs
+                .into_iter()
+                .map(|expr| (expr.get_own_id(), expr))
+                .collect(),
+            ret: ret,
+        }
+    }
+
     /// returns the identifier of the return statement
     pub fn get_return(&self) -> u64 {
-        self.ret.clone()
+        self.ret
     }
 
     pub fn get_expr<'a>(&'a self, expr: u64) -> Option<&'a OrderedExpression> {
         self.data.get(&expr)
     }
 
+    /// if an expression is constant, or random. It is random when
+    /// it, or any expression it reads, is random.
+    pub fn get_determinism(&self, expr: u64) -> Determinism {
+        match self.get_expr(expr) {
+            Option::Some(expr) if !expr.is_random() => expr
+                .as_ref()
+                .get_sources()
+                .iter()
+                .fold(Determinism::Constant, |acc, source| {
+                    acc.join(&self.get_determinism(source.0))
+                }),
+            _ => Determinism::Random,
+        }
+    }
+
     fn get_mut_expr<'a>(&'a mut self, expr: u64) -> Option<&'a mut OrderedExpression> {
         self.data.get_mut(&expr)
     }
@@ -50,6 +82,25 @@ impl OrderedCollection {
         self.data.remove(&expr);
     }
 
+    /// replaces every parameter with the value `bindings` gives it,
+    /// the graph may then be optimized further.
+    pub fn bind(&mut self, bindings: &Bindings) -> Result<(), String> {
+        for expr in self.data.values_mut() {
+            let bound = match expr {
+                &mut OrderedExpression::Constant(ConstantValue::Parameter(ref name, ref ord)) => {
+                    let ord = ord.clone();
+                    match ord.get_own_type() {
+                        TypeData::Bool => ConstantValue::Bool(bindings.get_bool(name)?, ord),
+                        _ => ConstantValue::Int(bindings.get_int(name)?, ord),
+                    }
+                }
+                _ => continue,
+            };
+            *expr = OrderedExpression::Constant(bound);
+        }
+        Ok(())
+    }
+
     pub fn keys<'a>(&'a self) -> std::collections::btree_map::Keys<'a, u64, OrderedExpression> {
         self.data.keys()
     }
@@ -58,20 +109,15 @@ impl OrderedCollection {
     where
         I: IntoIterator<Item = u64> + Clone,
     {
-        let iter = (0u64..u64::MAX).filter_map(|x: u64| -> Option<u64> {
-            let bad_value = isnt
+        let iter = (0u64..u64::MAX).filter(|x: &u64| -> bool {
+            !isnt
                 .clone()
                 .into_iter()
-                .map(|isnt_val| isnt_val == x)
-                .fold(false, |x, y| x | y);
-            if bad_value {
-                None
-            } else {
-                Some(x)
-            }
+                .map(|isnt_val| isnt_val == *x)
+                .fold(false, |x, y| x | y)
         });
         for i in iter {
-            if self.data.get(&i).is_none() {
+            if !self.data.contains_key(&i) {
                 return i;
             }
         }
@@ -106,15 +152,16 @@ impl Graph for OrderedCollection {
             .zip(new_sink.get_id())
             .zip(new_sink.get_kind())
             .filter(|tup| self.exists(((tup.0).0).0, ((tup.0).0).1))
-            .map(|(((expr_id, _), sink_id), sink_kind)| (expr_id, sink_id, sink_kind))
+            .map(|(((expr_id, expr_kind), sink_id), _)| (expr_id, expr_kind, sink_id))
             .next()
         {
             Option::None => {}
-            Option::Some((expr_id, sink_id, sink_kind)) => {
-                // the exists check proves that it exists & is the right type
+            Option::Some((expr_id, expr_kind, sink_id)) => {
+                // the exists check proves that it exists & is the right type,
+                // the edge has the type of the expression which is read.
                 self.get_mut_expr(expr_id)
                     .unwrap()
-                    .add_sink(sink_id, sink_kind);
+                    .add_sink(sink_id, expr_kind);
             }
         };
     }
@@ -127,15 +174,16 @@ impl Graph for OrderedCollection {
             .zip(new_sink.get_id())
             .zip(new_sink.get_kind())
             .filter(|tup| self.exists(((tup.0).0).0, ((tup.0).0).1))
-            .map(|(((expr_id, _), sink_id), sink_kind)| (expr_id, sink_id, sink_kind))
+            .map(|(((expr_id, expr_kind), sink_id), _)| (expr_id, expr_kind, sink_id))
             .next()
         {
             Option::None => {}
-            Option::Some((expr_id, sink_id, sink_kind)) => {
-                // the exists check proves that it exists & is the right type
+            Option::Some((expr_id, expr_kind, sink_id)) => {
+                // the exists check proves that it exists & is the right type,
+                // the edge has the type of the expression which is read.
                 self.get_mut_expr(expr_id)
                     .unwrap()
-                    .remove_sink(sink_id, sink_kind);
+                    .remove_sink(sink_id, expr_kind);
             }
         };
     }
@@ -148,7 +196,9 @@ impl Graph for OrderedCollection {
             .zip(expr.get_kind())
             .zip(old.get_id().into_iter().zip(old.get_kind()))
             .zip(new.get_id().into_iter().zip(new.get_kind()))
-            .filter(|tup| self.exists(((tup.0).0).0, ((tup.0).0).1))
+            // readers are often matched by one of their sinks, whose
+            // type is that of the expression read, not the reader's.
+            .filter(|tup| self.get_expr(((tup.0).0).0).is_some())
             .map(|(((expr_id, _), (old_id, old_kind)), (new_id, new_kind))| {
                 (expr_id, old_id, old_kind, new_id, new_kind)
             })
@@ -182,6 +232,39 @@ impl Graph for OrderedCollection {
     }
 }
 
+impl fmt::Display for OrderedCollection {
+    /// writes one expression per line, every expression follows
+    /// its sources & lists its sinks. Identifiers are numbered in
+    /// that order.
+    ///
+    /// ```text
+    /// %0: int = const 3 -> %1
+    /// %1: int = sum_roll_d6 %0
+    /// return %1
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.dump())
+    }
+}
+impl OrderedCollection {
+    /// the graph as it is written, sinks are the ones each
+    /// expression records.
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::default();
+        for (id, expr) in self.data.iter() {
+            dump.insert_with_sinks(
+                *id,
+                expr.to_string(),
+                expr.get_own_type(),
+                expr.get_sources().iter().map(|(id, _)| *id).collect(),
+                expr.get_sinks().iter().map(|(id, _)| *id).collect(),
+            );
+        }
+        dump.set_return(self.ret);
+        dump
+    }
+}
+
 /// OrderingCollection is used to build the `OrderedCollection`.
 #[derive(Default, Clone)]
 pub struct OrderingCollection {
@@ -225,7 +308,7 @@ impl OrderingCollection {
 
     pub fn next_free_id(&self) -> u64 {
         for i in 0..u64::MAX {
-            if self.data.get(&i).is_none() {
+            if !self.data.contains_key(&i) {
                 return i;
             }
         }
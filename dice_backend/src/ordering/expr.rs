@@ -1,8 +1,11 @@
+use std::fmt;
+
 use super::super::inliner::{
     BoolArg as BArg, BoolOrInt as BI, InlinedCollection, InlinedExpression, IntArg as IArg,
     Op as IOp,
 };
-use super::super::parser_output::TypeData;
+use super::super::parser_output::{Rational, TypeData};
+use super::super::stdlib::{Lowering, StdFunc};
 
 use super::coll::OrderingCollection;
 use super::ord::{ExprVec, OrdTrait, OrdType};
@@ -11,6 +14,7 @@ const I: TypeData = TypeData::Int;
 const B: TypeData = TypeData::Bool;
 const C_I: TypeData = TypeData::CollectionOfInt;
 const C_B: TypeData = TypeData::CollectionOfBool;
+const D: TypeData = TypeData::Deck;
 
 /// Various Expressions
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -19,6 +23,7 @@ pub enum OrderedExpression {
     Constant(ConstantValue),
     Op(Op),
     Spec(Spec),
+    Random(Distribution),
 }
 impl OrderedExpression {
     /// converts an inlined expression into an ordered expression
@@ -37,130 +42,27 @@ impl OrderedExpression {
 
         // build our new expression, for inserting it
         let item = match expr {
-            &InlinedExpression::D6(ref arg) => {
-                // look up our argument
-                let old_arg = old_coll.get_expr(arg).unwrap();
-                // ensure that it is inserted into our new collection.
-                OrderedExpression::new(old_arg, old_coll, new_coll);
-                // mark that we use it, and how we use it.
-                new_coll.set_expr_sink(arg, self_id, I);
-                OrderedExpression::StdLib(StdLibraryFunc::D6(OrdType::new(
-                    self_id,
-                    C_I,
-                    s_v![(*arg, I)],
-                )))
-            }
-            &InlinedExpression::D3(ref arg) => {
-                // look up our argument
-                let old_arg = old_coll.get_expr(arg).unwrap();
-                // ensure that it is inserted into our new collection.
-                OrderedExpression::new(old_arg, old_coll, new_coll);
-                // mark that we use it, and how we use it.
-                new_coll.set_expr_sink(arg, self_id, I);
-                OrderedExpression::StdLib(StdLibraryFunc::D3(OrdType::new(
-                    self_id,
-                    C_I,
-                    s_v![(*arg, I)],
-                )))
-            }
-            &InlinedExpression::Sum(ref arg) => {
-                // look up our argument
-                let old_arg = old_coll.get_expr(arg).unwrap();
-                // ensure that it is inserted into our new collection.
-                OrderedExpression::new(old_arg, old_coll, new_coll);
-                // mark that we use it, and how we use it.
-                new_coll.set_expr_sink(arg, self_id, C_I);
-                OrderedExpression::StdLib(StdLibraryFunc::Sum(OrdType::new(
-                    self_id,
-                    I,
-                    s_v![(*arg, C_I)],
-                )))
-            }
-            &InlinedExpression::Max(ref arg) => {
-                // look up our argument
-                let old_arg = old_coll.get_expr(arg).unwrap();
-                // ensure that it is inserted into our new collection.
-                OrderedExpression::new(old_arg, old_coll, new_coll);
-                // mark that we use it, and how we use it.
-                new_coll.set_expr_sink(arg, self_id, C_I);
-                OrderedExpression::StdLib(StdLibraryFunc::Max(OrdType::new(
-                    self_id,
-                    I,
-                    s_v![(*arg, C_I)],
-                )))
-            }
-            &InlinedExpression::Min(ref arg) => {
-                // look up our argument
-                let old_arg = old_coll.get_expr(arg).unwrap();
-                // ensure that it is inserted into our new collection.
-                OrderedExpression::new(old_arg, old_coll, new_coll);
-                // mark that we use it, and how we use it.
-                new_coll.set_expr_sink(arg, self_id, C_I);
-                OrderedExpression::StdLib(StdLibraryFunc::Min(OrdType::new(
-                    self_id,
-                    I,
-                    s_v![(*arg, C_I)],
-                )))
-            }
-            &InlinedExpression::Count(ref arg) => {
-                // look up our argument
-                let old_arg = old_coll.get_expr(arg).unwrap();
-                // ensure that it is inserted into our new collection.
-                OrderedExpression::new(old_arg, old_coll, new_coll);
-                // mark that we use it, and how we use it.
-                new_coll.set_expr_sink(arg, self_id, C_B);
-                OrderedExpression::StdLib(StdLibraryFunc::Count(OrdType::new(
-                    self_id,
-                    C_B,
-                    s_v![(*arg, C_B)],
-                )))
-            }
-            &InlinedExpression::Len(ref arg) => {
-                // look up our argument
-                let old_arg = old_coll.get_expr(arg).unwrap();
-                // ensure that it is inserted into our new collection.
-                OrderedExpression::new(old_arg, old_coll, new_coll);
-                // mark that we use it, and how we use it.
-                new_coll.set_expr_sink(arg, self_id, C_I);
-                OrderedExpression::StdLib(StdLibraryFunc::Len(OrdType::new(
-                    self_id,
-                    C_I,
-                    s_v![(*arg, C_I)],
-                )))
-            }
-            &InlinedExpression::Filter(ref a, ref b) => {
-                // ensure `a` is inserted
-                let old_arg_a = old_coll.get_expr(a).unwrap();
-                OrderedExpression::new(old_arg_a, old_coll, new_coll);
-                new_coll.set_expr_sink(a, self_id, C_B);
-
-                // ensure `b` is inserted
-                let old_arg_b = old_coll.get_expr(b).unwrap();
-                OrderedExpression::new(old_arg_b, old_coll, new_coll);
-                new_coll.set_expr_sink(b, self_id, C_I);
-
-                OrderedExpression::StdLib(StdLibraryFunc::Filter(OrdType::new(
-                    self_id,
-                    C_I,
-                    s_v![(*a, C_B), (*b, C_I)],
-                )))
+            &InlinedExpression::StdLib(ref func, ref args, _) => {
+                let mut sources = ExprVec::new();
+                for (arg, &(_, kind)) in args.iter().zip(func.args()) {
+                    // ensure the argument is inserted
+                    let old_arg = old_coll.get_expr(arg).unwrap();
+                    OrderedExpression::new(old_arg, old_coll, new_coll);
+                    // mark that we use it, and how we use it.
+                    new_coll.set_expr_sink(arg, self_id, kind);
+                    sources.push((*arg, kind));
+                }
+                OrderedExpression::StdLib(StdLibraryFunc::new(
+                    func.clone(),
+                    OrdType::new(self_id, func.ret(), sources),
+                ))
             }
-            &InlinedExpression::Join(ref a, ref b) => {
-                // ensure `a` is inserted
-                let old_arg_a = old_coll.get_expr(a).unwrap();
-                OrderedExpression::new(old_arg_a, old_coll, new_coll);
-                new_coll.set_expr_sink(a, self_id, C_I);
-
-                // ensure `b` is inserted
-                let old_arg_b = old_coll.get_expr(b).unwrap();
-                OrderedExpression::new(old_arg_b, old_coll, new_coll);
-                new_coll.set_expr_sink(b, self_id, C_I);
-
-                OrderedExpression::StdLib(StdLibraryFunc::Join(OrdType::new(
-                    self_id,
-                    C_I,
-                    s_v![(*a, C_I), (*b, C_I)],
-                )))
+            &InlinedExpression::Deck(ref cards, _) => {
+                // no dependent expressions
+                OrderedExpression::Constant(ConstantValue::Deck(
+                    cards.clone(),
+                    OrdType::new(self_id, D, s_v![]),
+                ))
             }
             &InlinedExpression::ConstantInt(ref i) => {
                 // no dependent expressions
@@ -176,6 +78,27 @@ impl OrderedExpression {
                     OrdType::new(self_id, B, s_v![]),
                 ))
             }
+            &InlinedExpression::Parameter(ref name, kind) => {
+                // no dependent expressions
+                OrderedExpression::Constant(ConstantValue::Parameter(
+                    name.clone(),
+                    OrdType::new(self_id, kind, s_v![]),
+                ))
+            }
+            &InlinedExpression::Bernoulli(ref prob, _) => {
+                // no dependent expressions
+                OrderedExpression::Random(Distribution::Bernoulli(
+                    *prob,
+                    OrdType::new(self_id, B, s_v![]),
+                ))
+            }
+            &InlinedExpression::Categorical(ref weights, _) => {
+                // no dependent expressions
+                OrderedExpression::Random(Distribution::Categorical(
+                    weights.clone(),
+                    OrdType::new(self_id, I, s_v![]),
+                ))
+            }
             InlinedExpression::Op(IOp::Add(IArg::Int_Int(ref a, ref b))) => {
                 // ensure `a` is inserted
                 let old_arg_a = old_coll.get_expr(a).unwrap();
@@ -799,7 +722,7 @@ impl OrderedExpression {
                 OrderedExpression::new(old_arg_b, old_coll, new_coll);
                 new_coll.set_expr_sink(b, self_id, B);
 
-                OrderedExpression::Op(Op::And(OrdType::new(self_id, B, s_v![(*a, C_B), (*b, B)])))
+                OrderedExpression::Op(Op::And(OrdType::new(self_id, B, s_v![(*a, B), (*b, B)])))
             }
             InlinedExpression::Op(IOp::And(BArg::Bool_CollectionOfBool(ref a, ref b))) => {
                 // ensure `a` is inserted
@@ -815,7 +738,7 @@ impl OrderedExpression {
                 OrderedExpression::Op(Op::And(OrdType::new(
                     self_id,
                     C_B,
-                    s_v![(*a, C_B), (*b, B)],
+                    s_v![(*a, B), (*b, C_B)],
                 )))
             }
             InlinedExpression::Op(IOp::And(BArg::CollectionOfBool_Bool(ref a, ref b))) => {
@@ -846,7 +769,7 @@ impl OrderedExpression {
                 OrderedExpression::new(old_arg_b, old_coll, new_coll);
                 new_coll.set_expr_sink(b, self_id, B);
 
-                OrderedExpression::Op(Op::Or(OrdType::new(self_id, B, s_v![(*a, C_B), (*b, B)])))
+                OrderedExpression::Op(Op::Or(OrdType::new(self_id, B, s_v![(*a, B), (*b, B)])))
             }
             InlinedExpression::Op(IOp::Or(BArg::Bool_CollectionOfBool(ref a, ref b))) => {
                 // ensure `a` is inserted
@@ -859,7 +782,7 @@ impl OrderedExpression {
                 OrderedExpression::new(old_arg_b, old_coll, new_coll);
                 new_coll.set_expr_sink(b, self_id, C_B);
 
-                OrderedExpression::Op(Op::Or(OrdType::new(self_id, C_B, s_v![(*a, C_B), (*b, B)])))
+                OrderedExpression::Op(Op::Or(OrdType::new(self_id, C_B, s_v![(*a, B), (*b, C_B)])))
             }
             InlinedExpression::Op(IOp::Or(BArg::CollectionOfBool_Bool(ref a, ref b))) => {
                 // ensure `a` is inserted
@@ -878,6 +801,18 @@ impl OrderedExpression {
         // insert the new item
         new_coll.insert(item);
     }
+
+    /// if the expression is random, even when each of its sources
+    /// is constant
+    pub fn is_random(&self) -> bool {
+        match self {
+            &OrderedExpression::Random(_) | &OrderedExpression::Spec(_) => true,
+            &OrderedExpression::StdLib(ref func) => {
+                func.get_function().is_random() || func.get_function().consumes()
+            }
+            &OrderedExpression::Constant(_) | &OrderedExpression::Op(_) => false,
+        }
+    }
 }
 impl PartialEq<TypeData> for OrderedExpression {
     #[inline(always)]
@@ -893,6 +828,7 @@ impl AsRef<OrdType> for OrderedExpression {
             &OrderedExpression::Constant(ref c) => c.as_ref(),
             &OrderedExpression::Op(ref o) => o.as_ref(),
             &OrderedExpression::Spec(ref s) => s.as_ref(),
+            &OrderedExpression::Random(ref r) => r.as_ref(),
         }
     }
 }
@@ -904,22 +840,79 @@ impl AsMut<OrdType> for OrderedExpression {
             &mut OrderedExpression::Constant(ref mut c) => c.as_mut(),
             &mut OrderedExpression::Op(ref mut o) => o.as_mut(),
             &mut OrderedExpression::Spec(ref mut s) => s.as_mut(),
+            &mut OrderedExpression::Random(ref mut r) => r.as_mut(),
         }
     }
 }
 impl OrdTrait for OrderedExpression {}
+impl fmt::Display for OrderedExpression {
+    /// writes the operation & any immediate values, sources
+    /// are written by the collection.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            &OrderedExpression::Constant(ConstantValue::Bool(b, _)) => write!(f, "const {}", b),
+            &OrderedExpression::Constant(ConstantValue::Int(i, _)) => write!(f, "const {}", i),
+            &OrderedExpression::Constant(ConstantValue::Deck(ref cards, _)) => {
+                write!(f, "deck {:?}", cards)
+            }
+            &OrderedExpression::Constant(ConstantValue::Parameter(ref name, _)) => {
+                write!(f, "param {}", name)
+            }
+            &OrderedExpression::Random(Distribution::Bernoulli(ref prob, _)) => {
+                write!(f, "bernoulli {}", prob)
+            }
+            &OrderedExpression::Random(Distribution::Categorical(ref weights, _)) => {
+                write!(f, "categorical {{")?;
+                for (index, (value, weight)) in weights.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", value, weight)?;
+                }
+                write!(f, "}}")
+            }
+            &OrderedExpression::StdLib(ref func) => write!(f, "{}", func.get_name()),
+            &OrderedExpression::Spec(Spec::SumMultiD6(_)) => write!(f, "sum_roll_d6"),
+            &OrderedExpression::Spec(Spec::SumMultiD3(_)) => write!(f, "sum_roll_d3"),
+            &OrderedExpression::Op(ref op) => write!(
+                f,
+                "{}",
+                match op {
+                    &Op::Add(_) => "add",
+                    &Op::Sub(_) => "sub",
+                    &Op::Mul(_) => "mul",
+                    &Op::Div(_) => "div",
+                    &Op::Equal(_) => "eq",
+                    &Op::NotEqual(_) => "ne",
+                    &Op::GreaterThan(_) => "gt",
+                    &Op::GreaterThanEqual(_) => "ge",
+                    &Op::LessThan(_) => "lt",
+                    &Op::LessThanEqual(_) => "le",
+                    &Op::Or(_) => "or",
+                    &Op::And(_) => "and",
+                }
+            ),
+        }
+    }
+}
 
 /// Constant Values
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ConstantValue {
     Bool(bool, OrdType),
     Int(i8, OrdType),
+    Deck(Box<[i8]>, OrdType),
+    /// an `int` or `bool` which is bound before evaluation
+    Parameter(String, OrdType),
 }
 impl AsRef<OrdType> for ConstantValue {
     #[inline(always)]
     fn as_ref<'a>(&'a self) -> &'a OrdType {
         match self {
-            &ConstantValue::Bool(_, ref x) | &ConstantValue::Int(_, ref x) => x.as_ref(),
+            &ConstantValue::Bool(_, ref x)
+            | &ConstantValue::Int(_, ref x)
+            | &ConstantValue::Deck(_, ref x)
+            | &ConstantValue::Parameter(_, ref x) => x,
         }
     }
 }
@@ -927,9 +920,10 @@ impl AsMut<OrdType> for ConstantValue {
     #[inline(always)]
     fn as_mut<'a>(&'a mut self) -> &'a mut OrdType {
         match self {
-            &mut ConstantValue::Bool(_, ref mut x) | &mut ConstantValue::Int(_, ref mut x) => {
-                x.as_mut()
-            }
+            &mut ConstantValue::Bool(_, ref mut x)
+            | &mut ConstantValue::Int(_, ref mut x)
+            | &mut ConstantValue::Deck(_, ref mut x)
+            | &mut ConstantValue::Parameter(_, ref mut x) => x,
         }
     }
 }
@@ -941,51 +935,81 @@ impl PartialEq<TypeData> for ConstantValue {
 }
 impl OrdTrait for ConstantValue {}
 
-/// StandardLibrary functions
+/// Distributions are random values with exact weights,
+/// they have no dependent expressions.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum StdLibraryFunc {
-    D6(OrdType),
-    D3(OrdType),
-    Filter(OrdType),
-    Count(OrdType),
-    Len(OrdType),
-    Join(OrdType),
-    Sum(OrdType),
-    Max(OrdType),
-    Min(OrdType),
+pub enum Distribution {
+    Bernoulli(Rational, OrdType),
+    Categorical(Box<[(i8, Rational)]>, OrdType),
 }
-impl AsRef<OrdType> for StdLibraryFunc {
+impl AsRef<OrdType> for Distribution {
     #[inline(always)]
     fn as_ref<'a>(&'a self) -> &'a OrdType {
         match self {
-            &StdLibraryFunc::D6(ref x)
-            | &StdLibraryFunc::D3(ref x)
-            | &StdLibraryFunc::Filter(ref x)
-            | &StdLibraryFunc::Count(ref x)
-            | &StdLibraryFunc::Len(ref x)
-            | &StdLibraryFunc::Join(ref x)
-            | &StdLibraryFunc::Sum(ref x)
-            | &StdLibraryFunc::Max(ref x)
-            | &StdLibraryFunc::Min(ref x) => x.as_ref(),
+            &Distribution::Bernoulli(_, ref x) | &Distribution::Categorical(_, ref x) => {
+                x
+            }
         }
     }
 }
-impl AsMut<OrdType> for StdLibraryFunc {
+impl AsMut<OrdType> for Distribution {
     #[inline(always)]
     fn as_mut<'a>(&'a mut self) -> &'a mut OrdType {
         match self {
-            &mut StdLibraryFunc::D6(ref mut x)
-            | &mut StdLibraryFunc::D3(ref mut x)
-            | &mut StdLibraryFunc::Filter(ref mut x)
-            | &mut StdLibraryFunc::Count(ref mut x)
-            | &mut StdLibraryFunc::Len(ref mut x)
-            | &mut StdLibraryFunc::Join(ref mut x)
-            | &mut StdLibraryFunc::Sum(ref mut x)
-            | &mut StdLibraryFunc::Max(ref mut x)
-            | &mut StdLibraryFunc::Min(ref mut x) => x.as_mut(),
+            &mut Distribution::Bernoulli(_, ref mut x)
+            | &mut Distribution::Categorical(_, ref mut x) => x,
         }
     }
 }
+impl PartialEq<TypeData> for Distribution {
+    #[inline(always)]
+    fn eq(&self, other: &TypeData) -> bool {
+        self.as_ref().eq(other)
+    }
+}
+impl OrdTrait for Distribution {}
+
+/// an invocation of a standard library function
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StdLibraryFunc {
+    func: StdFunc,
+    ord: OrdType,
+}
+impl StdLibraryFunc {
+    pub fn new(func: StdFunc, ord: OrdType) -> StdLibraryFunc {
+        StdLibraryFunc { func, ord }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        self.func.name()
+    }
+
+    pub fn get_function(&self) -> &StdFunc {
+        &self.func
+    }
+
+    /// how the compiler may rewrite the invocation, if at all
+    pub fn get_lowering(&self) -> Option<Lowering> {
+        self.func.lowering()
+    }
+
+    /// invokes the same function, with different arguments
+    pub fn with(&self, ord: OrdType) -> StdLibraryFunc {
+        StdLibraryFunc::new(self.func.clone(), ord)
+    }
+}
+impl AsRef<OrdType> for StdLibraryFunc {
+    #[inline(always)]
+    fn as_ref<'a>(&'a self) -> &'a OrdType {
+        &self.ord
+    }
+}
+impl AsMut<OrdType> for StdLibraryFunc {
+    #[inline(always)]
+    fn as_mut<'a>(&'a mut self) -> &'a mut OrdType {
+        &mut self.ord
+    }
+}
 impl PartialEq<TypeData> for StdLibraryFunc {
     #[inline(always)]
     fn eq(&self, other: &TypeData) -> bool {
@@ -1005,7 +1029,7 @@ impl AsMut<OrdType> for Spec {
     #[inline(always)]
     fn as_mut<'a>(&'a mut self) -> &'a mut OrdType {
         match self {
-            &mut Spec::SumMultiD6(ref mut x) | &mut Spec::SumMultiD3(ref mut x) => x.as_mut(),
+            &mut Spec::SumMultiD6(ref mut x) | &mut Spec::SumMultiD3(ref mut x) => x,
         }
     }
 }
@@ -1013,7 +1037,7 @@ impl AsRef<OrdType> for Spec {
     #[inline(always)]
     fn as_ref<'a>(&'a self) -> &'a OrdType {
         match self {
-            &Spec::SumMultiD6(ref x) | &Spec::SumMultiD3(ref x) => x.as_ref(),
+            &Spec::SumMultiD6(ref x) | &Spec::SumMultiD3(ref x) => x,
         }
     }
 }
@@ -1055,7 +1079,7 @@ impl AsRef<OrdType> for Op {
             | &Op::LessThan(ref x)
             | &Op::LessThanEqual(ref x)
             | &Op::Or(ref x)
-            | &Op::And(ref x) => x.as_ref(),
+            | &Op::And(ref x) => x,
         }
     }
 }
@@ -1074,7 +1098,7 @@ impl AsMut<OrdType> for Op {
             | &mut Op::LessThan(ref mut x)
             | &mut Op::LessThanEqual(ref mut x)
             | &mut Op::Or(ref mut x)
-            | &mut Op::And(ref mut x) => x.as_mut(),
+            | &mut Op::And(ref mut x) => x,
         }
     }
 }
@@ -1,15 +1,16 @@
 mod ord;
 pub use self::ord::{OrdTrait, OrdType};
 mod expr;
-pub use self::expr::{ConstantValue, Op, OrderedExpression, StdLibraryFunc};
+pub use self::expr::{ConstantValue, Distribution, Op, OrderedExpression, Spec, StdLibraryFunc};
 mod coll;
 pub use self::coll::OrderedCollection;
+mod serial;
+mod verify;
 
 #[cfg(test)]
 mod test {
-    use super::{
-        ConstantValue, Op, OrdTrait, OrdType, OrderedCollection, OrderedExpression, StdLibraryFunc,
-    };
+    use super::super::stdlib::Lowering;
+    use super::{Op, OrdTrait, OrderedCollection, OrderedExpression};
 
     fn build_coll(source: &str) -> OrderedCollection {
         use super::super::cfgbuilder::ExpressionCollection;
@@ -46,11 +47,13 @@ analyze (sum(dice) - min(dice));
 
         // assert left is the sum expression
         match coll.get_expr(left).unwrap() {
-            OrderedExpression::StdLib(StdLibraryFunc::Sum(ref args)) => {
+            OrderedExpression::StdLib(ref args) if args.get_lowering() == Some(Lowering::Sum) => {
                 assert_eq!(args.get_own_id(), left);
                 assert_eq!(args.get_sources().len(), 1);
                 match coll.get_expr(args.get_sources()[0].0).unwrap() {
-                    OrderedExpression::StdLib(StdLibraryFunc::D6(ref arg)) => {
+                    OrderedExpression::StdLib(ref arg)
+                        if arg.get_lowering() == Some(Lowering::RollDice(6)) =>
+                    {
                         // it should be aware it is used twice
                         assert_eq!(arg.get_sinks().len(), 2);
                     }
@@ -61,11 +64,13 @@ analyze (sum(dice) - min(dice));
         };
 
         match coll.get_expr(right).unwrap() {
-            OrderedExpression::StdLib(StdLibraryFunc::Min(ref args)) => {
+            OrderedExpression::StdLib(ref args) if args.get_lowering() == Some(Lowering::Min) => {
                 assert_eq!(args.get_own_id(), right);
                 assert_eq!(args.get_sources().len(), 1);
                 match coll.get_expr(args.get_sources()[0].0).unwrap() {
-                    OrderedExpression::StdLib(StdLibraryFunc::D6(ref arg)) => {
+                    OrderedExpression::StdLib(ref arg)
+                        if arg.get_lowering() == Some(Lowering::RollDice(6)) =>
+                    {
                         // it should be aware it is used twice
                         assert_eq!(arg.get_sinks().len(), 2);
                     }
@@ -11,12 +11,12 @@ pub type ExprVec = SmallVec<[(u64, TypeData); 2]>;
 pub trait OrdTrait: AsRef<OrdType> + AsMut<OrdType> + PartialEq<TypeData> {
     /// returns the expression id of -this- expression.
     fn get_own_id(&self) -> u64 {
-        self.as_ref().self_id.clone()
+        self.as_ref().self_id
     }
 
     /// returns the type of -this- expression.
     fn get_own_type(&self) -> TypeData {
-        self.as_ref().self_type.clone()
+        self.as_ref().self_type
     }
 
     /// return a tuple that can construct a matcher
@@ -116,13 +116,6 @@ impl OrdType {
         }
     }
 
-    fn source_exists(&self, id: u64, kind: TypeData) -> bool {
-        self.sources
-            .iter()
-            .map(|(expr_id, expr_kind)| *expr_id == id && *expr_kind == kind)
-            .fold(false, |a, b| a | b)
-    }
-
     fn sink_exists(&self, id: u64, kind: TypeData) -> bool {
         self.sinks
             .iter()
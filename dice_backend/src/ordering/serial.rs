@@ -0,0 +1,246 @@
+use super::super::parser_output::{Rational, TypeData};
+use super::super::serde_json::Value;
+use super::super::stdlib::Registry;
+
+use super::coll::OrderedCollection;
+use super::expr::{ConstantValue, Distribution, Op, OrderedExpression, Spec, StdLibraryFunc};
+use super::ord::{ExprVec, OrdTrait, OrdType};
+
+/*
+ * Each expression is one JSON object, such as
+ *
+ * {"id": 7, "op": "std", "name": "roll_d6", "type": "vec<int>",
+ *  "sources": [[3, "int"]], "sinks": [[9, "vec<int>"]]}
+ *
+ * Rationals are written as `"a/b"` strings, as they do not fit
+ * within a JSON number.
+ *
+ */
+
+impl OrderedCollection {
+    /// writes every expression, and the return
+    pub fn to_json(&self) -> Value {
+        let exprs: Vec<Value> = self
+            .keys()
+            .flat_map(|id| self.get_expr(*id))
+            .map(expr_to_json)
+            .collect();
+        json!({ "return": self.get_return(), "expressions": exprs })
+    }
+
+    /// reads the collection `to_json` wrote. Standard library
+    /// functions are resolved within `registry`, and the graph
+    /// is verified.
+    pub fn from_json(value: &Value, registry: &Registry) -> Result<OrderedCollection, String> {
+        let ret = match value["return"].as_u64() {
+            Option::Some(ret) => ret,
+            Option::None => return Err("graph has no return".to_string()),
+        };
+        let exprs = match value["expressions"].as_array() {
+            Option::Some(exprs) => exprs
+                .iter()
+                .map(|expr| expr_from_json(expr, registry))
+                .collect::<Result<Vec<OrderedExpression>, String>>()?,
+            Option::None => return Err("graph has no expressions".to_string()),
+        };
+        let coll = OrderedCollection::from_exprs(exprs, ret);
+        // an artifact may have been edited since it was written
+        coll.verify().map_err(|errors| errors.join(", "))?;
+        Ok(coll)
+    }
+}
+
+fn expr_to_json(expr: &OrderedExpression) -> Value {
+    let edges = |edges: &[(u64, TypeData)]| -> Vec<Value> {
+        edges
+            .iter()
+            .map(|(id, kind)| json!([id, kind.to_string()]))
+            .collect()
+    };
+    let mut value = json!({
+        "id": expr.get_own_id(),
+        "type": expr.get_own_type().to_string(),
+        "sources": edges(expr.get_sources()),
+        "sinks": edges(expr.get_sinks()),
+    });
+    let (op, immediate) = match expr {
+        &OrderedExpression::Constant(ConstantValue::Bool(b, _)) => ("const", json!(b)),
+        &OrderedExpression::Constant(ConstantValue::Int(i, _)) => ("const", json!(i)),
+        &OrderedExpression::Constant(ConstantValue::Deck(ref cards, _)) => {
+            ("deck", json!(cards.to_vec()))
+        }
+        &OrderedExpression::Constant(ConstantValue::Parameter(ref name, _)) => {
+            ("param", json!(name))
+        }
+        &OrderedExpression::Random(Distribution::Bernoulli(ref prob, _)) => {
+            ("bernoulli", json!(prob.to_string()))
+        }
+        &OrderedExpression::Random(Distribution::Categorical(ref weights, _)) => {
+            let weights: Vec<Value> = weights
+                .iter()
+                .map(|(value, weight)| json!([value, weight.to_string()]))
+                .collect();
+            ("categorical", json!(weights))
+        }
+        &OrderedExpression::StdLib(ref func) => ("std", json!(func.get_name())),
+        // the remaining expressions are named as they are written
+        _ => return set(value, "op", json!(expr.to_string())),
+    };
+    value = set(value, "op", json!(op));
+    match op {
+        "std" | "param" => set(value, "name", immediate),
+        _ => set(value, "value", immediate),
+    }
+}
+
+fn set(mut value: Value, key: &str, field: Value) -> Value {
+    value[key] = field;
+    value
+}
+
+fn expr_from_json(value: &Value, registry: &Registry) -> Result<OrderedExpression, String> {
+    let id = match value["id"].as_u64() {
+        Option::Some(id) => id,
+        Option::None => return Err(format!("expression {} has no id", value)),
+    };
+    let err = |what: &str| format!("expression {} has {}", id, what);
+    let kind =
+        parse_type(value["type"].as_str().unwrap_or("")).map_err(|_| err("an unknown type"))?;
+    let mut ord = OrdType::new(
+        id,
+        kind,
+        read_edges(&value["sources"]).map_err(|e| err(&e))?,
+    );
+    for (sink, sink_kind) in read_edges(&value["sinks"]).map_err(|e| err(&e))? {
+        ord.add_sink(sink, sink_kind);
+    }
+    let immediate = &value["value"];
+    let int = |arg: &Value| -> Result<i8, String> {
+        match arg.as_i64() {
+            Option::Some(i) if i >= i8::MIN as i64 && i <= i8::MAX as i64 => {
+                Ok(i as i8)
+            }
+            _ => Err(err("a value which is not an int")),
+        }
+    };
+    let rational = |arg: &Value| Rational::parse(arg.as_str().unwrap_or("")).map_err(|e| err(&e));
+    Ok(match value["op"].as_str().unwrap_or("") {
+        "const" if kind == TypeData::Bool => match immediate.as_bool() {
+            Option::Some(b) => OrderedExpression::Constant(ConstantValue::Bool(b, ord)),
+            Option::None => return Err(err("a value which is not a bool")),
+        },
+        "const" => OrderedExpression::Constant(ConstantValue::Int(int(immediate)?, ord)),
+        "deck" => {
+            let cards = immediate
+                .as_array()
+                .ok_or_else(|| err("no cards"))?
+                .iter()
+                .map(&int)
+                .collect::<Result<Vec<i8>, String>>()?;
+            OrderedExpression::Constant(ConstantValue::Deck(cards.into_boxed_slice(), ord))
+        }
+        "param" => match value["name"].as_str() {
+            Option::Some(name) if kind == TypeData::Int || kind == TypeData::Bool => {
+                OrderedExpression::Constant(ConstantValue::Parameter(name.to_string(), ord))
+            }
+            Option::Some(_) => return Err(err("a parameter which is not an int or bool")),
+            Option::None => return Err(err("a parameter without a name")),
+        },
+        "bernoulli" => {
+            OrderedExpression::Random(Distribution::Bernoulli(rational(immediate)?, ord))
+        }
+        "categorical" => {
+            let weights = immediate
+                .as_array()
+                .ok_or_else(|| err("no weights"))?
+                .iter()
+                .map(|pair| Ok((int(&pair[0])?, rational(&pair[1])?)))
+                .collect::<Result<Vec<(i8, Rational)>, String>>()?;
+            OrderedExpression::Random(Distribution::Categorical(weights.into_boxed_slice(), ord))
+        }
+        "std" => {
+            let name = value["name"].as_str().unwrap_or("");
+            match registry.get(name) {
+                Option::Some(func) if func.args().len() == ord.get_sources().len() => {
+                    OrderedExpression::StdLib(StdLibraryFunc::new(func.clone(), ord))
+                }
+                Option::Some(_) => return Err(err("the wrong number of arguments")),
+                Option::None => {
+                    return Err(err(&format!("the unknown function '{}'", name)));
+                }
+            }
+        }
+        "sum_roll_d6" => OrderedExpression::Spec(Spec::SumMultiD6(ord)),
+        "sum_roll_d3" => OrderedExpression::Spec(Spec::SumMultiD3(ord)),
+        "add" => OrderedExpression::Op(Op::Add(ord)),
+        "sub" => OrderedExpression::Op(Op::Sub(ord)),
+        "mul" => OrderedExpression::Op(Op::Mul(ord)),
+        "div" => OrderedExpression::Op(Op::Div(ord)),
+        "eq" => OrderedExpression::Op(Op::Equal(ord)),
+        "ne" => OrderedExpression::Op(Op::NotEqual(ord)),
+        "gt" => OrderedExpression::Op(Op::GreaterThan(ord)),
+        "ge" => OrderedExpression::Op(Op::GreaterThanEqual(ord)),
+        "lt" => OrderedExpression::Op(Op::LessThan(ord)),
+        "le" => OrderedExpression::Op(Op::LessThanEqual(ord)),
+        "or" => OrderedExpression::Op(Op::Or(ord)),
+        "and" => OrderedExpression::Op(Op::And(ord)),
+        op => return Err(err(&format!("the unknown operation '{}'", op))),
+    })
+}
+
+fn read_edges(value: &Value) -> Result<ExprVec, String> {
+    let mut edges = ExprVec::new();
+    for edge in value.as_array().into_iter().flat_map(|x| x.iter()) {
+        match (edge[0].as_u64(), edge[1].as_str().map(parse_type)) {
+            (Option::Some(id), Option::Some(Ok(kind))) => edges.push((id, kind)),
+            _ => return Err(format!("a malformed edge {}", edge)),
+        };
+    }
+    Ok(edges)
+}
+
+/// reads a type, as `TypeData` writes it
+fn parse_type(arg: &str) -> Result<TypeData, String> {
+    match arg {
+        "bool" => Ok(TypeData::Bool),
+        "int" => Ok(TypeData::Int),
+        "vec<bool>" => Ok(TypeData::CollectionOfBool),
+        "vec<int>" => Ok(TypeData::CollectionOfInt),
+        "deck" => Ok(TypeData::Deck),
+        _ => Err(format!("unknown type '{}'", arg)),
+    }
+}
+
+#[test]
+fn test_json_round_trip() {
+    use super::super::program::{compile, CompileOptions};
+    use super::super::runtime::build_report;
+
+    let dut = r#"
+const coin: bool = bernoulli(1/3);
+const cards: deck = deck([1, 2, 3]);
+const loaded: int = categorical([(1, 1/2), (6, 1/2)]);
+analyze (((sum(draw(cards, 2)) + sum(roll_d6(3))) > loaded) | coin);
+"#;
+    let program = compile(dut, &CompileOptions::default()).unwrap();
+    let registry = Registry::standard();
+    let text = program.get_graph().to_json().to_string();
+    let coll = OrderedCollection::from_json(&text.parse().unwrap(), &registry).unwrap();
+    assert_eq!(coll.to_string(), program.get_graph().to_string());
+    assert_eq!(
+        build_report(&coll).unwrap().serialize_report(None),
+        build_report(program.get_graph())
+            .unwrap()
+            .serialize_report(None)
+    );
+
+    // malformed graphs are rejected, rather than loaded
+    let mut broken = program.get_graph().to_json();
+    broken["return"] = json!(1);
+    assert!(OrderedCollection::from_json(&broken, &registry).is_err());
+    let mut broken = program.get_graph().to_json();
+    broken["expressions"][0]["op"] = json!("pow");
+    assert!(OrderedCollection::from_json(&broken, &registry).is_err());
+    let unknown = program.get_graph().to_json();
+    assert!(OrderedCollection::from_json(&unknown, &Registry::default()).is_err());
+}
@@ -0,0 +1,363 @@
+use std::collections::BTreeSet;
+
+use super::super::parser_output::TypeData;
+
+use super::coll::OrderedCollection;
+use super::expr::{ConstantValue, Distribution, Op, OrderedExpression};
+use super::ord::OrdTrait;
+
+const I: TypeData = TypeData::Int;
+const B: TypeData = TypeData::Bool;
+const C_I: TypeData = TypeData::CollectionOfInt;
+const C_B: TypeData = TypeData::CollectionOfBool;
+const D: TypeData = TypeData::Deck;
+
+/*
+ * Peephole rewrites edit the graph one edge at a time, so every
+ * rewrite must leave it as `OrderedExpression::new` would build it.
+ *
+ * An edge `(id, kind)` is recorded twice, as a source of the reader
+ * and as a sink of the expression it reads. `kind` is always the
+ * type of the expression read.
+ *
+ */
+
+impl OrderedCollection {
+    /// checks the graph is well formed. Returns every problem
+    /// found, rather than only the first.
+    ///
+    /// * every source & sink is within the graph
+    /// * every source is listed as a sink, and every sink as a source
+    /// * every edge has the type of the expression it reads
+    /// * every expression has the type its operation returns
+    /// * no expression reads itself, however indirectly
+    /// * the return exists, and every expression contributes to it
+    pub fn verify(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        for id in self.keys() {
+            let expr = self.get_expr(*id).unwrap();
+            if expr.get_own_id() != *id {
+                errors.push(format!(
+                    "expression {} is stored as {}",
+                    expr.get_own_id(),
+                    id
+                ));
+            }
+            verify_edges(self, expr, &mut errors);
+            if let Err(e) = verify_type(expr) {
+                errors.push(format!("expression {} ({}) {}", id, expr, e));
+            }
+        }
+        verify_acyclic(self, &mut errors);
+        if self.get_expr(self.get_return()).is_none() {
+            errors.push(format!(
+                "graph returns expression {} which it lacks",
+                self.get_return()
+            ));
+        } else {
+            let read = reachable(self);
+            for id in self.keys().filter(|id| !read.contains(id)) {
+                errors.push(format!("expression {} is never read by the return", id));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// every edge exists at both of its ends, with the type of the
+/// expression which is read
+fn verify_edges(coll: &OrderedCollection, expr: &OrderedExpression, errors: &mut Vec<String>) {
+    let id = expr.get_own_id();
+    for &(source, kind) in expr.get_sources() {
+        let source_expr = match coll.get_expr(source) {
+            Option::Some(source_expr) => source_expr,
+            Option::None => {
+                errors.push(format!(
+                    "expression {} reads expression {} which the graph lacks",
+                    id, source
+                ));
+                continue;
+            }
+        };
+        if source_expr.get_own_type() != kind {
+            errors.push(format!(
+                "expression {} reads expression {} as {}, but it is {}",
+                id,
+                source,
+                kind,
+                source_expr.get_own_type()
+            ));
+        }
+        if !source_expr.get_sinks().contains(&(id, kind)) {
+            errors.push(format!(
+                "expression {} reads expression {}, which does not list it as a sink",
+                id, source
+            ));
+        }
+    }
+    for &(sink, kind) in expr.get_sinks() {
+        let sink_expr = match coll.get_expr(sink) {
+            Option::Some(sink_expr) => sink_expr,
+            Option::None => {
+                errors.push(format!(
+                    "expression {} is read by expression {} which the graph lacks",
+                    id, sink
+                ));
+                continue;
+            }
+        };
+        if expr.get_own_type() != kind {
+            errors.push(format!(
+                "expression {} is read by expression {} as {}, but it is {}",
+                id,
+                sink,
+                kind,
+                expr.get_own_type()
+            ));
+        }
+        if !sink_expr.get_sources().contains(&(id, kind)) {
+            errors.push(format!(
+                "expression {} is read by expression {}, which does not list it as a source",
+                id, sink
+            ));
+        }
+    }
+}
+
+/// the expression has the type its operation returns, given the
+/// types of its sources
+fn verify_type(expr: &OrderedExpression) -> Result<(), String> {
+    // edges which do not agree with their source are reported on
+    // their own, so types are read from the edges
+    let sources: Vec<TypeData> = expr.get_sources().iter().map(|(_, kind)| *kind).collect();
+    let expected = match expr {
+        &OrderedExpression::Constant(ConstantValue::Bool(_, _)) => arity(&sources, &[], B)?,
+        &OrderedExpression::Constant(ConstantValue::Int(_, _)) => arity(&sources, &[], I)?,
+        &OrderedExpression::Constant(ConstantValue::Deck(_, _)) => arity(&sources, &[], D)?,
+        &OrderedExpression::Constant(ConstantValue::Parameter(_, _)) => match expr.get_own_type() {
+            I => arity(&sources, &[], I)?,
+            B => arity(&sources, &[], B)?,
+            kind => return Err(format!("is a parameter of {}, not int or bool", kind)),
+        },
+        &OrderedExpression::Random(Distribution::Bernoulli(_, _)) => arity(&sources, &[], B)?,
+        &OrderedExpression::Random(Distribution::Categorical(_, _)) => arity(&sources, &[], I)?,
+        &OrderedExpression::Spec(_) => arity(&sources, &[I], I)?,
+        &OrderedExpression::StdLib(ref func) => {
+            let args: Vec<TypeData> = func
+                .get_function()
+                .args()
+                .iter()
+                .map(|(_, kind)| *kind)
+                .collect();
+            arity(&sources, &args, func.get_function().ret())?
+        }
+        &OrderedExpression::Op(ref op) => {
+            let (a, b) = match sources.as_slice() {
+                &[a, b] => (a, b),
+                _ => return Err(format!("reads {} sources, not 2", sources.len())),
+            };
+            let collection = is_collection(a) || is_collection(b);
+            let element = |kind: TypeData| match kind {
+                C_I => I,
+                C_B => B,
+                kind => kind,
+            };
+            let (reads, returns) = match op {
+                &Op::Add(_) | &Op::Sub(_) | &Op::Mul(_) | &Op::Div(_) => (vec![I], I),
+                &Op::GreaterThan(_)
+                | &Op::GreaterThanEqual(_)
+                | &Op::LessThan(_)
+                | &Op::LessThanEqual(_) => (vec![I], B),
+                &Op::Equal(_) | &Op::NotEqual(_) => (vec![I, B], B),
+                &Op::And(_) | &Op::Or(_) => (vec![B], B),
+            };
+            if element(a) != element(b) || !reads.contains(&element(a)) {
+                return Err(format!("cannot read {} and {}", a, b));
+            }
+            if is_collection(a) && is_collection(b) {
+                return Err("cannot read two collections".to_string());
+            }
+            match (returns, collection) {
+                (I, true) => C_I,
+                (B, true) => C_B,
+                (returns, _) => returns,
+            }
+        }
+    };
+    if expr.get_own_type() != expected {
+        return Err(format!(
+            "is {}, but its operation returns {}",
+            expr.get_own_type(),
+            expected
+        ));
+    }
+    Ok(())
+}
+
+/// the sources match the arguments, returns `ret`
+fn arity(sources: &[TypeData], args: &[TypeData], ret: TypeData) -> Result<TypeData, String> {
+    if sources != args {
+        return Err(format!(
+            "reads {}, rather than {}",
+            list(sources),
+            list(args)
+        ));
+    }
+    Ok(ret)
+}
+
+fn is_collection(kind: TypeData) -> bool {
+    kind == C_I || kind == C_B
+}
+
+fn list(kinds: &[TypeData]) -> String {
+    let kinds: Vec<String> = kinds.iter().map(|kind| kind.to_string()).collect();
+    format!("({})", kinds.join(", "))
+}
+
+/// no expression is its own source, however indirectly
+fn verify_acyclic(coll: &OrderedCollection, errors: &mut Vec<String>) {
+    fn visit(
+        coll: &OrderedCollection,
+        id: u64,
+        path: &mut Vec<u64>,
+        done: &mut BTreeSet<u64>,
+        errors: &mut Vec<String>,
+    ) {
+        if done.contains(&id) {
+            return;
+        }
+        if path.contains(&id) {
+            errors.push(format!("expression {} reads itself", id));
+            return;
+        }
+        path.push(id);
+        for &(source, _) in coll
+            .get_expr(id)
+            .into_iter()
+            .flat_map(|expr| expr.get_sources())
+        {
+            visit(coll, source, path, done, errors);
+        }
+        path.pop();
+        done.insert(id);
+    }
+
+    let mut done = BTreeSet::new();
+    for id in coll.keys() {
+        visit(coll, *id, &mut Vec::new(), &mut done, errors);
+    }
+}
+
+/// every expression the return reads, however indirectly
+fn reachable(coll: &OrderedCollection) -> BTreeSet<u64> {
+    let mut read = BTreeSet::new();
+    let mut stack = vec![coll.get_return()];
+    while let Option::Some(id) = stack.pop() {
+        if !read.insert(id) {
+            continue;
+        }
+        for &(source, _) in coll
+            .get_expr(id)
+            .into_iter()
+            .flat_map(|expr| expr.get_sources())
+        {
+            stack.push(source);
+        }
+    }
+    read
+}
+
+#[test]
+fn test_verify() {
+    use super::super::program::{compile, CompileOptions};
+    use super::ord::{ExprVec, OrdType};
+
+    // every operator, at each of the types it accepts
+    let dut = r#"
+const coin: bool = bernoulli(1/2);
+const loaded: int = categorical([(1, 1/2), (6, 1/2)]);
+const cards: deck = deck([1, 2, 3, 4]);
+const dice: vec<int> = roll_d6(3);
+const high: vec<bool> = ((dice + 1) > 2);
+const low: vec<bool> = (loaded >= (1 - dice));
+const hits: int = (count(((coin == high) | coin)) + count((coin & (low != coin))));
+analyze ((hits <= ((len(dice) * 2) / loaded))
+    & ((sum(draw(cards, 2)) == loaded) | (coin != (sum(dice) < 7))));
+"#;
+    let program = compile(dut, &CompileOptions::default()).unwrap();
+    assert_eq!(
+        program.order(program.get_bindings()).unwrap().verify(),
+        Ok(())
+    );
+    assert_eq!(program.get_graph().verify(), Ok(()));
+
+    // `%3 = add %1, %2`, with whatever edges each case gives it
+    let int = |id: u64, value: i8, sinks: &[(u64, TypeData)]| {
+        let mut ord = OrdType::new(id, I, ExprVec::new());
+        for &(sink, kind) in sinks {
+            ord.add_sink(sink, kind);
+        }
+        OrderedExpression::Constant(ConstantValue::Int(value, ord))
+    };
+    let add = |id: u64, sources: &[(u64, TypeData)], sinks: &[(u64, TypeData)]| {
+        let mut ord = OrdType::new(id, I, sources.iter().cloned().collect());
+        for &(sink, kind) in sinks {
+            ord.add_sink(sink, kind);
+        }
+        OrderedExpression::Op(Op::Add(ord))
+    };
+    let errors = |exprs: Vec<OrderedExpression>, ret: u64| {
+        OrderedCollection::from_exprs(exprs, ret)
+            .verify()
+            .unwrap_err()
+            .join("\n")
+    };
+    let valid = vec![
+        int(1, 1, &[(3, I)]),
+        int(2, 2, &[(3, I)]),
+        add(3, &[(1, I), (2, I)], &[]),
+    ];
+    assert_eq!(
+        OrderedCollection::from_exprs(valid.clone(), 3).verify(),
+        Ok(())
+    );
+    assert!(errors(valid.clone(), 4).contains("returns expression 4 which it lacks"));
+
+    let mut dead = valid.clone();
+    dead.push(int(4, 4, &[]));
+    assert!(errors(dead, 3).contains("expression 4 is never read by the return"));
+
+    let asymmetric = vec![
+        int(1, 1, &[(3, I)]),
+        int(2, 2, &[]),
+        add(3, &[(1, I), (2, I)], &[]),
+    ];
+    assert!(errors(asymmetric, 3).contains("reads expression 2, which does not list it as a sink"));
+
+    let dangling = vec![int(1, 1, &[(3, I), (5, I)]), add(3, &[(1, I), (4, I)], &[])];
+    let dangling = errors(dangling, 3);
+    assert!(dangling.contains("expression 3 reads expression 4 which the graph lacks"));
+    assert!(dangling.contains("expression 1 is read by expression 5 which the graph lacks"));
+
+    let mistyped = vec![
+        int(1, 1, &[(3, B)]),
+        int(2, 2, &[(3, I)]),
+        add(3, &[(1, B), (2, I)], &[]),
+    ];
+    let mistyped = errors(mistyped, 3);
+    assert!(mistyped.contains("expression 3 reads expression 1 as bool, but it is int"));
+    assert!(mistyped.contains("cannot read bool and int"));
+
+    let cyclic = vec![
+        int(1, 1, &[(3, I)]),
+        int(2, 2, &[(4, I)]),
+        add(3, &[(1, I), (4, I)], &[(4, I)]),
+        add(4, &[(3, I), (2, I)], &[(3, I)]),
+    ];
+    assert!(errors(cyclic, 3).contains("reads itself"));
+}
@@ -1,9 +1,12 @@
 use std::fmt;
+use std::mem::replace;
 
+use super::comment::Comment;
+use super::span::Span;
 use super::structures::Structures;
 
+use super::super::diagnostic::{Code, Diagnostic};
 use super::super::lalrpop_util::ParseError;
-use super::super::syntaxhelper::CharacterLookup;
 
 use super::super::value::TreeParser;
 
@@ -13,6 +16,8 @@ use super::super::value::TreeParser;
 /// complete to ensure that literals are well formed.
 pub struct AbstractSyntaxTree<'a> {
     pub ast: Box<[Structures<'a>]>,
+    /// comments between the top level items, in source order
+    pub comments: Box<[Comment<'a>]>,
 }
 impl<'a> fmt::Display for AbstractSyntaxTree<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -23,28 +28,206 @@ impl<'a> fmt::Display for AbstractSyntaxTree<'a> {
     }
 }
 impl<'a> AbstractSyntaxTree<'a> {
-    pub fn new(args: Vec<(Structures<'a>)>) -> AbstractSyntaxTree<'a> {
+    pub fn new(args: Vec<Structures<'a>>) -> AbstractSyntaxTree<'a> {
         let ast = args.into_boxed_slice();
-        AbstractSyntaxTree { ast }
+        AbstractSyntaxTree {
+            ast,
+            comments: vec![].into_boxed_slice(),
+        }
+    }
+
+    /// builds the tree from the parser's mix of comments & items.
+    /// Items which failed to parse are `None`.
+    ///
+    /// `///` comments directly before a declaration become its
+    /// documentation, they are still kept as comments.
+    pub fn from_items(
+        items: Vec<(Option<Comment<'a>>, Option<Structures<'a>>)>,
+    ) -> AbstractSyntaxTree<'a> {
+        let mut ast = Vec::with_capacity(items.len());
+        let mut comments = Vec::new();
+        let mut doc = Vec::new();
+        for (comment, item) in items {
+            match comment.as_ref().map(Comment::get_doc) {
+                Option::Some(Option::Some(line)) => doc.push(line),
+                Option::Some(Option::None) => doc.clear(),
+                Option::None => {}
+            };
+            comments.extend(comment);
+            match item {
+                Option::Some(mut item) => {
+                    item.set_doc(std::mem::take(&mut doc));
+                    ast.push(item);
+                }
+                Option::None => {}
+            };
+        }
+        AbstractSyntaxTree {
+            ast: ast.into_boxed_slice(),
+            comments: comments.into_boxed_slice(),
+        }
     }
 
     /// Parse will attempt to construct an abstract syntax tree from the input
-    pub fn parse<'b>(input: &'b str) -> Result<AbstractSyntaxTree<'b>, String> {
-        let index = CharacterLookup::new(input);
-        match TreeParser::new().parse(input) {
-            Ok(tree) => Ok(tree),
-            Err(ParseError::InvalidToken{ location }) => {
-                Err(format!("Unable to parse: InvalidToken.\n character: {} line: {} \n {} \n", index.get_char(location), index.get_line_number(location), index.get_line(location)))
-            },
-            Err(ParseError::UnrecognizedEOF{ location: _, expected: _}) => {
-                Err(format!("File terminated before it should"))
-            },
-            Err(ParseError::UnrecognizedToken{token: (a,_,b), expected }) => {
-                Err(format!("Unable to parse: UnreconginzedToken.\n start_line: {} ending_line: {}\n Offending section:\"{}\"\n{}", index.get_line_number(a), index.get_line_number(b), index.get_span(a,b), index.get_span_lines(a,b)))
-            },
-            Err(_) => {
-                unreachable!()
+    ///
+    /// the parser recovers from errors at the next `;`, so every
+    /// syntax error in the input is returned, not just the first.
+    pub fn parse<'b>(input: &'b str) -> Result<AbstractSyntaxTree<'b>, Vec<Diagnostic>> {
+        let mut recovered = Vec::new();
+        let result = TreeParser::new().parse(&mut recovered, input);
+        let mut errors: Vec<Diagnostic> = recovered
+            .into_iter()
+            .map(|recovery| syntax_error(input, recovery.error))
+            .collect();
+        match result {
+            Ok(tree) => {
+                if errors.is_empty() {
+                    return Ok(tree);
+                }
             }
+            Err(e) => errors.push(syntax_error(input, e)),
+        };
+        Err(errors)
+    }
+}
+
+/// converts an error from the parser into a diagnostic
+fn syntax_error<T: fmt::Display>(
+    input: &str,
+    error: ParseError<usize, T, (Span, &'static str)>,
+) -> Diagnostic {
+    match error {
+        ParseError::InvalidToken { location } => {
+            let end = input[location..]
+                .chars()
+                .next()
+                .map(|c| location + c.len_utf8())
+                .unwrap_or(location);
+            Diagnostic::error(
+                Code::SyntaxError,
+                format!("Unable to parse: invalid token \"{}\"", &input[location..end]),
+            )
+            .with_label(Span::new(location, end), "not recognized")
+        }
+        ParseError::UnrecognizedEOF { location, expected } => {
+            Diagnostic::error(Code::SyntaxError, "File terminated before it should")
+                .with_label(Span::new(location, location), "unexpected end of file")
+                .with_note(expected_tokens(&expected))
         }
+        ParseError::UnrecognizedToken {
+            token: (start, _, end),
+            ..
+        } if input[start..end].starts_with("//") => Diagnostic::error(
+            Code::SyntaxError,
+            "Unable to parse: comments may only be written between items & statements",
+        )
+        .with_label(Span::new(start, end), "comment within a declaration or expression")
+        .with_note("move the comment onto its own line, before the statement"),
+        ParseError::UnrecognizedToken {
+            token: (start, token, end),
+            expected,
+        } => Diagnostic::error(
+            Code::SyntaxError,
+            format!("Unable to parse: unexpected token \"{}\"", token),
+        )
+        .with_label(Span::new(start, end), "unexpected token")
+        .with_note(expected_tokens(&expected)),
+        ParseError::ExtraToken {
+            token: (start, token, end),
+        } => Diagnostic::error(
+            Code::SyntaxError,
+            format!("Unable to parse: extra token \"{}\"", token),
+        )
+        .with_label(Span::new(start, end), "expected the end of the file"),
+        ParseError::User {
+            error: (span, message),
+        } => Diagnostic::error(Code::SyntaxError, format!("Unable to parse: {}", message))
+            .with_label(span, ""),
+    }
+}
+
+/// lists the tokens the parser would have accepted
+fn expected_tokens(expected: &[String]) -> String {
+    let mut names: Vec<String> = expected.iter().filter_map(|token| token_name(token)).collect();
+    names.dedup();
+    match names.len() {
+        0 => "no further input was expected".to_string(),
+        1 => format!("expected {}", names[0]),
+        _ => format!("expected one of {}", names.join(", ")),
     }
 }
+
+/// lalrpop describes terminals as they are written in the grammar,
+/// this converts them into something readable.
+///
+/// comments may be written almost anywhere, so they are never
+/// listed as expected.
+fn token_name(token: &str) -> Option<String> {
+    if token.starts_with("r#") {
+        return match token {
+            r##"r#"//[^\\n]*"#"## => None,
+            r##"r#"[a-zA-Z][A-Za-z0-9_]*"#"## => Some("identifier".to_string()),
+            r##"r#"-?[0-9]+"#"## => Some("integer".to_string()),
+            r##"r#"[0-9]+/[0-9]+"#"## => Some("rational".to_string()),
+            r##"r#"\"[^\"]*\""#"## => Some("string".to_string()),
+            _ => Some(format!("`{}`", token)),
+        };
+    }
+    let unquoted = token.trim_matches('"').replace("\\\"", "\"");
+    Some(format!("`{}`", unquoted))
+}
+
+#[test]
+fn test_parse_errors() {
+    // both statements are reported
+    let errors = match AbstractSyntaxTree::parse("analyze (1 + ;\nconst x: int = ;\nanalyze 1;") {
+        Ok(_) => panic!("source has two syntax errors"),
+        Err(errors) => errors,
+    };
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].get_labels()[0].span.start, 13);
+    assert!(errors[0].get_notes()[0].contains("integer"), "{:?}", errors[0]);
+    assert_eq!(errors[1].get_labels()[0].span.start, 30);
+
+    // errors without a recovery point still have a location
+    let errors = match AbstractSyntaxTree::parse("analyze roll_d6(") {
+        Ok(_) => panic!("source ends early"),
+        Err(errors) => errors,
+    };
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].get_labels()[0].span.start, 16);
+    assert!(errors[0].get_notes()[0].contains("`)`"), "{:?}", errors[0]);
+
+    // literals which are out of range are not a panic
+    let errors = match AbstractSyntaxTree::parse("analyze 300;") {
+        Ok(_) => panic!("300 does not fit within an int"),
+        Err(errors) => errors,
+    };
+    assert_eq!(errors[0].get_labels()[0].span.start, 8);
+    assert_eq!(errors[0].get_labels()[0].span.end, 11);
+    assert!(AbstractSyntaxTree::parse("analyze $;").is_err());
+}
+
+#[test]
+fn test_expected_tokens() {
+    let note = |source: &str| match AbstractSyntaxTree::parse(source) {
+        Ok(_) => panic!("{} should not parse", source),
+        Err(errors) => errors[0].get_notes()[0].clone(),
+    };
+
+    // items are expected at the top level, neither comments nor
+    // statements are listed
+    let expected = "expected one of `#[allow(`, `analyze`, `const`, `fn`";
+    assert_eq!(note("cnst x: int = 1;"), expected);
+    assert_eq!(note("#[allow(unused)]\ncnst x: int = 1;"), expected);
+
+    // within a function, statements are expected
+    assert_eq!(
+        note("fn f() -> int {\n    lt x = 1;\n    return x;\n}"),
+        "expected one of `#[allow(`, `let`, `return`, `}`"
+    );
+    assert_eq!(
+        note("fn f() -> int {\n    #[allow(unused)]\n    lt x = 1;\n    return 1;\n}"),
+        "expected one of `#[allow(`, `let`"
+    );
+}
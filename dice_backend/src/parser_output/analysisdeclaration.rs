@@ -1,14 +1,25 @@
 use std::fmt;
 
+use super::allows;
 use super::expression::Expression;
+use super::span::Span;
 
 /// AnalysisDeclaraction is one of the last top level structures.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct AnalysisDeclaration<'a> {
     pub expr: Expression<'a>,
+    pub span: Span,
+    /// lints which are allowed by `#[allow(..)]`
+    pub allow: Box<[&'a str]>,
+}
+impl<'a> AnalysisDeclaration<'a> {
+    /// checks if a lint is allowed by an attribute
+    pub fn allows(&self, lint: &str) -> bool {
+        allows(&self.allow, lint)
+    }
 }
 impl<'a> fmt::Display for AnalysisDeclaration<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "analyze {};\n", self.expr)
+        writeln!(f, "analyze {};", self.expr)
     }
 }
@@ -0,0 +1,37 @@
+use std::fmt;
+
+use super::span::Span;
+
+/// Comment is a `//` line comment.
+///
+/// Comments may be written between items & statements, the
+/// parser keeps them so the formatter can preserve them.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Comment<'a> {
+    pub text: &'a str,
+    pub span: Span,
+}
+impl<'a> Comment<'a> {
+    #[inline(always)]
+    pub fn new(text: &'a str, span: Span) -> Comment<'a> {
+        Comment {
+            text: text.trim_end(),
+            span,
+        }
+    }
+
+    /// the text of a `///` doc comment, without the slashes &
+    /// the space which follows them. `None` for other comments.
+    pub fn get_doc(&self) -> Option<&'a str> {
+        if !self.text.starts_with("///") || self.text.starts_with("////") {
+            return None;
+        }
+        let doc = &self.text[3..];
+        Some(doc.strip_prefix(' ').unwrap_or(doc))
+    }
+}
+impl<'a> fmt::Display for Comment<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
@@ -1,17 +1,45 @@
 use std::fmt;
 
 use super::expression::Expression;
+use super::span::Span;
 use super::typedata::TypeData;
+use super::{allows, annotation};
 
 /// ConstantDeclaration is when a constant is declared globally.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ConstantDeclaration<'a> {
     pub name: &'a str,
-    pub kind: TypeData,
+    /// the annotated type, when absent it is inferred
+    pub kind: Option<TypeData>,
     pub expr: Expression<'a>,
+    pub span: Span,
+    /// lints which are allowed by `#[allow(..)]`
+    pub allow: Box<[&'a str]>,
+    /// lines of the `///` comments before the declaration
+    pub doc: Box<[&'a str]>,
+}
+impl<'a> ConstantDeclaration<'a> {
+    /// checks if a lint is allowed by an attribute
+    pub fn allows(&self, lint: &str) -> bool {
+        allows(&self.allow, lint)
+    }
+
+    /// the declaration without its value, `const name: int`. The
+    /// type is the annotation, or else `inferred` when it is known.
+    pub fn get_signature(&self, inferred: Option<TypeData>) -> String {
+        match self.kind.or(inferred) {
+            Option::Some(kind) => format!("const {}: {}", self.name, kind),
+            Option::None => format!("const {}", self.name),
+        }
+    }
+
+    /// the documentation of the constant
+    pub fn get_doc(&self) -> String {
+        self.doc.join("\n")
+    }
 }
 impl<'a> fmt::Display for ConstantDeclaration<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "const {}: {} = {} ;", self.name, self.kind, self.expr)
+        write!(f, "const {}{} = {} ;", self.name, annotation(self.kind), self.expr)
     }
 }
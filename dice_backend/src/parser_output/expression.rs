@@ -5,6 +5,7 @@ use super::literal::Literal;
 use super::literalvalue::LiteralValue;
 use super::operation::Operation;
 use super::operationresult::OperationResult;
+use super::span::Span;
 use super::typedata::TypeData;
 use super::varreference::VariableReference;
 
@@ -66,108 +67,128 @@ fn test_expression_parsing() {
 
     // Literal Tests
     assert!(
-        parser.parse("15").unwrap()
+        parser.parse(&mut Vec::new(), "15").unwrap()
             == Expression::Literal(LiteralValue {
-                lit: Literal::Number(15i8)
+                lit: Literal::Number(15i8),
+                span: Span::default(),
             })
     );
     assert!(
-        parser.parse("-35").unwrap()
+        parser.parse(&mut Vec::new(), "-35").unwrap()
             == Expression::Literal(LiteralValue {
-                lit: Literal::Number(-35i8)
+                lit: Literal::Number(-35i8),
+                span: Span::default(),
             })
     );
     assert!(
-        parser.parse("-35").unwrap()
+        parser.parse(&mut Vec::new(), "-35").unwrap()
             == Expression::Literal(LiteralValue {
-                lit: Literal::Number(-35i8)
+                lit: Literal::Number(-35i8),
+                span: Span::default(),
             })
     );
     assert!(
-        parser.parse("false").unwrap()
+        parser.parse(&mut Vec::new(), "false").unwrap()
             == Expression::Literal(LiteralValue {
-                lit: Literal::Boolean(false)
+                lit: Literal::Boolean(false),
+                span: Span::default(),
             })
     );
     assert!(
-        parser.parse("true").unwrap()
+        parser.parse(&mut Vec::new(), "true").unwrap()
             == Expression::Literal(LiteralValue {
-                lit: Literal::Boolean(true)
+                lit: Literal::Boolean(true),
+                span: Span::default(),
             })
     );
     assert!(
-        parser.parse("15").unwrap()
+        parser.parse(&mut Vec::new(), "15").unwrap()
             == Expression::Literal(LiteralValue {
-                lit: Literal::Number(15i8)
+                lit: Literal::Number(15i8),
+                span: Span::default(),
             })
     );
     assert!(
-        parser.parse("-30").unwrap()
+        parser.parse(&mut Vec::new(), "-30").unwrap()
             == Expression::Literal(LiteralValue {
-                lit: Literal::Number(-30i8)
+                lit: Literal::Number(-30i8),
+                span: Span::default(),
             })
     );
     assert!(
-        parser.parse("%d{{ENV_VAR}}").unwrap()
+        parser.parse(&mut Vec::new(), "%d{{ENV_VAR}}").unwrap()
             == Expression::Literal(LiteralValue {
-                lit: Literal::EnvirNumber("ENV_VAR")
+                lit: Literal::EnvirNumber("ENV_VAR"),
+                span: Span::default(),
             })
     );
     assert!(
-        parser.parse("%b{{ENV_VAR}}").unwrap()
+        parser.parse(&mut Vec::new(), "%b{{ENV_VAR}}").unwrap()
             == Expression::Literal(LiteralValue {
-                lit: Literal::EnvirBool("ENV_VAR")
+                lit: Literal::EnvirBool("ENV_VAR"),
+                span: Span::default(),
             })
     );
 
     // Operation Tests
     assert!(
-        parser.parse("( false | true )").unwrap()
+        parser.parse(&mut Vec::new(), "( false | true )").unwrap()
             == Expression::Operation(OperationResult {
                 left: Box::new(Expression::Literal(LiteralValue {
-                    lit: Literal::Boolean(false)
+                    lit: Literal::Boolean(false),
+                    span: Span::default(),
                 })),
                 op: Operation::Or,
                 right: Box::new(Expression::Literal(LiteralValue {
-                    lit: Literal::Boolean(true)
-                }))
+                    lit: Literal::Boolean(true),
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             })
     );
     assert!(
-        parser.parse("( 20 + %d{{INPUT_VALUE_TEST}} )").unwrap()
+        parser.parse(&mut Vec::new(), "( 20 + %d{{INPUT_VALUE_TEST}} )").unwrap()
             == Expression::Operation(OperationResult {
                 left: Box::new(Expression::Literal(LiteralValue {
-                    lit: Literal::Number(20)
+                    lit: Literal::Number(20),
+                    span: Span::default(),
                 })),
                 op: Operation::Add,
                 right: Box::new(Expression::Literal(LiteralValue {
-                    lit: Literal::EnvirNumber("INPUT_VALUE_TEST")
-                }))
+                    lit: Literal::EnvirNumber("INPUT_VALUE_TEST"),
+                    span: Span::default(),
+                })),
+                span: Span::default(),
             })
     );
 
     // Variable Tests
     assert!(
-        parser.parse("helloWorld").unwrap()
-            == Expression::Variable(VariableReference { name: "helloWorld" })
+        parser.parse(&mut Vec::new(), "helloWorld").unwrap()
+            == Expression::Variable(VariableReference {
+                name: "helloWorld",
+                span: Span::default(),
+            })
     );
 
     // function test
     assert!(
-        parser.parse("roll_d6(%d{{INPUT_VALUE}})").unwrap()
+        parser.parse(&mut Vec::new(), "roll_d6(%d{{INPUT_VALUE}})").unwrap()
             == Expression::Func(FunctionInvocation {
                 name: "roll_d6",
                 args: vec![Expression::Literal(LiteralValue {
-                    lit: Literal::EnvirNumber("INPUT_VALUE")
+                    lit: Literal::EnvirNumber("INPUT_VALUE"),
+                    span: Span::default(),
                 })]
-                .into_boxed_slice()
+                .into_boxed_slice(),
+                span: Span::default(),
             })
     );
 }
 impl<'a> Expression<'a> {
     #[inline(always)]
-    pub fn new_literal(lit: Literal<'a>) -> Self {
-        Expression::Literal(LiteralValue { lit })
+    pub fn new_literal(lit: Literal<'a>, span: Span) -> Self {
+        Expression::Literal(LiteralValue { lit, span })
     }
 
     #[inline(always)]
@@ -175,6 +196,7 @@ impl<'a> Expression<'a> {
         name: &'a str,
         args: Vec<(Expression<'a>, &'a str)>,
         arg: Option<Expression<'a>>,
+        span: Span,
     ) -> Self {
         #[inline(always)]
         fn tuple_mapper<'a>(arg: (Expression<'a>, &'a str)) -> Expression<'a> {
@@ -183,12 +205,13 @@ impl<'a> Expression<'a> {
         Expression::Func(FunctionInvocation {
             name,
             args: args.into_iter().map(tuple_mapper).chain(arg).collect(),
+            span,
         })
     }
 
     #[inline(always)]
-    pub fn new_var(name: &'a str) -> Self {
-        Expression::Variable(VariableReference { name })
+    pub fn new_var(name: &'a str, span: Span) -> Self {
+        Expression::Variable(VariableReference { name, span })
     }
 
     #[inline(always)]
@@ -196,11 +219,23 @@ impl<'a> Expression<'a> {
         left: Expression<'a>,
         op: Operation,
         right: Expression<'a>,
+        span: Span,
     ) -> Expression<'a> {
         Expression::Operation(OperationResult {
             left: Box::new(left),
             right: Box::new(right),
             op,
+            span,
         })
     }
+
+    /// returns where the expression was written
+    pub fn get_span(&self) -> Span {
+        match self {
+            Expression::Func(ref func) => func.span,
+            Expression::Literal(ref lit) => lit.span,
+            Expression::Operation(ref op) => op.span,
+            Expression::Variable(ref var) => var.span,
+        }
+    }
 }
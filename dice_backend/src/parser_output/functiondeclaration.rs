@@ -1,7 +1,8 @@
 use std::fmt;
 
+use super::span::Span;
 use super::typedata::TypeData;
-use super::Statements;
+use super::{allows, Statements};
 
 /// ConstantDeclaration is when a constant is declared globally.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -11,6 +12,33 @@ pub struct FunctionDeclaration<'a> {
     pub args: Box<[(&'a str, TypeData)]>,
     pub ret: TypeData,
     pub body: Statements<'a>,
+    pub span: Span,
+    /// lints which are allowed by `#[allow(..)]`
+    pub allow: Box<[&'a str]>,
+    /// lines of the `///` comments before the declaration, or
+    /// the description of a standard library function
+    pub doc: Box<[&'a str]>,
+}
+impl<'a> FunctionDeclaration<'a> {
+    /// checks if a lint is allowed by an attribute
+    pub fn allows(&self, lint: &str) -> bool {
+        allows(&self.allow, lint)
+    }
+
+    /// the declaration without its body, `fn name(x: int) -> int`
+    pub fn get_signature(&self) -> String {
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|(name, kind)| format!("{}: {}", name, kind))
+            .collect();
+        format!("fn {}({}) -> {}", self.name, args.join(", "), self.ret)
+    }
+
+    /// the documentation of the function
+    pub fn get_doc(&self) -> String {
+        self.doc.join("\n")
+    }
 }
 impl<'a> fmt::Display for FunctionDeclaration<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
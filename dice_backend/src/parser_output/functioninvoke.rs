@@ -2,6 +2,7 @@ use std::fmt;
 
 use super::expression::Expression;
 use super::typedata::TypeData;
+use super::span::Span;
 
 use super::GetType;
 
@@ -9,6 +10,7 @@ use super::GetType;
 pub struct FunctionInvocation<'a> {
     pub name: &'a str,
     pub args: Box<[Expression<'a>]>,
+    pub span: Span,
 }
 impl<'a> fmt::Display for FunctionInvocation<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -30,8 +32,9 @@ impl<'a> GetType for FunctionInvocation<'a> {
     }
 
     fn get_type(&self) -> Result<TypeData, String> {
-        Err(format!(
+        Err(
             "FunctionInvocation requires its name be looked up prior to resolving typing"
-        ))
+                .to_string(),
+        )
     }
 }
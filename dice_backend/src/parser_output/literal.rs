@@ -1,5 +1,6 @@
 use std::fmt;
 
+use super::rational::Rational;
 use super::typedata::TypeData;
 
 use super::GetType;
@@ -10,12 +11,26 @@ use super::GetType;
 ///
 /// There is some fancier types for loading things from the
 /// programs environment as well.
+///
+/// `Bernoulli` and `Categorical` are literal distributions,
+/// they carry exact weights rather than a single value.
+///
+/// `LoadDistribution` is the path of a CSV file of value &
+/// probability pairs. The namespace replaces it with a
+/// `Categorical` once the file has been read.
+///
+/// `Deck` is the face list of a deck of cards, which can
+/// be drawn from without replacement.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Literal<'a> {
     Number(i8),
     Boolean(bool),
     EnvirBool(&'a str),
     EnvirNumber(&'a str),
+    Bernoulli(Rational),
+    Categorical(Box<[(i8, Rational)]>),
+    Deck(Box<[i8]>),
+    LoadDistribution(&'a str),
 }
 impl<'a> GetType for Literal<'a> {
     fn requires_namespace(&self) -> bool {
@@ -24,8 +39,39 @@ impl<'a> GetType for Literal<'a> {
 
     fn get_type(&self) -> Result<TypeData, String> {
         match self {
-            Literal::Number(_) | Literal::EnvirNumber(_) => Ok(TypeData::Int),
+            Literal::Number(_) | Literal::EnvirNumber(_) | Literal::LoadDistribution(_) => {
+                Ok(TypeData::Int)
+            }
             Literal::Boolean(_) | Literal::EnvirBool(_) => Ok(TypeData::Bool),
+            Literal::Bernoulli(ref prob) => {
+                if prob.complement().is_none() {
+                    Err(format!("bernoulli probability {} is greater than 1", prob))
+                } else {
+                    Ok(TypeData::Bool)
+                }
+            }
+            Literal::Deck(ref cards) => {
+                if cards.is_empty() {
+                    Err(format!("deck {} has no cards", self))
+                } else {
+                    Ok(TypeData::Deck)
+                }
+            }
+            Literal::Categorical(ref weights) => {
+                let total = weights
+                    .iter()
+                    .try_fold(Rational::zero(), |total, (_, weight)| total.checked_add(weight));
+                match total {
+                    Option::None => Err(format!(
+                        "categorical distribution {} has weights too precise to sum exactly",
+                        self
+                    )),
+                    Option::Some(ref total) if total.is_zero() => {
+                        Err(format!("categorical distribution {} has no weight", self))
+                    }
+                    Option::Some(_) => Ok(TypeData::Int),
+                }
+            }
         }
     }
 }
@@ -42,6 +88,28 @@ impl<'a> fmt::Display for Literal<'a> {
             }
             Literal::EnvirBool(ref name) => write!(f, "%b{{{{{}}}}}", name),
             Literal::EnvirNumber(ref name) => write!(f, "%d{{{{{}}}}}", name),
+            Literal::Bernoulli(ref prob) => write!(f, "bernoulli({})", prob),
+            Literal::Categorical(ref weights) => {
+                write!(f, "categorical([")?;
+                for (pos, (value, weight)) in weights.iter().enumerate() {
+                    if pos != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "({}, {})", value, weight)?;
+                }
+                write!(f, "])")
+            }
+            Literal::LoadDistribution(ref path) => write!(f, "load_distribution(\"{}\")", path),
+            Literal::Deck(ref cards) => {
+                write!(f, "deck([")?;
+                for (pos, card) in cards.iter().enumerate() {
+                    if pos != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", card)?;
+                }
+                write!(f, "])")
+            }
         }
     }
 }
@@ -51,10 +119,34 @@ fn test_literal_parsing() {
     use super::super::value::LitParser;
 
     let parser = LitParser::new();
-    assert!(parser.parse("false").unwrap() == Literal::Boolean(false));
-    assert!(parser.parse("true").unwrap() == Literal::Boolean(true));
-    assert!(parser.parse("15").unwrap() == Literal::Number(15i8));
-    assert!(parser.parse("-30").unwrap() == Literal::Number(-30i8));
-    assert!(parser.parse("%d{{ENV_VAR}}").unwrap() == Literal::EnvirNumber("ENV_VAR"));
-    assert!(parser.parse("%b{{ENV_VAR}}").unwrap() == Literal::EnvirBool("ENV_VAR"));
+    assert!(parser.parse(&mut Vec::new(), "false").unwrap() == Literal::Boolean(false));
+    assert!(parser.parse(&mut Vec::new(), "true").unwrap() == Literal::Boolean(true));
+    assert!(parser.parse(&mut Vec::new(), "15").unwrap() == Literal::Number(15i8));
+    assert!(parser.parse(&mut Vec::new(), "-30").unwrap() == Literal::Number(-30i8));
+    assert!(parser.parse(&mut Vec::new(), "%d{{ENV_VAR}}").unwrap() == Literal::EnvirNumber("ENV_VAR"));
+    assert!(parser.parse(&mut Vec::new(), "%b{{ENV_VAR}}").unwrap() == Literal::EnvirBool("ENV_VAR"));
+    assert!(parser.parse(&mut Vec::new(), "bernoulli(1/3)").unwrap() == Literal::Bernoulli(Rational::new(1, 3)));
+    assert!(
+        parser.parse(&mut Vec::new(), "categorical([(1, 1/2), (4, 2/4), (6, 1)])").unwrap()
+            == Literal::Categorical(
+                vec![
+                    (1, Rational::new(1, 2)),
+                    (4, Rational::new(1, 2)),
+                    (6, Rational::one())
+                ]
+                .into_boxed_slice()
+            )
+    );
+    assert!(
+        parser.parse(&mut Vec::new(), "deck([1, 1, 2])").unwrap()
+            == Literal::Deck(vec![1, 1, 2].into_boxed_slice())
+    );
+    assert!(Literal::Deck(vec![].into_boxed_slice()).get_type().is_err());
+    assert!(
+        parser.parse(&mut Vec::new(), r#"load_distribution("tables/hits.csv")"#).unwrap()
+            == Literal::LoadDistribution("tables/hits.csv")
+    );
+    assert!(parser.parse(&mut Vec::new(), "bernoulli(1/0)").is_err());
+    assert!(parser.parse(&mut Vec::new(), "300").is_err());
+    assert!(Literal::Bernoulli(Rational::new(4, 3)).get_type().is_err());
 }
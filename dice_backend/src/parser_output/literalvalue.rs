@@ -2,6 +2,7 @@ use std::fmt;
 
 use super::literal::Literal;
 use super::typedata::TypeData;
+use super::span::Span;
 
 use super::GetType;
 
@@ -11,6 +12,7 @@ use super::GetType;
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct LiteralValue<'a> {
     pub lit: Literal<'a>,
+    pub span: Span,
 }
 impl<'a> GetType for LiteralValue<'a> {
     fn requires_namespace(&self) -> bool {
@@ -8,6 +8,15 @@ pub use self::operation::Operation;
 mod typedata;
 pub use self::typedata::TypeData;
 
+mod rational;
+pub use self::rational::Rational;
+
+mod span;
+pub use self::span::Span;
+
+mod comment;
+pub use self::comment::Comment;
+
 mod literal;
 pub use self::literal::Literal;
 
@@ -61,3 +70,22 @@ pub trait GetType {
     /// returns typing information.
     fn get_type(&self) -> Result<TypeData, String>;
 }
+
+/// checks if a list of `#[allow(..)]` attributes covers a lint.
+///
+/// `unused` covers every `unused_*` lint, and `warnings` covers
+/// every lint.
+pub fn allows(allow: &[&str], lint: &str) -> bool {
+    allow.iter().any(|name| {
+        *name == lint || *name == "warnings" || (*name == "unused" && lint.starts_with("unused_"))
+    })
+}
+
+/// the `: int` of a declaration, which is empty when the type
+/// is inferred.
+pub fn annotation(kind: Option<TypeData>) -> String {
+    match kind {
+        Option::Some(kind) => format!(": {}", kind),
+        Option::None => String::new(),
+    }
+}
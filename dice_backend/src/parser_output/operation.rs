@@ -40,8 +40,8 @@ fn test_operation_parsing() {
     use super::super::value::OpParser;
 
     let parser = OpParser::new();
-    assert!(parser.parse("+").unwrap() == Operation::Add);
-    assert!(parser.parse("-").unwrap() == Operation::Sub);
-    assert!(parser.parse("*").unwrap() == Operation::Mul);
-    assert!(parser.parse("/").unwrap() == Operation::Div);
+    assert!(parser.parse(&mut Vec::new(), "+").unwrap() == Operation::Add);
+    assert!(parser.parse(&mut Vec::new(), "-").unwrap() == Operation::Sub);
+    assert!(parser.parse(&mut Vec::new(), "*").unwrap() == Operation::Mul);
+    assert!(parser.parse(&mut Vec::new(), "/").unwrap() == Operation::Div);
 }
@@ -3,6 +3,7 @@ use std::fmt;
 use super::expression::Expression;
 use super::operation::Operation;
 use super::typedata::TypeData;
+use super::span::Span;
 
 use super::GetType;
 
@@ -11,6 +12,7 @@ pub struct OperationResult<'a> {
     pub left: Box<Expression<'a>>,
     pub op: Operation,
     pub right: Box<Expression<'a>>,
+    pub span: Span,
 }
 impl<'a> fmt::Display for OperationResult<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -24,12 +26,13 @@ impl<'a> GetType for OperationResult<'a> {
 
     fn get_type(&self) -> Result<TypeData, String> {
         if self.requires_namespace() {
-            return Err(format!(
+            return Err(
                 "interior expressions require namespacing for this operation to complete"
-            ));
+                    .to_string(),
+            );
         }
-        let left = self.left.get_type()?;
-        let right = self.right.get_type()?;
+        self.left.get_type()?;
+        self.right.get_type()?;
         panic!("todo")
     }
 }
@@ -0,0 +1,248 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Rational is an exact, non-negative fraction.
+///
+/// It is used for probability literals such as `1/3` and
+/// for the weights the runtime carries, so that repeated
+/// multiplication doesn't accumulate floating point error.
+///
+/// The value is always stored fully reduced, which means the
+/// derived `Eq` & `Hash` implementations compare values.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: u128,
+    den: u128,
+}
+impl Rational {
+    /// build a new rational, reducing the fraction.
+    ///
+    /// panics if the denominator is zero.
+    pub fn new(num: u128, den: u128) -> Rational {
+        if den == 0 {
+            panic!("rational with a zero denominator");
+        }
+        let div = gcd(num, den);
+        Rational {
+            num: num / div,
+            den: den / div,
+        }
+    }
+
+    /// the rational `0/1`
+    pub fn zero() -> Rational {
+        Rational { num: 0, den: 1 }
+    }
+
+    /// the rational `1/1`
+    pub fn one() -> Rational {
+        Rational { num: 1, den: 1 }
+    }
+
+    /// parses `"a/b"`, `"a"`, or a decimal such as `"0.25"`
+    pub fn parse(arg: &str) -> Result<Rational, String> {
+        if arg.contains('.') {
+            return Rational::parse_decimal(arg);
+        }
+        let mut iter = arg.splitn(2, '/');
+        let num = match iter.next().map(|x| x.trim().parse::<u128>()) {
+            Option::Some(Ok(num)) => num,
+            _ => return Err(format!("could not parse '{}' as a rational", arg)),
+        };
+        let den = match iter.next().map(|x| x.trim().parse::<u128>()) {
+            Option::None => 1,
+            Option::Some(Ok(0)) => {
+                return Err(format!("rational '{}' has a zero denominator", arg));
+            }
+            Option::Some(Ok(den)) => den,
+            Option::Some(Err(_)) => {
+                return Err(format!("could not parse '{}' as a rational", arg));
+            }
+        };
+        Ok(Rational::new(num, den))
+    }
+
+    fn parse_decimal(arg: &str) -> Result<Rational, String> {
+        let err = || format!("could not parse '{}' as a rational", arg);
+        let mut iter = arg.trim().splitn(2, '.');
+        let whole = iter.next().unwrap_or("");
+        let frac = iter.next().unwrap_or("");
+        if (whole.is_empty() && frac.is_empty())
+            || !whole.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+        {
+            return Err(err());
+        }
+        let den = 10u128.checked_pow(frac.len() as u32).ok_or_else(err)?;
+        let whole = if whole.is_empty() {
+            0
+        } else {
+            whole.parse::<u128>().map_err(|_| err())?
+        };
+        let frac = if frac.is_empty() {
+            0
+        } else {
+            frac.parse::<u128>().map_err(|_| err())?
+        };
+        let num = whole
+            .checked_mul(den)
+            .and_then(|x| x.checked_add(frac))
+            .ok_or_else(err)?;
+        Ok(Rational::new(num, den))
+    }
+
+    pub fn numerator(&self) -> u128 {
+        self.num
+    }
+
+    pub fn denominator(&self) -> u128 {
+        self.den
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.num == self.den
+    }
+
+    /// returns `1 - self`, or `None` if `self > 1`
+    pub fn complement(&self) -> Option<Rational> {
+        if self.num > self.den {
+            None
+        } else {
+            Some(Rational::new(self.den - self.num, self.den))
+        }
+    }
+
+    /// adds, or `None` upon overflow
+    pub fn checked_add(&self, other: &Rational) -> Option<Rational> {
+        let div = gcd(self.den, other.den);
+        let lcm = (self.den / div).checked_mul(other.den)?;
+        let left = self.num.checked_mul(lcm / self.den)?;
+        let right = other.num.checked_mul(lcm / other.den)?;
+        Some(Rational::new(left.checked_add(right)?, lcm))
+    }
+
+    /// subtracts, saturating at zero as probabilities can't be
+    /// negative. `None` upon overflow
+    pub fn checked_sub(&self, other: &Rational) -> Option<Rational> {
+        if *other >= *self {
+            return Some(Rational::zero());
+        }
+        let div = gcd(self.den, other.den);
+        let lcm = (self.den / div).checked_mul(other.den)?;
+        let left = self.num.checked_mul(lcm / self.den)?;
+        let right = other.num.checked_mul(lcm / other.den)?;
+        Some(Rational::new(left - right, lcm))
+    }
+
+    /// multiplies, or `None` upon overflow
+    pub fn checked_mul(&self, other: &Rational) -> Option<Rational> {
+        // cross reduce first to keep the intermediate values small
+        let a = gcd(self.num, other.den);
+        let b = gcd(other.num, self.den);
+        let num = (self.num / a).checked_mul(other.num / b)?;
+        let den = (self.den / b).checked_mul(other.den / a)?;
+        Some(Rational::new(num, den))
+    }
+
+    /// divides by a non-zero rational, or `None` upon overflow
+    pub fn checked_div(&self, other: &Rational) -> Option<Rational> {
+        if other.is_zero() {
+            None
+        } else {
+            self.checked_mul(&Rational::new(other.den, other.num))
+        }
+    }
+
+    /// lossy conversion for display & reporting
+    pub fn to_f64(&self) -> f64 {
+        (self.num as f64) / (self.den as f64)
+    }
+}
+impl Default for Rational {
+    fn default() -> Self {
+        Rational::zero()
+    }
+}
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Rational {
+    /// compares the continued fractions of both, so no product
+    /// is formed which could overflow
+    fn cmp(&self, other: &Rational) -> Ordering {
+        let (mut a, mut b) = (*self, *other);
+        let mut flip = false;
+        loop {
+            let order = (a.num / a.den).cmp(&(b.num / b.den));
+            if order != Ordering::Equal {
+                return if flip { order.reverse() } else { order };
+            }
+            let (a_rem, b_rem) = (a.num % a.den, b.num % b.den);
+            // a smaller remainder is a larger reciprocal
+            match (a_rem, b_rem) {
+                (0, 0) => return Ordering::Equal,
+                (0, _) => return if flip { Ordering::Greater } else { Ordering::Less },
+                (_, 0) => return if flip { Ordering::Less } else { Ordering::Greater },
+                _ => {}
+            };
+            a = Rational { num: a.den, den: a_rem };
+            b = Rational { num: b.den, den: b_rem };
+            flip = !flip;
+        }
+    }
+}
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    if a == 0 {
+        1
+    } else {
+        a
+    }
+}
+
+#[test]
+fn test_rational_arithmetic() {
+    let third = Rational::new(1, 3);
+    let sixth = Rational::new(2, 12);
+    assert_eq!(sixth, Rational::new(1, 6));
+    assert_eq!(third.checked_add(&sixth), Some(Rational::new(1, 2)));
+    assert_eq!(third.checked_mul(&sixth), Some(Rational::new(1, 18)));
+    assert_eq!(third.checked_sub(&sixth), Some(sixth));
+    assert_eq!(sixth.checked_sub(&third), Some(Rational::zero()));
+    assert_eq!(third.complement(), Some(Rational::new(2, 3)));
+    assert_eq!(Rational::new(4, 3).complement(), None);
+    assert!(sixth < third);
+    assert!(Rational::new(7, 3) > Rational::new(9, 4));
+    assert_eq!(Rational::new(4, 2).cmp(&Rational::new(2, 1)), Ordering::Equal);
+
+    // overflow is reported, rather than a panic
+    let tiny = Rational::new(1, u128::MAX - 1);
+    assert!(tiny.checked_mul(&tiny).is_none());
+    assert!(tiny.checked_add(&Rational::new(1, u128::MAX)).is_none());
+    assert!(tiny < Rational::new(1, u128::MAX - 2));
+    assert_eq!(Rational::parse("2/4"), Ok(Rational::new(1, 2)));
+    assert_eq!(Rational::parse("3"), Ok(Rational::new(3, 1)));
+    assert!(Rational::parse("1/0").is_err());
+    assert_eq!(Rational::parse("0.25"), Ok(Rational::new(1, 4)));
+    assert_eq!(Rational::parse(".5"), Ok(Rational::new(1, 2)));
+    assert!(Rational::parse("0.-5").is_err());
+}
@@ -0,0 +1,45 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+/// Span is the byte range within the source a node was
+/// parsed from.
+///
+/// Spans are only metadata for diagnostics. Two expressions
+/// that are written identically in different places are still
+/// the same expression, so spans are always equal to one
+/// another and contribute nothing to a hash.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// true for spans of nodes which were not parsed from source,
+    /// such as the standard library.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+impl PartialEq for Span {
+    fn eq(&self, _: &Span) -> bool {
+        true
+    }
+}
+impl Eq for Span {}
+impl PartialOrd for Span {
+    fn partial_cmp(&self, other: &Span) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Span {
+    fn cmp(&self, _: &Span) -> Ordering {
+        Ordering::Equal
+    }
+}
+impl Hash for Span {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
@@ -3,9 +3,11 @@ use std::fmt;
 use super::expression::Expression;
 use super::literal::Literal;
 use super::literalvalue::LiteralValue;
+use super::span::Span;
 use super::terminalexpression::TerminalExpression;
 use super::typedata::TypeData;
 use super::variabledeclaration::VariableDeclaration;
+use super::annotation;
 
 /// Statements are expressions within a function
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -17,14 +19,14 @@ impl<'a> fmt::Display for Statement<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Statement::Variable(ref var) => {
-                write!(f, "    let {}: {} = {};\n", var.name, var.kind, var.expr)
+                writeln!(f, "    let {}{} = {};", var.name, annotation(var.kind), var.expr)
             }
-            Statement::Return(ref ret) => write!(f, "    return {};\n", ret.expr),
+            Statement::Return(ref ret) => writeln!(f, "    return {};", ret.expr),
         }
     }
 }
 impl<'a> Statement<'a> {
-    pub fn get_variable_declaration<'b>(s: &'b Self) -> Option<&'b VariableDeclaration> {
+    pub fn get_variable_declaration<'b>(s: &'b Self) -> Option<&'b VariableDeclaration<'b>> {
         match s {
             Statement::Variable(ref var) => Some(var),
             _ => None,
@@ -32,13 +34,25 @@ impl<'a> Statement<'a> {
     }
 
     #[inline(always)]
-    pub fn new_var(name: &'a str, kind: TypeData, expr: Expression<'a>) -> Statement<'a> {
-        Statement::Variable(VariableDeclaration { name, kind, expr })
+    pub fn new_var(
+        allow: Vec<&'a str>,
+        name: &'a str,
+        kind: Option<TypeData>,
+        expr: Expression<'a>,
+        span: Span,
+    ) -> Statement<'a> {
+        Statement::Variable(VariableDeclaration {
+            name,
+            kind,
+            expr,
+            span,
+            allow: allow.into_boxed_slice(),
+        })
     }
 
     #[inline(always)]
-    pub fn new_ret(expr: Expression<'a>) -> Statement<'a> {
-        Statement::Return(TerminalExpression { expr })
+    pub fn new_ret(expr: Expression<'a>, span: Span) -> Statement<'a> {
+        Statement::Return(TerminalExpression { expr, span })
     }
 }
 
@@ -50,18 +64,29 @@ fn test_statement_parse() {
     // let arg: int = 15
     let stmt = Statement::Variable(VariableDeclaration {
         name: "arg",
-        kind: TypeData::Int,
+        kind: Some(TypeData::Int),
         expr: Expression::Literal(LiteralValue {
             lit: Literal::Number(15i8),
+            span: Span::default(),
         }),
+        span: Span::default(),
+        allow: vec![].into_boxed_slice(),
     });
-    assert!(parser.parse("let arg: int = 15;").unwrap() == stmt);
+    assert!(parser.parse(&mut Vec::new(), "let arg: int = 15;").unwrap() == stmt);
+
+    // let arg = 15, the type is inferred
+    match parser.parse(&mut Vec::new(), "let arg = 15;").unwrap() {
+        Statement::Variable(ref var) => assert_eq!(var.kind, None),
+        other => panic!("{:?}", other),
+    };
 
     // return false
     let stmt = Statement::Return(TerminalExpression {
         expr: Expression::Literal(LiteralValue {
             lit: Literal::Boolean(false),
+            span: Span::default(),
         }),
+        span: Span::default(),
     });
-    assert!(parser.parse("return false;").unwrap() == stmt);
+    assert!(parser.parse(&mut Vec::new(), "return false;").unwrap() == stmt);
 }
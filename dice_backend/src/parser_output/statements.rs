@@ -1,11 +1,14 @@
 use std::fmt;
 
+use super::comment::Comment;
 use super::statement::Statement;
 
 /// Statements are a collection of operations
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Statements<'a> {
     pub data: Box<[Statement<'a>]>,
+    /// comments between the statements, in source order
+    pub comments: Box<[Comment<'a>]>,
 }
 impl<'a> fmt::Display for Statements<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -27,6 +30,22 @@ impl<'a> Statements<'a> {
         //let collect: Vec<Statement<'a>> = arg.into_iter().map(mapper).collect();
         Statements {
             data: collect.into_boxed_slice(),
+            comments: vec![].into_boxed_slice(),
+        }
+    }
+
+    /// builds statements from the parser's mix of comments &
+    /// statements. Statements which failed to parse are `None`.
+    pub fn from_items(items: Vec<(Option<Comment<'a>>, Option<Statement<'a>>)>) -> Statements<'a> {
+        let mut data = Vec::with_capacity(items.len());
+        let mut comments = Vec::new();
+        for (comment, stmt) in items {
+            comments.extend(comment);
+            data.extend(stmt);
+        }
+        Statements {
+            data: data.into_boxed_slice(),
+            comments: comments.into_boxed_slice(),
         }
     }
 }
@@ -4,9 +4,10 @@ use super::analysisdeclaration::AnalysisDeclaration;
 use super::constantdeclaration::ConstantDeclaration;
 use super::expression::Expression;
 use super::functiondeclaration::FunctionDeclaration;
+use super::span::Span;
 use super::typedata::TypeData;
 
-use super::Statements;
+use super::{annotation, Statements};
 
 /// Structures are top level arguments they exist
 /// outside of functions.
@@ -19,9 +20,9 @@ pub enum Structures<'a> {
 impl<'a> fmt::Display for Structures<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Structures::Analyze(ref ana) => write!(f, "analyze {};\n", ana.expr),
+            Structures::Analyze(ref ana) => writeln!(f, "analyze {};", ana.expr),
             Structures::Constant(ref con) => {
-                write!(f, "const {}: {} = {};\n", con.name, con.kind, con.expr)
+                writeln!(f, "const {}{} = {};", con.name, annotation(con.kind), con.expr)
             }
             Structures::Func(ref func) => {
                 write!(f, "fn {}( ", func.name)?;
@@ -61,22 +62,41 @@ impl<'a> Structures<'a> {
     }
 
     #[inline(always)]
-    pub fn new_const(name: &'a str, kind: TypeData, expr: Expression<'a>) -> Structures<'a> {
-        Structures::Constant(ConstantDeclaration { name, kind, expr })
+    pub fn new_const(
+        allow: Vec<&'a str>,
+        name: &'a str,
+        kind: Option<TypeData>,
+        expr: Expression<'a>,
+        span: Span,
+    ) -> Structures<'a> {
+        Structures::Constant(ConstantDeclaration {
+            name,
+            kind,
+            expr,
+            span,
+            allow: allow.into_boxed_slice(),
+            doc: vec![].into_boxed_slice(),
+        })
     }
 
     #[inline(always)]
-    pub fn new_analysis(expr: Expression<'a>) -> Structures<'a> {
-        Structures::Analyze(AnalysisDeclaration { expr })
+    pub fn new_analysis(allow: Vec<&'a str>, expr: Expression<'a>, span: Span) -> Structures<'a> {
+        Structures::Analyze(AnalysisDeclaration {
+            expr,
+            span,
+            allow: allow.into_boxed_slice(),
+        })
     }
 
     #[inline(always)]
     pub fn new_func(
+        allow: Vec<&'a str>,
         name: &'a str,
         args: Vec<(&'a str, &'a str, TypeData, &'a str)>,
         last_arg: Option<(&'a str, &'a str, TypeData)>,
         ret: TypeData,
         body: Statements<'a>,
+        span: Span,
     ) -> Structures<'a> {
         #[inline(always)]
         fn args_mapper<'a>(tup: (&'a str, &'a str, TypeData, &'a str)) -> (&'a str, TypeData) {
@@ -100,6 +120,19 @@ impl<'a> Structures<'a> {
             args,
             ret,
             body,
+            span,
+            allow: allow.into_boxed_slice(),
+            doc: vec![].into_boxed_slice(),
         })
     }
+
+    /// attaches `///` comments to a declaration, analyses have
+    /// no documentation.
+    pub fn set_doc(&mut self, doc: Vec<&'a str>) {
+        match self {
+            &mut Structures::Constant(ref mut constant) => constant.doc = doc.into_boxed_slice(),
+            &mut Structures::Func(ref mut func) => func.doc = doc.into_boxed_slice(),
+            &mut Structures::Analyze(_) => {}
+        };
+    }
 }
@@ -1,10 +1,12 @@
 use std::fmt;
 
 use super::expression::Expression;
+use super::span::Span;
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TerminalExpression<'a> {
     pub expr: Expression<'a>,
+    pub span: Span,
 }
 impl<'a> fmt::Display for TerminalExpression<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -1,16 +1,14 @@
 use std::fmt;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Default)]
 pub enum TypeData {
+    #[default]
     Bool,
     Int,
     CollectionOfBool,
     CollectionOfInt,
-}
-impl Default for TypeData {
-    fn default() -> Self {
-        Self::Bool
-    }
+    Deck,
 }
 impl fmt::Display for TypeData {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -19,6 +17,7 @@ impl fmt::Display for TypeData {
             TypeData::Int => write!(f, "int"),
             TypeData::CollectionOfBool => write!(f, "vec<bool>"),
             TypeData::CollectionOfInt => write!(f, "vec<int>"),
+            TypeData::Deck => write!(f, "deck"),
         }
     }
 }
@@ -28,8 +27,9 @@ fn test_type_data_parsing() {
     use super::super::value::KindParser;
 
     let parser = KindParser::new();
-    assert!(parser.parse("bool").unwrap() == TypeData::Bool);
-    assert!(parser.parse("int").unwrap() == TypeData::Int);
-    assert!(parser.parse("vec<bool>").unwrap() == TypeData::CollectionOfBool);
-    assert!(parser.parse("vec<int>").unwrap() == TypeData::CollectionOfInt);
+    assert!(parser.parse(&mut Vec::new(), "bool").unwrap() == TypeData::Bool);
+    assert!(parser.parse(&mut Vec::new(), "int").unwrap() == TypeData::Int);
+    assert!(parser.parse(&mut Vec::new(), "vec<bool>").unwrap() == TypeData::CollectionOfBool);
+    assert!(parser.parse(&mut Vec::new(), "vec<int>").unwrap() == TypeData::CollectionOfInt);
+    assert!(parser.parse(&mut Vec::new(), "deck").unwrap() == TypeData::Deck);
 }
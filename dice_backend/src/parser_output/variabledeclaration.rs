@@ -1,16 +1,28 @@
 use std::fmt;
 
 use super::expression::Expression;
+use super::span::Span;
 use super::typedata::TypeData;
+use super::{allows, annotation};
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct VariableDeclaration<'a> {
     pub name: &'a str,
-    pub kind: TypeData,
+    /// the annotated type, when absent it is inferred
+    pub kind: Option<TypeData>,
     pub expr: Expression<'a>,
+    pub span: Span,
+    /// lints which are allowed by `#[allow(..)]`
+    pub allow: Box<[&'a str]>,
+}
+impl<'a> VariableDeclaration<'a> {
+    /// checks if a lint is allowed by an attribute
+    pub fn allows(&self, lint: &str) -> bool {
+        allows(&self.allow, lint)
+    }
 }
 impl<'a> fmt::Display for VariableDeclaration<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "let {}: {} = {}", self.name, self.kind, self.expr)
+        write!(f, "let {}{} = {}", self.name, annotation(self.kind), self.expr)
     }
 }
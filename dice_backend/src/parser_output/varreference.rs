@@ -1,12 +1,14 @@
 use std::fmt;
 
 use super::typedata::TypeData;
+use super::span::Span;
 
 use super::GetType;
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct VariableReference<'a> {
     pub name: &'a str,
+    pub span: Span,
 }
 impl<'a> fmt::Display for VariableReference<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -18,8 +20,6 @@ impl<'a> GetType for VariableReference<'a> {
         true
     }
     fn get_type(&self) -> Result<TypeData, String> {
-        Err(format!(
-            "variable reference requires a variable lookup to find type data"
-        ))
+        Err("variable reference requires a variable lookup to find type data".to_string())
     }
 }
@@ -17,6 +17,11 @@ impl AddSink {
         let sink = Match::from(sink);
         Self { to, sink }
     }
+
+    /// the expression whose sinks change
+    pub fn get_expr(&self) -> Match {
+        self.to
+    }
 }
 impl<G: Graph> ModifyGraph<G> for AddSink {
     /// apply this transform to a graph
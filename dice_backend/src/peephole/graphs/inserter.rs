@@ -1,5 +1,4 @@
 use super::traits::{Graph, ModifyGraph};
-use super::Match;
 
 /// Insert a node into the graph
 #[derive(Clone)]
@@ -11,6 +10,11 @@ impl<E: Clone> Inserter<E> {
     pub fn new(expr: E) -> Self {
         Self { expr }
     }
+
+    /// the expression which is inserted
+    pub fn get_expr(&self) -> &E {
+        &self.expr
+    }
 }
 impl<E, G> ModifyGraph<G> for Inserter<E>
 where
@@ -12,10 +12,10 @@ pub struct Match {
 }
 impl Match {
     pub fn get_kind(&self) -> Option<TypeData> {
-        self.kind.clone()
+        self.kind
     }
     pub fn get_id(&self) -> Option<u64> {
-        self.id.clone()
+        self.id
     }
 
     pub fn none() -> Match {
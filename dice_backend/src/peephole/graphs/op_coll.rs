@@ -1,3 +1,6 @@
+use std::collections::BTreeSet;
+
+use super::super::super::ordering::OrdTrait;
 use super::{Graph, ModifyGraph, Operation};
 
 /// Modifications allows for the easy collection of modifications
@@ -32,6 +35,13 @@ impl<E: Clone> Modifications<E> {
         self.data.push(Operation::from(arg));
     }
 }
+impl<E: Clone + OrdTrait> Modifications<E> {
+    /// the ids of every expression which is inserted, removed,
+    /// or has its edges changed.
+    pub fn get_changed(&self) -> BTreeSet<u64> {
+        self.data.iter().flat_map(Operation::get_changed).collect()
+    }
+}
 impl<E: Clone, G: Graph<Expr = E>> ModifyGraph<G> for Modifications<E> {
     fn apply(&self, graph: &mut G) {
         // iterate over items, and apply them all
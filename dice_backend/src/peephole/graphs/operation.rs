@@ -1,3 +1,4 @@
+use super::super::super::ordering::OrdTrait;
 use super::{AddSink, Graph, Inserter, ModifyGraph, RemoveSink, Remover, SwapSource};
 
 /// Operation is an enum over the permitted graph transformations.
@@ -15,6 +16,18 @@ pub enum Operation<E: Clone> {
     RemoveExpr(Remover),
     Inserter(Inserter<E>),
 }
+impl<E: Clone + OrdTrait> Operation<E> {
+    /// the id of the expression this operation changes
+    pub fn get_changed(&self) -> Option<u64> {
+        match self {
+            &Operation::Inserter(ref insert) => Some(insert.get_expr().get_own_id()),
+            &Operation::AddSink(ref add_sink) => add_sink.get_expr().get_id(),
+            &Operation::RemoveSink(ref remove_sink) => remove_sink.get_expr().get_id(),
+            &Operation::SwapSource(ref swap_source) => swap_source.get_expr().get_id(),
+            &Operation::RemoveExpr(ref remove) => remove.get_expr().get_id(),
+        }
+    }
+}
 impl<E: Clone> From<Inserter<E>> for Operation<E> {
     #[inline(always)]
     fn from(insert: Inserter<E>) -> Self {
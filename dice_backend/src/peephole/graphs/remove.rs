@@ -19,6 +19,11 @@ impl Remover {
         let data = Match::from(data);
         Self { data }
     }
+
+    /// the expression which is removed
+    pub fn get_expr(&self) -> Match {
+        self.data
+    }
 }
 impl<G: Graph> ModifyGraph<G> for Remover {
     /// apply this transform to a graph
@@ -17,6 +17,11 @@ impl RemoveSink {
         let sink = Match::from(sink);
         Self { from, sink }
     }
+
+    /// the expression whose sinks change
+    pub fn get_expr(&self) -> Match {
+        self.from
+    }
 }
 impl<G: Graph> ModifyGraph<G> for RemoveSink {
     /// apply this transform to a graph
@@ -20,6 +20,12 @@ impl SwapSource {
         let new = Match::from(new);
         Self { expr, old, new }
     }
+
+    /// the expression whose sources change, when it matches
+    /// nothing the return is swapped.
+    pub fn get_expr(&self) -> Match {
+        self.expr
+    }
 }
 impl<G: Graph> ModifyGraph<G> for SwapSource {
     /// apply this transform to a graph
@@ -1,4 +1,3 @@
-use super::super::super::ordering::OrdTrait;
 use super::Match;
 
 /// ModifyGraph handles the application of a change to a graph.
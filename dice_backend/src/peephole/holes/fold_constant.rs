@@ -0,0 +1,80 @@
+use super::super::super::namespace::Determinism;
+use super::super::super::ordering::*;
+use super::super::super::runtime::{evaluate, Datum};
+
+use super::super::graphs::*;
+
+/// FoldConstant replaces an expression which is constant, & whose
+/// sources are each a value, with the value it evaluates to.
+///
+/// expressions which fail to evaluate, such as division by zero,
+/// are left for the runtime to report.
+pub fn fold_constant(
+    expr: u64,
+    coll: &OrderedCollection,
+) -> Option<Modifications<OrderedExpression>> {
+    let root = match coll.get_expr(expr)? {
+        &OrderedExpression::Constant(_) => return None,
+        root => root,
+    };
+
+    // every source must already be folded
+    let mut args = Vec::with_capacity(root.get_sources().len());
+    for source in root.get_sources() {
+        match coll.get_expr(source.0)? {
+            &OrderedExpression::Constant(ConstantValue::Int(x, _)) => args.push(Datum::from(x)),
+            &OrderedExpression::Constant(ConstantValue::Bool(x, _)) => args.push(Datum::from(x)),
+            _ => return None,
+        };
+    }
+    if coll.get_determinism(expr) != Determinism::Constant {
+        return None;
+    }
+    let args: Vec<&Datum> = args.iter().collect();
+    let mut elements = evaluate(root, &args).ok()?;
+    if elements.len() != 1 {
+        return None;
+    }
+
+    let mut new_constant = OrdType::new(coll.next_free_id(None), root.get_own_type(), s_v![]);
+    let mut mods = Modifications::default();
+
+    // where ever the root flowed, the new constant now flows
+    for sink in root.get_sinks() {
+        mods.push(SwapSource::new(
+            sink,
+            root.get_matcher_tuple(),
+            new_constant.get_matcher_tuple(),
+        ));
+        new_constant.add_sink(sink.0, sink.1);
+    }
+    mods.push(SwapSource::new(
+        Match::default(),
+        root.get_matcher_tuple(),
+        new_constant.get_matcher_tuple(),
+    ));
+    mods.push(Remover::new(root.get_matcher_tuple()));
+
+    // sources which only the root read are no longer required
+    let mut sources = root.get_sources().to_vec();
+    sources.sort();
+    sources.dedup();
+    for source in sources.iter() {
+        let source = coll.get_expr(source.0)?;
+        mods.push(RemoveSink::new(
+            source.get_matcher_tuple(),
+            root.get_matcher_tuple(),
+        ));
+        if source.get_sinks().len() == 1 {
+            mods.push(Remover::new(source.get_matcher_tuple()));
+        }
+    }
+
+    let value = match elements.pop()?.split().0 {
+        Datum::Int(x) => ConstantValue::Int(x, new_constant),
+        Datum::Bool(x) => ConstantValue::Bool(x, new_constant),
+        _ => return None,
+    };
+    mods.push(Inserter::new(OrderedExpression::Constant(value)));
+    Some(mods)
+}
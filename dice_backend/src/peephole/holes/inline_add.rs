@@ -26,9 +26,11 @@ pub fn add_inline(expr: u64, coll: &OrderedCollection) -> Option<Modifications<O
             Option::Some(OrderedExpression::Constant(ConstantValue::Int(ref x, ref x_args))),
             Option::Some(OrderedExpression::Constant(ConstantValue::Int(ref y, ref y_args))),
         ) => {
+            // an overflow is left to be reported when evaluated
+            let value = x.checked_add(*y)?;
             let (new_constant, mut mods) = interior(add_op, new_id, TypeData::Int, x_args, y_args);
             mods.push(Inserter::new(OrderedExpression::Constant(
-                ConstantValue::Int(x + y, new_constant),
+                ConstantValue::Int(value, new_constant),
             )));
             Some(mods)
         }
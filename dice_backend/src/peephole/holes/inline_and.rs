@@ -6,10 +6,10 @@ use super::super::graphs::*;
 use super::interior;
 
 pub fn and_inline(expr: u64, coll: &OrderedCollection) -> Option<Modifications<OrderedExpression>> {
-    // assert we're dealing with addition
+    // assert we're dealing with conjunction
     let and_op = match coll.get_expr(expr) {
         Option::Some(OrderedExpression::Op(Op::And(ref and_op))) => {
-            if and_op != TypeData::Int {
+            if and_op != TypeData::Bool {
                 return None;
             }
             and_op
@@ -24,16 +24,6 @@ pub fn and_inline(expr: u64, coll: &OrderedCollection) -> Option<Modifications<O
         coll.get_expr(and_op.get_sources()[0].0),
         coll.get_expr(and_op.get_sources()[1].0),
     ) {
-        (
-            Option::Some(OrderedExpression::Constant(ConstantValue::Int(ref x, ref x_args))),
-            Option::Some(OrderedExpression::Constant(ConstantValue::Int(ref y, ref y_args))),
-        ) => {
-            let (new_constant, mut mods) = interior(and_op, new_id, TypeData::Int, x_args, y_args);
-            mods.push(Inserter::new(OrderedExpression::Constant(
-                ConstantValue::Int(*x & *y, new_constant),
-            )));
-            Some(mods)
-        }
         (
             Option::Some(OrderedExpression::Constant(ConstantValue::Bool(ref x, ref x_args))),
             Option::Some(OrderedExpression::Constant(ConstantValue::Bool(ref y, ref y_args))),
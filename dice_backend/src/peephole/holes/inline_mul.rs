@@ -5,9 +5,9 @@ use super::super::graphs::*;
 use super::interior;
 
 pub fn mul_inline(expr: u64, coll: &OrderedCollection) -> Option<Modifications<OrderedExpression>> {
-    // assert we're dealing with addition
+    // assert we're dealing with multiplication
     let mul_op = match coll.get_expr(expr) {
-        Option::Some(OrderedExpression::Op(Op::Add(ref mul_op))) => {
+        Option::Some(OrderedExpression::Op(Op::Mul(ref mul_op))) => {
             if mul_op != TypeData::Int {
                 return None;
             }
@@ -26,9 +26,11 @@ pub fn mul_inline(expr: u64, coll: &OrderedCollection) -> Option<Modifications<O
             Option::Some(OrderedExpression::Constant(ConstantValue::Int(ref x, ref x_args))),
             Option::Some(OrderedExpression::Constant(ConstantValue::Int(ref y, ref y_args))),
         ) => {
+            // an overflow is left to be reported when evaluated
+            let value = x.checked_mul(*y)?;
             let (new_constant, mut mods) = interior(mul_op, new_id, TypeData::Int, x_args, y_args);
             mods.push(Inserter::new(OrderedExpression::Constant(
-                ConstantValue::Int(x * y, new_constant),
+                ConstantValue::Int(value, new_constant),
             )));
             Some(mods)
         }
@@ -6,13 +6,13 @@ use super::super::graphs::*;
 use super::interior;
 
 pub fn or_inline(expr: u64, coll: &OrderedCollection) -> Option<Modifications<OrderedExpression>> {
-    // assert we're dealing with addition
-    let and_or = match coll.get_expr(expr) {
-        Option::Some(OrderedExpression::Op(Op::And(ref and_or))) => {
-            if and_or != TypeData::Int {
+    // assert we're dealing with disjunction
+    let or_op = match coll.get_expr(expr) {
+        Option::Some(OrderedExpression::Op(Op::Or(ref or_op))) => {
+            if or_op != TypeData::Bool {
                 return None;
             }
-            and_or
+            or_op
         }
         _ => return None,
     };
@@ -21,26 +21,16 @@ pub fn or_inline(expr: u64, coll: &OrderedCollection) -> Option<Modifications<Or
 
     // both arguments must be a constant
     match (
-        coll.get_expr(and_or.get_sources()[0].0),
-        coll.get_expr(and_or.get_sources()[1].0),
+        coll.get_expr(or_op.get_sources()[0].0),
+        coll.get_expr(or_op.get_sources()[1].0),
     ) {
-        (
-            Option::Some(OrderedExpression::Constant(ConstantValue::Int(ref x, ref x_args))),
-            Option::Some(OrderedExpression::Constant(ConstantValue::Int(ref y, ref y_args))),
-        ) => {
-            let (new_constant, mut mods) = interior(and_or, new_id, TypeData::Int, x_args, y_args);
-            mods.push(Inserter::new(OrderedExpression::Constant(
-                ConstantValue::Int(*x & *y, new_constant),
-            )));
-            Some(mods)
-        }
         (
             Option::Some(OrderedExpression::Constant(ConstantValue::Bool(ref x, ref x_args))),
             Option::Some(OrderedExpression::Constant(ConstantValue::Bool(ref y, ref y_args))),
         ) => {
-            let (new_constant, mut mods) = interior(and_or, new_id, TypeData::Bool, x_args, y_args);
+            let (new_constant, mut mods) = interior(or_op, new_id, TypeData::Bool, x_args, y_args);
             mods.push(Inserter::new(OrderedExpression::Constant(
-                ConstantValue::Bool(*x & *y, new_constant),
+                ConstantValue::Bool(*x | *y, new_constant),
             )));
             Some(mods)
         }
@@ -1,34 +1,36 @@
 use super::super::super::ordering::*;
 use super::super::super::parser_output::TypeData;
+use super::super::super::stdlib::Lowering;
 
 use super::super::graphs::*;
 
 pub fn join_roll(expr: u64, coll: &OrderedCollection) -> Option<Modifications<OrderedExpression>> {
     // check that this is in fact a join expression
     let join_op = match coll.get_expr(expr) {
-        Option::Some(OrderedExpression::StdLib(StdLibraryFunc::Join(ref args)))
-            if args == TypeData::CollectionOfInt =>
+        Option::Some(OrderedExpression::StdLib(ref args))
+            if args.get_lowering() == Some(Lowering::Join) && *args == TypeData::CollectionOfInt =>
         {
             args
         }
         _ => return None,
     };
 
-    let (left, right, expect) = match (
+    let (left, right) = match (
         coll.get_expr(join_op.get_sources()[0].0).unwrap(),
         coll.get_expr(join_op.get_sources()[1].0).unwrap(),
     ) {
         // source code is roughly `join(roll_d6(_),roll_d6(_))`
-        (
-            OrderedExpression::StdLib(StdLibraryFunc::D6(ref left_d6)),
-            OrderedExpression::StdLib(StdLibraryFunc::D6(ref right_d6)),
-        ) => (left_d6, right_d6, Expect::D6),
-
-        // source code is roughy `join(roll_d3(_),roll_d3(_))`
-        (
-            OrderedExpression::StdLib(StdLibraryFunc::D3(ref left_d3)),
-            OrderedExpression::StdLib(StdLibraryFunc::D3(ref right_d3)),
-        ) => (left_d3, right_d3, Expect::D3),
+        // or `join(roll_d3(_),roll_d3(_))`, where the join is the
+        // only reader of two distinct rolls
+        (OrderedExpression::StdLib(ref left_roll), OrderedExpression::StdLib(ref right_roll))
+            if left_roll.get_lowering() == right_roll.get_lowering()
+                && matches!(left_roll.get_lowering(), Option::Some(Lowering::RollDice(_)))
+                && left_roll.get_own_id() != right_roll.get_own_id()
+                && left_roll.get_sinks().len() == 1
+                && right_roll.get_sinks().len() == 1 =>
+        {
+            (left_roll, right_roll)
+        }
         _ => return None,
     };
 
@@ -46,7 +48,6 @@ pub fn join_roll(expr: u64, coll: &OrderedCollection) -> Option<Modifications<Or
             // fetch a new identifier for the roll.
             let new_id_roll = coll.next_free_id(Some(new_id_const));
             Some(internal_boilerplate(
-                expect,
                 join_op,
                 new_id_const,
                 new_id_roll,
@@ -62,24 +63,16 @@ pub fn join_roll(expr: u64, coll: &OrderedCollection) -> Option<Modifications<Or
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
-enum Expect {
-    D6,
-    D3,
-}
-
 /// all of this is identical no matter what type of dice
 /// we're dealing with.
 ///
-/// we just need to tell this function what kind of dice
-/// it needs to construct
+/// the new roll invokes the same function as `l_roll`
 #[inline(always)]
-fn internal_boilerplate<A, B, C, D, E>(
-    expect: Expect,
+fn internal_boilerplate<A, C, D, E>(
     join_op: &A,
     new_const_id: u64,
     new_roll_id: u64,
-    l_roll: &B,
+    l_roll: &StdLibraryFunc,
     l_const: &C,
     l_val: i8,
     r_roll: &D,
@@ -88,7 +81,6 @@ fn internal_boilerplate<A, B, C, D, E>(
 ) -> Modifications<OrderedExpression>
 where
     A: OrdTrait,
-    B: OrdTrait,
     C: OrdTrait,
     D: OrdTrait,
     E: OrdTrait,
@@ -104,7 +96,7 @@ where
         TypeData::CollectionOfInt,
         s_v![(new_const_id, TypeData::Int)],
     );
-    new_const.add_sink(new_roll.get_own_id(), new_roll.get_own_type());
+    new_const.add_sink(new_roll.get_own_id(), new_const.get_own_type());
 
     // for every `join(_,_)`'s result flows, we need to update that.
     for sink in join_op.get_sinks() {
@@ -125,18 +117,9 @@ where
     // remove the join operation itself
     mods.push(Remover::new(join_op));
     // insert the new arguments
-    match expect {
-        Expect::D3 => {
-            mods.push(Inserter::new(OrderedExpression::StdLib(
-                StdLibraryFunc::D3(new_roll),
-            )));
-        }
-        Expect::D6 => {
-            mods.push(Inserter::new(OrderedExpression::StdLib(
-                StdLibraryFunc::D6(new_roll),
-            )));
-        }
-    };
+    mods.push(Inserter::new(OrderedExpression::StdLib(
+        l_roll.with(new_roll),
+    )));
     mods.push(Inserter::new(OrderedExpression::Constant(
         ConstantValue::Int(l_val + r_val, new_const),
     )));
@@ -144,24 +127,26 @@ where
     // tell our constants they aren't being consumed
     // if possible, remove them.
     mods.push(RemoveSink::new(l_const, l_roll));
-    if l_const.get_sinks().len() == 1 {
-        mods.push(Remover::new(l_const));
-    }
     mods.push(RemoveSink::new(r_const, r_roll));
-    if r_const.get_sinks().len() == 1 {
-        mods.push(Remover::new(r_const));
+    if l_const.get_own_id() == r_const.get_own_id() {
+        // both rolls read one constant, `join(roll_d6(1),roll_d6(1))`
+        if l_const.get_sinks().len() == 2 {
+            mods.push(Remover::new(l_const));
+        }
+    } else {
+        if l_const.get_sinks().len() == 1 {
+            mods.push(Remover::new(l_const));
+        }
+        if r_const.get_sinks().len() == 1 {
+            mods.push(Remover::new(r_const));
+        }
     }
 
-    // tell our `roll_d3|d6(_)` they aren't being consumed
-    // if possible, remove them.
+    // the join was the only reader of our `roll_d3|d6(_)`
     mods.push(RemoveSink::new(l_roll, join_op));
-    if l_roll.get_sinks().len() == 1 {
-        mods.push(Remover::new(l_roll));
-    }
+    mods.push(Remover::new(l_roll));
     mods.push(RemoveSink::new(r_roll, join_op));
-    if r_roll.get_sinks().len() == 1 {
-        mods.push(Remover::new(r_roll));
-    }
+    mods.push(Remover::new(r_roll));
 
     // return the vector of operations needed to patch the graph
     mods
@@ -1,20 +1,24 @@
 use super::super::super::ordering::*;
+use super::super::super::stdlib::Lowering;
 use super::super::graphs::*;
 
 /// LenDice handles taking the `len(roll_dice6(VAR))` or `len(roll_dice3(VAR))`
 /// and reducing this to just `VAR`.
-
 pub fn len_dice(expr: u64, coll: &OrderedCollection) -> Option<Modifications<OrderedExpression>> {
     // are we dealing with a length?
     let len_op = match coll.get_expr(expr).unwrap() {
-        OrderedExpression::StdLib(StdLibraryFunc::Len(ref len_op)) => len_op,
+        OrderedExpression::StdLib(ref len_op) if len_op.get_lowering() == Some(Lowering::Len) => {
+            len_op
+        }
         _ => return None,
     };
 
     // ensure we have something like `len(roll_d6(_))` or `len(roll_d3(_))`
     let roll_op = match coll.get_expr(len_op.get_sources()[0].0).unwrap() {
-        OrderedExpression::StdLib(StdLibraryFunc::D6(ref roll)) => roll,
-        OrderedExpression::StdLib(StdLibraryFunc::D3(ref roll)) => roll,
+        OrderedExpression::StdLib(ref roll) => match roll.get_lowering() {
+            Option::Some(Lowering::RollDice(_)) => roll,
+            _ => return None,
+        },
         _ => return None,
     };
 
@@ -42,6 +46,7 @@ pub fn len_dice(expr: u64, coll: &OrderedCollection) -> Option<Modifications<Ord
         // if `roll_op` is only consumed once... and we just
         // removed the only consumption, we can remove it.
         mods.push(Remover::new(roll_op));
+        mods.push(RemoveSink::new(count_args, roll_op));
     }
 
     Some(mods)
@@ -1,8 +1,11 @@
+use std::collections::BTreeSet;
+
 use super::super::itertools::Itertools;
 use super::super::ordering::{OrdTrait, OrdType, OrderedCollection, OrderedExpression};
 use super::super::parser_output::TypeData;
 use super::graphs::{Modifications, ModifyGraph};
 
+pub mod fold_constant;
 pub mod inline_add;
 pub mod inline_and;
 pub mod inline_div;
@@ -25,25 +28,77 @@ const OPT: &'static [Peeping] = &[
     &inline_or::or_inline,
     &len_dice::len_dice,
     &join_rolls::join_roll,
+    &fold_constant::fold_constant,
 ];
 
-pub fn brute_force_optimize(coll: &mut OrderedCollection) {
-    loop {
-        // did we find something to change?
-        let found_opt = coll
-            .keys()
-            .cartesian_product(OPT)
-            .flat_map(|(key, lambda)| lambda(*key, coll))
-            .next();
-
-        // change it
-        match found_opt {
-            Option::None => {
-                break;
-            }
-            Option::Some(item) => {
-                item.apply(coll);
-            }
+/// applies the first rewrite which matches the graph. Returns
+/// the expressions it changed, or `None` when nothing matched.
+///
+/// debug builds verify the graph after the rewrite.
+pub fn optimize_once(coll: &mut OrderedCollection) -> Option<BTreeSet<u64>> {
+    let changed = rewrite(coll)?;
+    if cfg!(debug_assertions) {
+        expect_valid(coll, Some(&changed));
+    }
+    Some(changed)
+}
+
+fn rewrite(coll: &mut OrderedCollection) -> Option<BTreeSet<u64>> {
+    // did we find something to change?
+    let found_opt = coll
+        .keys()
+        .cartesian_product(OPT)
+        .flat_map(|(key, lambda)| lambda(*key, coll))
+        .next();
+
+    // change it
+    match found_opt {
+        Option::None => None,
+        Option::Some(item) => {
+            item.apply(coll);
+            Some(item.get_changed())
+        }
+    }
+}
+
+/// applies rewrites until none match. Returns the expressions
+/// the last rewrite changed.
+///
+/// debug builds verify the graph before & after every rewrite.
+pub fn brute_force_optimize(coll: &mut OrderedCollection) -> BTreeSet<u64> {
+    optimize(coll, cfg!(debug_assertions))
+}
+
+/// as `brute_force_optimize`, though the graph is verified
+/// before & after every rewrite within release builds as well.
+pub fn verified_optimize(coll: &mut OrderedCollection) -> BTreeSet<u64> {
+    optimize(coll, true)
+}
+
+fn optimize(coll: &mut OrderedCollection, verify: bool) -> BTreeSet<u64> {
+    if verify {
+        expect_valid(coll, None);
+    }
+    let mut changed = BTreeSet::new();
+    while let Option::Some(last) = rewrite(coll) {
+        if verify {
+            expect_valid(coll, Some(&last));
+        }
+        changed = last;
+    }
+    changed
+}
+
+/// an invalid graph is a bug within the compiler, not the program
+fn expect_valid(coll: &OrderedCollection, changed: Option<&BTreeSet<u64>>) {
+    if let Err(errors) = coll.verify() {
+        match changed {
+            Option::Some(changed) => panic!(
+                "rewriting expressions {:?} left the graph invalid:\n{}",
+                changed,
+                errors.join("\n")
+            ),
+            Option::None => panic!("the ordered graph is invalid:\n{}", errors.join("\n")),
         };
     }
 }
@@ -81,6 +136,7 @@ where
         root.get_matcher_tuple(),
         new_constant.get_matcher_tuple(),
     ));
+    mods.push(Remover::new(root));
 
     // determine if we can drop our sink?
     mods.push(RemoveSink::new(x_args, root));
@@ -1,11 +1,13 @@
 pub mod graphs;
 mod holes;
 
-pub use self::holes::brute_force_optimize;
+pub use self::holes::{brute_force_optimize, optimize_once, verified_optimize};
 
 #[cfg(test)]
 mod test {
+    use super::super::namespace::Determinism;
     use super::super::ordering::*;
+    use super::super::stdlib::Lowering;
     use super::brute_force_optimize;
 
     // construct the optimized collection
@@ -32,7 +34,9 @@ mod test {
         let mut coll = build_coll(dut);
         brute_force_optimize(&mut coll);
         match coll.get_expr(coll.get_return()) {
-            Option::Some(OrderedExpression::StdLib(StdLibraryFunc::D6(ref args))) => {
+            Option::Some(OrderedExpression::StdLib(ref args))
+                if args.get_lowering() == Some(Lowering::RollDice(6)) =>
+            {
                 assert!(matches!(
                     coll.get_expr(args.get_sources()[0].0),
                     Option::Some(OrderedExpression::Constant(ConstantValue::Int(5, _)))
@@ -50,7 +54,9 @@ mod test {
         // assert that it currently makes sense
         let old_return = coll.get_return();
         match coll.get_expr(coll.get_return()) {
-            Option::Some(OrderedExpression::StdLib(StdLibraryFunc::Len(_))) => {
+            Option::Some(OrderedExpression::StdLib(ref len))
+                if len.get_lowering() == Some(Lowering::Len) =>
+            {
                 // cool that is our length
             }
             what_ever => panic!("{:?}", what_ever),
@@ -67,4 +73,40 @@ mod test {
             what_ever => panic!("unexpected: {:?}", what_ever),
         };
     }
+
+    #[test]
+    fn test_fold_constants() {
+        let dut = r#"analyze ((((((7 - 2) * 3) / 5) + len(roll_d6(2))) > 4) & (true | false));"#;
+        let mut coll = build_coll(dut);
+        brute_force_optimize(&mut coll);
+        assert_eq!(coll.verify(), Ok(()));
+        let expected = "%0: bool = const true\nreturn %0";
+        assert_eq!(coll.to_string(), expected);
+
+        // random sub-graphs are left alone, even when every argument
+        // of a random function is constant
+        let dut = r#"analyze ((sum(roll(3, 1, 1)) > (2 * 2)) & (3 > 2));"#;
+        let mut coll = build_coll(dut);
+        brute_force_optimize(&mut coll);
+        assert_eq!(coll.verify(), Ok(()));
+        let ret = coll.get_return();
+        assert_eq!(coll.get_determinism(ret), Determinism::Random);
+        let optimized = coll.to_string();
+        assert!(optimized.contains("roll %"), "{}", optimized);
+        assert!(optimized.contains("= const 4 ->"), "{}", optimized);
+        assert!(optimized.contains("= const true ->"), "{}", optimized);
+
+        // folding would overflow, so the evaluation reports it
+        let dut = r#"analyze ((len(roll_d6(100)) + 100) + (len(roll_d6(1)) / 0));"#;
+        let mut coll = build_coll(dut);
+        brute_force_optimize(&mut coll);
+        let expected = r#"%0: int = const 100 -> %1
+%1: int = add %0, %0 -> %5
+%2: int = const 1 -> %4
+%3: int = const 0 -> %4
+%4: int = div %2, %3 -> %5
+%5: int = add %1, %4
+return %5"#;
+        assert_eq!(coll.to_string(), expected);
+    }
 }
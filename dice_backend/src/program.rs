@@ -0,0 +1,470 @@
+use std::collections::BTreeSet;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::cfgbuilder::ExpressionCollection;
+use super::diagnostic::{Code, Diagnostic};
+use super::dump::to_dot;
+use super::inliner::InlinedCollection;
+use super::namespace::{lint, Bindings, Namespace};
+use super::ordering::OrderedCollection;
+use super::parser_output::AbstractSyntaxTree;
+use super::peephole::{brute_force_optimize, verified_optimize};
+use super::runtime::{build_report, simulate, Report};
+use super::stdlib::Registry;
+
+/// the errors, or warnings, a program produced
+pub type Diagnostics = Vec<Diagnostic>;
+
+/// CompileOptions control how source code is checked.
+#[derive(Clone)]
+pub struct CompileOptions {
+    /// files the program loads are resolved relative to this
+    pub source_dir: PathBuf,
+    /// resolves parameters while the program is checked
+    pub bindings: Bindings,
+    /// the functions of the standard library
+    pub registry: Registry,
+    /// verifies the graph after every peephole rewrite, as debug
+    /// builds always do
+    pub verify_ir: bool,
+}
+impl Default for CompileOptions {
+    fn default() -> CompileOptions {
+        CompileOptions {
+            source_dir: PathBuf::new(),
+            bindings: Bindings::default(),
+            registry: Registry::standard(),
+            verify_ir: false,
+        }
+    }
+}
+impl CompileOptions {
+    /// options for the program at `path`, the files it loads are
+    /// relative to the program itself.
+    pub fn for_path(path: &str) -> CompileOptions {
+        CompileOptions {
+            source_dir: Path::new(path)
+                .parent()
+                .unwrap_or_else(|| Path::new(""))
+                .to_path_buf(),
+            ..CompileOptions::default()
+        }
+    }
+}
+
+/// Stage is a step of the compiler, after which the program may
+/// be written out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// the parsed source
+    Ast,
+    /// the basic block of the analysis, and of each function
+    Namespace,
+    /// the expression graph of each block
+    Cfg,
+    /// one expression graph, with every function inlined
+    Inlined,
+    /// the inlined graph, ordered for evaluation
+    Ordered,
+    /// the ordered graph, after every peephole optimization
+    Optimized,
+    /// the ordered & optimized graphs, as a Graphviz digraph
+    Dot,
+}
+impl Stage {
+    pub fn parse(arg: &str) -> Result<Stage, String> {
+        match arg {
+            "ast" => Ok(Stage::Ast),
+            "namespace" => Ok(Stage::Namespace),
+            "cfg" => Ok(Stage::Cfg),
+            "inlined" => Ok(Stage::Inlined),
+            "ordered" => Ok(Stage::Ordered),
+            "optimized" => Ok(Stage::Optimized),
+            "dot" => Ok(Stage::Dot),
+            _ => Err(format!(
+                "stage '{}' is not one of {}",
+                arg, "'ast', 'namespace', 'cfg', 'inlined', 'ordered', 'optimized' or 'dot'"
+            )),
+        }
+    }
+}
+
+/// Program is source code which parses, resolves, and type
+/// checks. It may be evaluated any number of times, from any
+/// number of threads.
+///
+/// The optimized graph for the bindings it was compiled with is
+/// kept. Evaluations whose parameters have the same values reuse
+/// it, others bind their values into the unbound graph.
+#[derive(Clone)]
+pub struct Program {
+    source: String,
+    source_dir: PathBuf,
+    registry: Registry,
+    warnings: Diagnostics,
+    bindings: Bindings,
+    /// the parameters the graph depends upon
+    parameters: BTreeSet<String>,
+    /// the optimized graph, with every parameter left unbound
+    inputs: Arc<OrderedCollection>,
+    graph: Arc<OrderedCollection>,
+    verify_ir: bool,
+}
+
+/// parses, resolves, & type checks a program without building
+/// it. Returns the program's warnings, or its errors.
+pub fn check(source: &str, options: &CompileOptions) -> Result<Diagnostics, Diagnostics> {
+    let ast = AbstractSyntaxTree::parse(source)?;
+    let namespace = build_namespace(&ast, options)?;
+    Ok(lint(&namespace))
+}
+
+fn build_namespace<'a>(
+    ast: &AbstractSyntaxTree<'a>,
+    options: &CompileOptions,
+) -> Result<Namespace<'a>, Diagnostics> {
+    Namespace::build(
+        ast,
+        &options.source_dir,
+        &options.bindings,
+        &options.registry,
+    )
+    .map_err(|e| vec![e])
+}
+
+/// checks a program, returning its errors if it does not build.
+///
+/// parameters are resolved with the bindings of `options`, each
+/// evaluation may resolve them again with different values.
+pub fn compile(source: &str, options: &CompileOptions) -> Result<Program, Diagnostics> {
+    let ast = AbstractSyntaxTree::parse(source)?;
+    let namespace = build_namespace(&ast, options)?;
+    let warnings = lint(&namespace);
+    let cfgcoll = ExpressionCollection::new(&namespace);
+    let mut inputs = OrderedCollection::new(&InlinedCollection::new(&cfgcoll));
+    optimize(&mut inputs, options.verify_ir);
+    let graph = bind(&inputs, &options.bindings, options.verify_ir)?;
+    Ok(Program {
+        source: source.to_string(),
+        source_dir: options.source_dir.clone(),
+        registry: options.registry.clone(),
+        warnings: warnings,
+        bindings: options.bindings.clone(),
+        parameters: namespace.get_parameters(),
+        inputs: Arc::new(inputs),
+        graph: Arc::new(graph),
+        verify_ir: options.verify_ir,
+    })
+}
+
+/// applies every peephole rewrite, returns the expressions the
+/// last one changed
+fn optimize(coll: &mut OrderedCollection, verify_ir: bool) -> BTreeSet<u64> {
+    if verify_ir {
+        verified_optimize(coll)
+    } else {
+        brute_force_optimize(coll)
+    }
+}
+
+/// substitutes the value of every parameter, then optimizes the
+/// graph further, as constants may now be folded
+fn bind(
+    inputs: &OrderedCollection,
+    bindings: &Bindings,
+    verify_ir: bool,
+) -> Result<OrderedCollection, Diagnostics> {
+    let mut graph = inputs.clone();
+    graph.bind(bindings).map_err(invalid_binding)?;
+    optimize(&mut graph, verify_ir);
+    Ok(graph)
+}
+
+fn invalid_binding(e: String) -> Diagnostics {
+    vec![Diagnostic::error(Code::InvalidLiteral, e)]
+}
+
+impl Program {
+    pub fn get_source(&self) -> &str {
+        &self.source
+    }
+
+    /// the lints the program raised when it was compiled
+    pub fn get_warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    /// the bindings the program was compiled with
+    pub fn get_bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    /// the name of every parameter the program depends upon
+    pub fn get_parameters(&self) -> &BTreeSet<String> {
+        &self.parameters
+    }
+
+    /// the optimized graph, for the bindings the program was
+    /// compiled with
+    pub fn get_graph(&self) -> &OrderedCollection {
+        &self.graph
+    }
+
+    /// the optimized graph, for the values `bindings` gives each
+    /// parameter. This never touches the source.
+    pub fn bind(&self, bindings: &Bindings) -> Result<Arc<OrderedCollection>, Diagnostics> {
+        if self.bindings.agrees_with(bindings, &self.parameters) {
+            Ok(self.graph.clone())
+        } else {
+            Ok(Arc::new(bind(&self.inputs, bindings, self.verify_ir)?))
+        }
+    }
+
+    /// evaluates every possible outcome of the program
+    pub fn evaluate(&self, bindings: &Bindings) -> Result<Report, Diagnostics> {
+        let graph = self.bind(bindings)?;
+        build_report(&graph).map_err(|e| vec![Diagnostic::error(Code::Evaluation, e)])
+    }
+
+    /// samples `trials` outcomes of the program, rather than
+    /// computing its exact distribution
+    pub fn simulate(
+        &self,
+        bindings: &Bindings,
+        trials: usize,
+        seed: Option<u64>,
+    ) -> Result<Report, Diagnostics> {
+        let graph = self.bind(bindings)?;
+        simulate(&graph, trials, seed).map_err(|e| vec![Diagnostic::error(Code::Evaluation, e)])
+    }
+
+    /*
+     * Each stage of the compiler, these are rebuilt from the source
+     * upon every call
+     *
+     */
+
+    pub fn parse<'a>(&'a self) -> Result<AbstractSyntaxTree<'a>, Diagnostics> {
+        AbstractSyntaxTree::parse(&self.source)
+    }
+
+    /// resolves the names & types of a parsed program
+    pub fn namespace<'a>(
+        &self,
+        ast: &AbstractSyntaxTree<'a>,
+        bindings: &Bindings,
+    ) -> Result<Namespace<'a>, Diagnostics> {
+        Namespace::build(ast, &self.source_dir, bindings, &self.registry).map_err(|e| vec![e])
+    }
+
+    /// the program as one expression graph, with every function inlined
+    pub fn inline(&self, bindings: &Bindings) -> Result<InlinedCollection, Diagnostics> {
+        let ast = self.parse()?;
+        let namespace = self.namespace(&ast, bindings)?;
+        let cfgcoll = ExpressionCollection::new(&namespace);
+        Ok(InlinedCollection::new(&cfgcoll))
+    }
+
+    /// the inlined graph, ordered for evaluation
+    pub fn order(&self, bindings: &Bindings) -> Result<OrderedCollection, Diagnostics> {
+        Ok(OrderedCollection::new(&self.inline(bindings)?))
+    }
+
+    /// the ordered graph, after every peephole optimization
+    pub fn optimize(&self, bindings: &Bindings) -> Result<OrderedCollection, Diagnostics> {
+        let mut coll = self.order(bindings)?;
+        optimize(&mut coll, self.verify_ir);
+        bind(&coll, bindings, self.verify_ir)
+    }
+
+    /// writes the program as it is after `stage`. Graphs list each
+    /// expression after its sources, with its type & sinks.
+    ///
+    /// when drawn, the expressions the last peephole rewrite
+    /// changed are highlighted within the optimized graph.
+    pub fn emit(&self, stage: Stage, bindings: &Bindings) -> Result<String, Diagnostics> {
+        let ast = self.parse()?;
+        if stage == Stage::Ast {
+            return Ok(ast.to_string());
+        }
+        let namespace = self.namespace(&ast, bindings)?;
+        let cfgcoll = ExpressionCollection::new(&namespace);
+        Ok(match stage {
+            Stage::Ast => _unreachable_panic!("the ast is emitted before it is resolved"),
+            Stage::Namespace => namespace.to_string(),
+            Stage::Cfg => cfgcoll.to_string(),
+            Stage::Inlined => InlinedCollection::new(&cfgcoll).to_string(),
+            Stage::Ordered => OrderedCollection::new(&InlinedCollection::new(&cfgcoll)).to_string(),
+            Stage::Optimized => {
+                let mut coll = OrderedCollection::new(&InlinedCollection::new(&cfgcoll));
+                optimize(&mut coll, self.verify_ir);
+                bind(&coll, bindings, self.verify_ir)?.to_string()
+            }
+            Stage::Dot => {
+                let ordered = OrderedCollection::new(&InlinedCollection::new(&cfgcoll));
+                let mut optimized = ordered.clone();
+                let mut changed = optimize(&mut optimized, self.verify_ir);
+                optimized.bind(bindings).map_err(invalid_binding)?;
+                let bound = optimize(&mut optimized, self.verify_ir);
+                if !bound.is_empty() {
+                    changed = bound;
+                }
+                let mut dump = optimized.dump();
+                dump.set_changed(changed);
+                to_dot(&[("ordered", &ordered.dump()), ("optimized", &dump)])
+            }
+        })
+    }
+}
+
+/// read_path loads a program's source
+pub fn read_path(path: &str) -> Result<String, String> {
+    let mut input_file = match OpenOptions::new().read(true).open(path) {
+        Ok(input_file) => input_file,
+        Err(e) => return Err(format!("failed to open file={:?} with error={:?}", path, e)),
+    };
+    let mut file_data = String::with_capacity(4096);
+    match input_file.read_to_string(&mut file_data) {
+        Ok(_) => {}
+        Err(e) => return Err(format!("failed to read file={:?} with error={:?}", path, e)),
+    };
+    Ok(file_data)
+}
+
+#[test]
+fn test_compile_and_evaluate() {
+    use super::parser_output::Rational;
+    use super::runtime::Datum;
+
+    let dut = r#"
+const unused: int = 4;
+analyze (sum(roll_d6(1)) >= %d{{T}});
+"#;
+    let mut options = CompileOptions::default();
+    options.bindings.read_environment(false);
+    options.bindings.define("T", "1");
+    let program = compile(dut, &options).unwrap();
+    assert_eq!(program.get_warnings().len(), 1);
+
+    // the same program, evaluated with different parameters
+    for t in 1..4 {
+        let mut bindings = options.bindings.clone();
+        bindings.define("T", t.to_string());
+        let report = program.evaluate(&bindings).unwrap();
+        let expected = Rational::new(7 - t as u128, 6);
+        assert_eq!(report.get_probability(&Datum::from(true)), expected);
+    }
+
+    // every stage is reachable from the program
+    assert!(program.parse().is_ok());
+    assert!(program
+        .inline(&options.bindings)
+        .unwrap()
+        .get_return()
+        .is_some());
+    let ordered = program.order(&options.bindings).unwrap();
+    let optimized = program.optimize(&options.bindings).unwrap();
+    assert!(optimized.get_expr(optimized.get_return()).is_some());
+    assert!(ordered.get_expr(ordered.get_return()).is_some());
+
+    // evaluation binds the compiled graph, the source isn't parsed again
+    let mut detached = program.clone();
+    detached.source = String::from("analyze missing;");
+    let mut bindings = options.bindings.clone();
+    bindings.define("T", "6");
+    let report = detached.evaluate(&bindings).unwrap();
+    assert_eq!(report.get_probability(&Datum::from(true)), Rational::new(1, 6));
+    assert!(detached.simulate(&bindings, 10, Option::Some(1)).is_ok());
+
+    // errors are reported, rather than a program being returned
+    assert!(compile("analyze missing;", &options).is_err());
+    let mut unbound = options.bindings.clone();
+    unbound.define("T", "true");
+    assert!(program.evaluate(&unbound).is_err());
+}
+
+#[test]
+fn test_emit_every_stage() {
+    let dut = r#"
+fn double(x: int) -> int {
+    return (x * 2);
+}
+const dice: vec<int> = roll_d6(2);
+analyze double(sum(dice));
+"#;
+    let program = compile(dut, &CompileOptions::default()).unwrap();
+    let bindings = Bindings::default();
+    let emit = |stage| program.emit(stage, &bindings).unwrap();
+    assert!(emit(Stage::Ast).contains("const dice: vec<int> = roll_d6( 2 );"));
+    let namespace = emit(Stage::Namespace);
+    assert!(namespace.contains("    dice: vec<int> = roll_d6(2)"));
+    assert!(namespace.contains("fn double:\n    x: int = x\n    return: int = ( x * 2 )"));
+    assert!(!namespace.contains("fn sum:"));
+    assert!(emit(Stage::Cfg).contains("vec<int> = extern dice %1"));
+    assert!(emit(Stage::Cfg).contains("fn double:\n%0: int = arg 0"));
+    let expected = r#"%0: int = const 2 -> %1, %3
+%1: vec<int> = roll_d6 %0 -> %2
+%2: int = sum %1 -> %3
+%3: int = mul %2, %0
+return %3"#;
+    assert_eq!(emit(Stage::Inlined), expected);
+    assert_eq!(emit(Stage::Ordered), expected);
+    assert!(emit(Stage::Optimized).ends_with("return %3"));
+    let dot = emit(Stage::Dot);
+    assert!(dot.starts_with("digraph program {"));
+    assert!(dot.contains("subgraph cluster_ordered {"));
+    assert!(dot.contains("optimized_3 [label=\"%3 mul\\nint\", peripheries=2, style=\"bold\"];"));
+    assert!(Stage::parse("ir").is_err());
+}
+
+#[test]
+fn test_program_is_shared_between_threads() {
+    use super::inliner::InlinedCollection;
+    use super::parser_output::Rational;
+    use super::runtime::Datum;
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Program>();
+    assert_send_sync::<InlinedCollection>();
+    assert_send_sync::<OrderedCollection>();
+    assert_send_sync::<Report>();
+
+    let dut = r#"
+const other: int = %d{{OTHER}};
+analyze (sum(roll_d6(2)) >= %d{{T}});
+"#;
+    let mut options = CompileOptions::default();
+    options.bindings.read_environment(false);
+    options.bindings.define("T", "2");
+    options.bindings.define("OTHER", "1");
+    options.bindings.define("UNUSED", "1");
+    let program = Arc::new(compile(dut, &options).unwrap());
+    let names: Vec<&str> = program
+        .get_parameters()
+        .iter()
+        .map(|x| x.as_str())
+        .collect();
+    assert_eq!(names, vec!["OTHER", "T"]);
+
+    // the program outlives its source & is evaluated concurrently
+    let threads: Vec<_> = (2..8)
+        .map(|t| {
+            let program = program.clone();
+            let mut bindings = options.bindings.clone();
+            bindings.define("T", t.to_string());
+            thread::spawn(move || program.evaluate(&bindings).unwrap())
+        })
+        .collect();
+    let expected = [36, 35, 33, 30, 26, 21];
+    for (thread, expected) in threads.into_iter().zip(expected.iter()) {
+        let report = thread.join().unwrap();
+        assert_eq!(
+            report.get_probability(&Datum::from(true)),
+            Rational::new(*expected, 36)
+        );
+    }
+}
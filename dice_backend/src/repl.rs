@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::io::{stdin, stdout, BufRead, Write};
+
+use super::diagnostic::{render_all, Diagnostic, ErrorFormat};
+use super::formatter::format_item;
+use super::parser_output::{AbstractSyntaxTree, Structures};
+use super::program::{check, compile, read_path, CompileOptions, Program};
+use super::runtime::Report;
+use super::seahasher::DefaultSeaHasher;
+
+const HELP: &'static str = "\
+enter `const` & `fn` definitions, or an expression to see its distribution.
+
+  :type EXPR    prints the type of an expression
+  :ir EXPR      prints the optimized program which evaluates an expression
+  :load FILE    loads the definitions of a file, and evaluates its analysis
+  :defs         prints every definition
+  :reset        forgets every definition
+  :quit         exits";
+
+/// Repl holds the definitions entered so far.
+///
+/// Every input is compiled as a program of the definitions.
+/// Reports are kept by the hash of the optimized graph they
+/// evaluate, so an expression is only evaluated again when a
+/// definition it depends upon has changed.
+pub struct Repl {
+    /// the name & formatted source of each definition, in the
+    /// order they were first entered
+    definitions: Vec<(String, String)>,
+    /// files are loaded relative to the last file loaded
+    options: CompileOptions,
+    cache: HashMap<u64, Report, DefaultSeaHasher>,
+    /// the number of expressions which were evaluated, rather
+    /// than found within the cache
+    evaluations: usize,
+}
+impl Repl {
+    pub fn new(options: CompileOptions) -> Repl {
+        Repl {
+            definitions: Vec::new(),
+            options,
+            cache: HashMap::default(),
+            evaluations: 0,
+        }
+    }
+
+    /// handles one complete input, returns what should be printed
+    pub fn eval(&mut self, input: &str) -> Result<String, String> {
+        let input = input.trim();
+        if input.is_empty() {
+            Ok(String::new())
+        } else if input.starts_with(':') {
+            self.command(input)
+        } else if is_definition(input) {
+            self.define(input)
+        } else {
+            self.evaluate(input.trim_end_matches(';'))
+        }
+    }
+
+    fn command(&mut self, input: &str) -> Result<String, String> {
+        let mut parts = input.splitn(2, char::is_whitespace);
+        let name = parts.next().unwrap_or("");
+        let arg = parts.next().unwrap_or("").trim().trim_end_matches(';');
+        match name {
+            ":type" | ":t" => self.type_of(arg),
+            ":ir" => self.ir(arg),
+            ":load" | ":l" => {
+                let source = read_path(arg)?;
+                self.options.source_dir = CompileOptions::for_path(arg).source_dir;
+                self.define(&source)
+            }
+            ":defs" => Ok(self.get_definitions()),
+            ":reset" => {
+                self.definitions.clear();
+                Ok(String::new())
+            }
+            ":help" | ":h" => Ok(HELP.to_string()),
+            _ => Err(format!("unknown command '{}', see :help", name)),
+        }
+    }
+
+    /// adds (or replaces) the definitions of a program. The
+    /// program's `analyze` statement, if any, is evaluated.
+    fn define(&mut self, source: &str) -> Result<String, String> {
+        let ast = AbstractSyntaxTree::parse(source).map_err(|e| render(&e, source))?;
+        let mut definitions = self.definitions.clone();
+        let mut analysis = None;
+        for item in ast.ast.iter() {
+            let name = match item {
+                &Structures::Constant(ref constant) => constant.name,
+                &Structures::Func(ref func) => func.name,
+                &Structures::Analyze(ref analyze) => {
+                    let span = analyze.expr.get_span();
+                    analysis = Some(source[span.start..span.end].to_string());
+                    continue;
+                }
+            };
+            let text = format_item(source, item);
+            match definitions.iter().position(|(defined, _)| defined == name) {
+                Option::Some(index) => definitions[index].1 = text,
+                Option::None => definitions.push((name.to_string(), text)),
+            };
+        }
+
+        // definitions are only kept if they resolve
+        let program = with_analysis(&definitions, "true");
+        check(&program, &self.options).map_err(|e| render(&e, &program))?;
+        self.definitions = definitions;
+
+        match analysis {
+            Option::Some(expr) => self.evaluate(&expr),
+            Option::None => Ok(String::new()),
+        }
+    }
+
+    fn evaluate(&mut self, expr: &str) -> Result<String, String> {
+        let program = self.compile(expr)?;
+        let key = self.cache.hasher().hash_one(program.get_graph().to_string());
+        match self.cache.get(&key) {
+            Option::Some(report) => return Ok(report.serialize_report(None)),
+            Option::None => {}
+        };
+        let report = program
+            .evaluate(&self.options.bindings)
+            .map_err(|e| render(&e, program.get_source()))?;
+        self.evaluations += 1;
+        let output = report.serialize_report(None);
+        self.cache.insert(key, report);
+        Ok(output)
+    }
+
+    fn type_of(&self, expr: &str) -> Result<String, String> {
+        let program = self.compile(expr)?;
+        let render = |e: Vec<Diagnostic>| render(&e, program.get_source());
+        let ast = program.parse().map_err(render)?;
+        let namespace = program
+            .namespace(&ast, &self.options.bindings)
+            .map_err(render)?;
+        let kind = namespace
+            .get_own_block()
+            .as_ref()
+            .and_then(|block| block.get_return().as_ref())
+            .map(|expr| expr.get_kind());
+        Ok(match kind {
+            Option::Some(kind) => kind.to_string(),
+            Option::None => String::new(),
+        })
+    }
+
+    fn ir(&self, expr: &str) -> Result<String, String> {
+        Ok(self.compile(expr)?.get_graph().to_string())
+    }
+
+    /// compiles a program of every definition, which analyzes `expr`
+    fn compile(&self, expr: &str) -> Result<Program, String> {
+        let program = with_analysis(&self.definitions, expr);
+        compile(&program, &self.options).map_err(|e| render(&e, &program))
+    }
+
+    fn get_definitions(&self) -> String {
+        let definitions: Vec<&str> = self
+            .definitions
+            .iter()
+            .map(|(_, text)| text.as_str())
+            .collect();
+        definitions.join("\n")
+    }
+}
+
+/// reads inputs from stdin until it closes, or `:quit` is entered
+pub fn run(options: CompileOptions) -> Result<(), String> {
+    let stdin = stdin();
+    let mut input = stdin.lock();
+    let mut repl = Repl::new(options);
+    let mut buffer = String::new();
+    loop {
+        print!("{}", if buffer.is_empty() { "> " } else { "... " });
+        match stdout().flush() {
+            Ok(()) => {}
+            Err(e) => return Err(format!("failed to write prompt with error={:?}", e)),
+        };
+        let mut line = String::new();
+        match input.read_line(&mut line) {
+            Ok(0) => {
+                println!();
+                return Ok(());
+            }
+            Ok(_) => buffer.push_str(&line),
+            Err(e) => return Err(format!("failed to read input with error={:?}", e)),
+        };
+        if !is_complete(&buffer) {
+            continue;
+        }
+        let entry = std::mem::take(&mut buffer);
+        match entry.trim() {
+            ":quit" | ":q" => return Ok(()),
+            _ => {}
+        };
+        match repl.eval(&entry) {
+            Ok(ref output) if output.is_empty() => {}
+            Ok(output) => println!("{}", output.trim_end()),
+            Err(e) => eprintln!("{}", e.trim_end()),
+        };
+    }
+}
+
+fn render(diagnostics: &[Diagnostic], source: &str) -> String {
+    render_all(diagnostics, ErrorFormat::Human, "<repl>", source)
+}
+
+/// a program of every definition, which analyzes `expr`
+fn with_analysis(definitions: &[(String, String)], expr: &str) -> String {
+    let mut program = String::new();
+    for (_, text) in definitions {
+        program.push_str(text);
+        program.push('\n');
+    }
+    program.push_str(&format!("analyze {};\n", expr));
+    program
+}
+
+/// definitions & `analyze` statements are read as a program,
+/// anything else is an expression.
+fn is_definition(input: &str) -> bool {
+    let word: String = input
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    match word.as_str() {
+        "const" | "fn" | "analyze" => true,
+        _ => input.starts_with("#[") || input.starts_with("//"),
+    }
+}
+
+/// an input is complete once its brackets are balanced, and a
+/// definition is terminated.
+fn is_complete(buffer: &str) -> bool {
+    let trimmed = buffer.trim();
+    if trimmed.starts_with(':') {
+        return true;
+    }
+    let mut depth = 0i64;
+    for line in trimmed.lines() {
+        let code = line.split("//").next().unwrap_or("");
+        for c in code.chars() {
+            match c {
+                '(' | '{' => depth += 1,
+                ')' | '}' => depth -= 1,
+                _ => {}
+            };
+        }
+    }
+    if depth > 0 {
+        return false;
+    }
+    let last = trimmed.lines().last().unwrap_or("");
+    let code = last.split("//").next().unwrap_or("").trim_end();
+    !is_definition(trimmed) || code.ends_with(';') || code.ends_with('}')
+}
+
+#[test]
+fn test_repl() {
+    let mut repl = Repl::new(CompileOptions::default());
+    assert_eq!(repl.eval("const dice: vec<int> = roll_d6(2);").unwrap(), "");
+    assert_eq!(repl.eval(":type sum(dice)").unwrap(), "int");
+
+    let first = repl.eval("(sum(dice) > 11)").unwrap();
+    assert!(first.contains("true: 0.027777777778 (1/36)"));
+    assert_eq!(repl.evaluations, 1);
+
+    // an unrelated definition does not cause re-evaluation
+    repl.eval("fn double(x: int) -> int {\n    return (x * 2);\n}")
+        .unwrap();
+    assert_eq!(repl.eval("(sum(dice) > 11);").unwrap(), first);
+    assert_eq!(repl.evaluations, 1);
+
+    // redefining a constant replaces it
+    repl.eval("const dice: vec<int> = roll_d6(1);").unwrap();
+    assert!(repl.eval("(sum(dice) > 11)").unwrap().contains("false: 1"));
+    assert_eq!(repl.evaluations, 2);
+    assert!(repl.eval(":ir double(sum(dice))").unwrap().contains("return %"));
+
+    // definitions which do not resolve are not kept
+    assert!(repl.eval("const broken: int = roll_d6(1);").is_err());
+    assert!(repl.eval(":type broken").is_err());
+    assert!(repl.eval("(dice + true)").is_err());
+
+    repl.eval(":reset").unwrap();
+    assert!(repl.eval("sum(dice)").is_err());
+    assert!(repl.eval(":frobnicate").is_err());
+
+    assert!(!is_complete("fn double(x: int) -> int {\n"));
+    assert!(!is_complete("const x: int = 1\n"));
+    assert!(!is_complete("sum(roll_d6(\n"));
+    assert!(is_complete("sum(roll_d6(2))\n"));
+    assert!(is_complete(":type (1 + 2\n"));
+}
+
+#[test]
+fn test_repl_load() {
+    use std::fs;
+
+    // files are loaded relative to the file which loads them
+    let dir = ::std::env::temp_dir().join(format!("dice_backend_repl_{}", ::std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("hits.csv"), "value,probability\n0,0.5\n1,0.5\n").unwrap();
+    fs::write(
+        dir.join("hits.dice"),
+        "const hits: int = load_distribution(\"hits.csv\");\nanalyze hits;\n",
+    )
+    .unwrap();
+
+    let mut repl = Repl::new(CompileOptions::default());
+    let path = dir.join("hits.dice");
+    let output = repl.eval(&format!(":load {}", path.display())).unwrap();
+    assert!(output.contains("1: 0.5"), "{}", output);
+    assert!(repl.eval("(hits + hits)").unwrap().contains("2: 0.5"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,176 +1,133 @@
-use std::collections::BTreeMap;
-use std::mem::replace;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
-use super::super::ordering::{OrderedCollection, OrderedExpression, StdLibraryFunc, OrdTrait, ConstantValue};
-use super::super::parser_output::{Operation, TypeData};
+use super::super::ordering::{OrdTrait, OrderedCollection, OrderedExpression};
+use super::super::parser_output::Rational;
+use super::super::seahasher::DefaultSeaHasher;
 
-use super::*;
+use super::eval::evaluate;
+use super::math::rational_overflow;
+use super::{Datum, Element, Report};
 
-#[derive(Default)]
-pub struct Resolved<'a> {
-    pub data: BTreeMap<&'a OrderedExpression, LambdaKind>,
-}
-impl<'a> Resolved<'a> {
-    fn mark_idempotent(&mut self, expr: &&'a OrderedExpression) {
-        match self.data.get_mut(expr) {
-            Option::Some(ref mut lambda_kind) => {
-                lambda_kind.make_idempotent();
-            }
-            _ => {}
-        };
-    }
+/// Frame is one possible assignment of every live expression.
+///
+/// Expressions which are read more than once (such as a `const`
+/// holding a dice roll) keep their value in the frame until their
+/// last reader runs. This is what keeps their readers dependent.
+type Frame = BTreeMap<u64, Datum>;
 
-    fn contains(&self, expr: &&'a OrderedExpression) -> bool {
-        self.data.get(expr).is_some()
-    }
+/// every distinct frame, and the probability of observing it
+type Frames = HashMap<Frame, Rational, DefaultSeaHasher>;
 
-    fn insert(&mut self, expr: &'a OrderedExpression, lambda: LambdaKind) {
-        self.data.insert(expr, lambda);
-    }
+/// runs the program & builds the report
+pub fn build_report(coll: &OrderedCollection) -> Result<Report, String> {
+    let ret = coll.get_return();
+    let order = topological_order(coll)?;
+    let last_use = last_use(coll, &order);
 
-    fn resolve(&mut self, expr: &&'a OrderedExpression, args: &mut Vec<Iter>) -> Iter {
-        match self.data.get_mut(expr) {
-            Option::Some(ref mut lambda_kind) => lambda_kind.get_iter(args),
-            Option::None => _unreachable_panic!(),
+    let mut frames = Frames::default();
+    frames.insert(Frame::new(), Rational::one());
+    for id in order.iter() {
+        let expr = coll.get_expr(*id).unwrap();
+        let dead: &[u64] = last_use.get(id).map(|v| v.as_slice()).unwrap_or(&[]);
+        let mut next = Frames::default();
+        for (frame, prob) in frames.into_iter() {
+            let args: Vec<&Datum> = expr
+                .get_sources()
+                .iter()
+                .map(|(source, _)| &frame[source])
+                .collect();
+            // drawing consumes cards, later draws from the
+            // same deck only see what remains
+            let consumes = match expr {
+                &OrderedExpression::StdLib(ref func) if func.get_function().consumes() => {
+                    Some(func.get_function())
+                }
+                _ => None,
+            };
+            for outcome in evaluate(expr, &args)? {
+                let (datum, outcome_prob) = outcome.split();
+                let mut new_frame = frame.clone();
+                for source in dead {
+                    new_frame.remove(source);
+                }
+                if let Option::Some(func) = consumes {
+                    let consumed = expr.get_sources()[0].0;
+                    if !dead.contains(&consumed) {
+                        new_frame.insert(consumed, func.remaining(&args, &datum)?);
+                    }
+                }
+                new_frame.insert(*id, datum);
+                let weight = next.entry(new_frame).or_insert_with(Rational::zero);
+                *weight = prob
+                    .checked_mul(&outcome_prob)
+                    .and_then(|prob| weight.checked_add(&prob))
+                    .ok_or_else(rational_overflow)?;
+            }
         }
+        frames = next;
     }
-}
-
-/// runs the program & builds the report
-pub fn build_report<'a>(coll: &'a OrderedCollection) -> Report {
-    let mut resolved = Resolved::default();
-
-    let ret = coll.get_expr(coll.get_return()).unwrap();
-    lambda_builder_recursive(&mut resolved, coll, ret);
 
-    let mut stack = Vec::<Iter>::new();
-    builder_recursive(&mut resolved, coll, ret, &mut stack).collect()
+    Report::from_elements(
+        frames
+            .into_iter()
+            .map(|(mut frame, prob)| Element::new(frame.remove(&ret).unwrap(), prob)),
+    )
 }
 
-// builds the stack of computations to run
-fn builder_recursive<'a>(
-    resolve: &mut Resolved<'a>,
-    coll: &'a OrderedCollection,
-    expr: &'a OrderedExpression,
-    stack: &mut Vec<Iter>,
-) -> Iter {
-
-    let sources = expr.get_sources();
-    match sources.len() {
-        0 => {
-            resolve.resolve(&expr, stack)
-        }
-        1 => {
-            let arg = sources[0].0;
-            let arg_iter = builder_recursive(resolve, coll, coll.get_expr(arg).unwrap(), stack);
-            stack.push(arg_iter);
-            resolve.resolve(&expr, stack)
+/// orders every expression reachable from the return statement
+/// such that sources are always evaluated before their sinks.
+pub fn topological_order(coll: &OrderedCollection) -> Result<Vec<u64>, String> {
+    fn visit(
+        coll: &OrderedCollection,
+        id: u64,
+        visiting: &mut BTreeSet<u64>,
+        done: &mut BTreeSet<u64>,
+        order: &mut Vec<u64>,
+    ) -> Result<(), String> {
+        if done.contains(&id) {
+            return Ok(());
         }
-        2 => {
-            let left = sources[0].0;
-            let right = sources[1].0;
-            let arg_left = builder_recursive(resolve, coll, coll.get_expr(left).unwrap(), stack);
-            let arg_right = builder_recursive(resolve, coll, coll.get_expr(right).unwrap(), stack);
-            stack.push(arg_right);
-            stack.push(arg_left);
-            resolve.resolve(&expr, stack)
+        if !visiting.insert(id) {
+            return Err(format!("expression {} depends upon itself", id));
         }
-        _ => {
-            unimplemented!("3 or more argument byte code isn't supported");
+        let expr = match coll.get_expr(id) {
+            Option::Some(expr) => expr,
+            Option::None => return Err(format!("expression {} does not exist", id)),
+        };
+        for (source, _) in expr.get_sources() {
+            visit(coll, *source, visiting, done, order)?;
         }
-    };
+        visiting.remove(&id);
+        done.insert(id);
+        order.push(id);
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    visit(
+        coll,
+        coll.get_return(),
+        &mut BTreeSet::new(),
+        &mut BTreeSet::new(),
+        &mut order,
+    )?;
+    Ok(order)
 }
 
-// converts enum's into boxed iterators that represent their
-// calcs
-fn lambda_builder_recursive<'a>(
-    resolve: &mut Resolved<'a>,
-    coll: &'a OrderedCollection,
-    expr: &'a OrderedExpression,
-) {
-    if resolve.contains(&expr) {
-        // updates collection so value is idempotent
-        resolve.mark_idempotent(&expr);
-        return;
-    }
-    match expr {
-        &OrderedExpression::Constant(ConstantValue::Bool(ref b,_)) => {
-            resolve.insert(expr, LambdaKind::Init(const_bool(b.clone())))
-        },
-        &OrderedExpression::Constant(ConstantValue::Int(ref i,_)) => {
-            resolve.insert(expr, LambdaKind::Init(const_int(i.clone())))
-        },
-        &OrderedExpression::StdLib(ref std) => {
-            let sources = std.get_sources();
-            match sources.len() {
-                1 => {
-                    let arg = sources[0].0;
-                    match std {
-                        &StdLibraryFunc::D6(_) => {
-            lambda_builder_recursive(resolve, coll, coll.get_expr(arg).unwrap());
-            resolve.insert(expr, LambdaKind::Chain(d3()));
-                        }
-                        &StdLibraryFunc::D3(_) => {
-            lambda_builder_recursive(resolve, coll, coll.get_expr(arg).unwrap());
-            resolve.insert(expr, LambdaKind::Chain(d6()));
-                        }
-                        &StdLibraryFunc::Count(_) => {
-            lambda_builder_recursive(resolve, coll, coll.get_expr(arg).unwrap());
-            resolve.insert(expr, LambdaKind::Chain(count()));
-                        }
-                        &StdLibraryFunc::Len(_) => {
-            lambda_builder_recursive(resolve, coll, coll.get_expr(arg).unwrap());
-            resolve.insert(expr, LambdaKind::Chain(len()));
-                        }
-                        &StdLibraryFunc::Sum(_) => {
-            lambda_builder_recursive(resolve, coll, coll.get_expr(arg).unwrap());
-            resolve.insert(expr, LambdaKind::Chain(sum()));
-                        }
-                        &StdLibraryFunc::Max(_) => {
-            lambda_builder_recursive(resolve, coll, coll.get_expr(arg).unwrap());
-            resolve.insert(expr, LambdaKind::Chain(max()));
-                        }
-                        &StdLibraryFunc::Min(_) => {
-            lambda_builder_recursive(resolve, coll, coll.get_expr(arg).unwrap());
-            resolve.insert(expr, LambdaKind::Chain(min()));
-                        }
-                        x => {
-                            panic!("{:?} should not have 1 argument", x);
-                        }
-                    };
-                }
-                2 => {
-                    let a = sources[0].0;
-                    let b = sources[1].0;
-                    match std {
-                        &StdLibraryFunc::Join(_) => {
-            lambda_builder_recursive(resolve, coll, coll.get_expr(a).unwrap());
-            lambda_builder_recursive(resolve, coll, coll.get_expr(b).unwrap());
-            resolve.insert(expr, LambdaKind::Combinator(join()));
-                        }
-                        &StdLibraryFunc::Filter(_) => {
-            lambda_builder_recursive(resolve, coll, coll.get_expr(a).unwrap());
-            lambda_builder_recursive(resolve, coll, coll.get_expr(b).unwrap());
-            resolve.insert(expr, LambdaKind::Combinator(filter()));
-                        },
-                        x => {
-                            panic!("{:?} should  not have 2 arguments", x);
-                        }
-                    }
-                }
-            }
-        }
-        &OrderedExpression::Op(ref op) => {
-            let sources = op.get_sources();
-            debug_assert_eq!(sources.len(), 2);
-            let left = sources[0].0;
-            let right = sources[0].0;
-            lambda_builder_recursive(resolve, coll, coll.get_expr(left).unwrap());
-            lambda_builder_recursive(resolve, coll, coll.get_expr(right).unwrap());
-            resolve.insert(expr, LambdaKind::Combinator(from_op(op)));
+/// for each expression, which sources are no longer needed after it runs
+pub fn last_use(coll: &OrderedCollection, order: &[u64]) -> BTreeMap<u64, Vec<u64>> {
+    let mut last_reader = BTreeMap::<u64, u64>::new();
+    for id in order {
+        for (source, _) in coll.get_expr(*id).unwrap().get_sources() {
+            last_reader.insert(*source, *id);
         }
-        x => {
-            panic!("{:?} is not implemented",x );
+    }
+    let mut output = BTreeMap::<u64, Vec<u64>>::new();
+    for (source, reader) in last_reader {
+        let list = output.entry(reader).or_default();
+        if !list.contains(&source) {
+            list.push(source);
         }
     }
+    output
 }
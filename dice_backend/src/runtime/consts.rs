@@ -9,6 +9,11 @@ impl Dice3 {
         Dice3 { arg: [1, 2, 3] }
     }
 }
+impl Default for Dice3 {
+    fn default() -> Dice3 {
+        Dice3::new()
+    }
+}
 impl IntoIterator for Dice3 {
     type Item = i8;
     type IntoIter = Dice3Iter;
@@ -32,7 +37,7 @@ impl Iterator for Dice3Iter {
         if self.pos >= 3 {
             None
         } else {
-            let x: i8 = self.arg[self.pos].clone();
+            let x: i8 = self.arg[self.pos];
             self.pos += 1;
             Some(x)
         }
@@ -45,11 +50,7 @@ impl Iterator for Dice3Iter {
 impl ::std::iter::ExactSizeIterator for Dice3Iter {
     #[inline(always)]
     fn len(&self) -> usize {
-        if self.pos >= 3 {
-            0
-        } else {
-            3 - self.pos
-        }
+        3_usize.saturating_sub(self.pos)
     }
 }
 
@@ -65,6 +66,11 @@ impl Dice6 {
         }
     }
 }
+impl Default for Dice6 {
+    fn default() -> Dice6 {
+        Dice6::new()
+    }
+}
 impl IntoIterator for Dice6 {
     type Item = i8;
     type IntoIter = Dice6Iter;
@@ -88,7 +94,7 @@ impl Iterator for Dice6Iter {
         if self.pos >= 6 {
             None
         } else {
-            let x: i8 = self.arg[self.pos].clone();
+            let x: i8 = self.arg[self.pos];
             self.pos += 1;
             Some(x)
         }
@@ -99,10 +105,6 @@ impl Iterator for Dice6Iter {
 }
 impl ::std::iter::ExactSizeIterator for Dice6Iter {
     fn len(&self) -> usize {
-        if self.pos >= 6 {
-            0
-        } else {
-            6 - self.pos
-        }
+        6_usize.saturating_sub(self.pos)
     }
 }
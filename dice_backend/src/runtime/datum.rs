@@ -14,6 +14,8 @@ pub enum Datum {
     Int(i8),
     CollectionOfInt(IntVec),
     CollectionOfBool(BoolVec),
+    /// the cards remaining within a deck, always sorted
+    Deck(IntVec),
 }
 #[test]
 fn assert_datum_size() {
@@ -25,10 +27,15 @@ impl fmt::Debug for Datum {
             &Datum::Bool(ref b) => write!(f, "{}", *b),
             &Datum::Int(ref i) => write!(f, "{}", *i),
             &Datum::CollectionOfInt(ref i) => {
-                f.debug_list().entries(i.clone().into_iter()).finish()
+                f.debug_list().entries(i.clone()).finish()
             }
             &Datum::CollectionOfBool(ref b) => {
-                f.debug_list().entries(b.clone().into_iter()).finish()
+                f.debug_list().entries(b.clone()).finish()
+            }
+            &Datum::Deck(ref d) => {
+                write!(f, "deck(")?;
+                f.debug_list().entries(d.clone()).finish()?;
+                write!(f, ")")
             }
         }
     }
@@ -62,6 +69,12 @@ impl Hash for Datum {
                     state.write_u8(*item as u8);
                 }
             }
+            &Datum::Deck(ref vec) => {
+                state.write_u8(5);
+                for item in vec.as_slice() {
+                    state.write_i8(*item);
+                }
+            }
         }
     }
 }
@@ -106,20 +119,21 @@ impl Datum {
             &Datum::CollectionOfBool(_) => TypeData::CollectionOfBool,
             &Datum::Bool(_) => TypeData::Bool,
             &Datum::Int(_) => TypeData::Int,
+            &Datum::Deck(_) => TypeData::Deck,
         }
     }
 
     /// return datum as an int
     pub fn get_int(&self) -> i8 {
         match self {
-            &Datum::Int(ref i) => i.clone(),
+            &Datum::Int(ref i) => *i,
             _ => _unreachable_panic!(),
         }
     }
 
     pub fn get_bool(&self) -> bool {
         match self {
-            &Datum::Bool(ref b) => b.clone(),
+            &Datum::Bool(ref b) => *b,
             _ => _unreachable_panic!(),
         }
     }
@@ -149,11 +163,16 @@ impl Datum {
         match self {
             &Datum::CollectionOfInt(ref s) => s.len() as i8,
             &Datum::CollectionOfBool(ref s) => s.len() as i8,
+            &Datum::Deck(ref s) => s.len() as i8,
             &Datum::Bool(_) => 1,
             &Datum::Int(_) => 1,
         }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn extend_from<I: IntoIterator<Item = i8>>(&mut self, arg: I) {
         match self {
             &mut Datum::CollectionOfInt(ref mut vec) => vec.extend(arg),
@@ -1,40 +1,43 @@
-use std::mem::replace;
-
+use super::super::parser_output::Rational;
 use super::Datum;
 
-/// Element is a single value
+/// Element is a single value, and the exact weight of observing it
 #[derive(Clone, Debug)]
 pub struct Element {
     datum: Datum,
-    prob: f64,
-    #[allow(dead_code)]
-    _pad: u64,
-}
-#[test]
-fn assert_element_size() {
-    assert_eq!(::std::mem::size_of::<Element>(), 64);
+    prob: Rational,
 }
 impl Element {
     /// build a new element from a datum
-    pub fn new<T>(datum: T, prob: f64) -> Element
+    pub fn new<T>(datum: T, prob: Rational) -> Element
     where
         Datum: From<T>,
     {
         Element {
             datum: Datum::from(datum),
-            _pad: 0,
             prob,
         }
     }
 
+    /// an element which is always observed
+    pub fn certain<T>(datum: T) -> Element
+    where
+        Datum: From<T>,
+    {
+        Element::new(datum, Rational::one())
+    }
+
     /// split into components
-    pub fn split(self) -> (Datum, f64) {
+    pub fn split(self) -> (Datum, Rational) {
         (self.datum, self.prob)
     }
 
-    /// build iterator
-    pub fn build_iter(self) -> Box<dyn Iterator<Item = Element>> {
-        Box::new(Some(self).into_iter())
+    pub fn get_datum<'a>(&'a self) -> &'a Datum {
+        &self.datum
+    }
+
+    pub fn get_prob(&self) -> Rational {
+        self.prob
     }
 }
 
@@ -59,7 +62,7 @@ impl PartialEq for Element {
 impl Eq for Element {}
 impl PartialOrd for Element {
     fn partial_cmp(&self, other: &Self) -> Option<::std::cmp::Ordering> {
-        self.datum.partial_cmp(&other.datum)
+        Some(self.cmp(other))
     }
 }
 impl Ord for Element {
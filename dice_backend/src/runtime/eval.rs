@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+
+use super::super::ordering::{ConstantValue, Distribution, Op, OrderedExpression, Spec};
+use super::super::parser_output::Rational;
+use super::super::stdlib::{count_arg, sum};
+use super::math::{rational_overflow, roll};
+use super::{BoolVec, Datum, Dice3, Dice6, Element, IntVec};
+
+/// evaluate a single expression, given the values of its sources.
+///
+/// Every possible result is returned alongside its probability,
+/// deterministic expressions return exactly one `Element`.
+pub fn evaluate(expr: &OrderedExpression, args: &[&Datum]) -> Result<Vec<Element>, String> {
+    match expr {
+        &OrderedExpression::Constant(ConstantValue::Bool(ref b, _)) => {
+            Ok(vec![Element::certain(*b)])
+        }
+        &OrderedExpression::Constant(ConstantValue::Int(ref i, _)) => {
+            Ok(vec![Element::certain(*i)])
+        }
+        &OrderedExpression::Constant(ConstantValue::Deck(ref cards, _)) => {
+            let mut deck = IntVec::new();
+            deck.extend_from_slice(cards);
+            deck.as_mut_slice().sort_unstable();
+            Ok(vec![Element::certain(Datum::Deck(deck))])
+        }
+        &OrderedExpression::Constant(ConstantValue::Parameter(ref name, _)) => {
+            Err(format!("parameter '{}' was not bound", name))
+        }
+        &OrderedExpression::Random(ref dist) => distribution(dist),
+        &OrderedExpression::StdLib(ref func) => func.get_function().evaluate(args),
+        &OrderedExpression::Spec(ref spec) => specialization(spec, args),
+        &OrderedExpression::Op(ref op) => operation(op, args).map(|d| vec![Element::certain(d)]),
+    }
+}
+
+fn distribution(dist: &Distribution) -> Result<Vec<Element>, String> {
+    match dist {
+        &Distribution::Bernoulli(ref prob, _) => {
+            let not_prob = match prob.complement() {
+                Option::Some(not_prob) => not_prob,
+                Option::None => {
+                    return Err(format!("bernoulli probability {} is greater than 1", prob));
+                }
+            };
+            Ok(vec![Element::new(true, *prob), Element::new(false, not_prob)]
+                .into_iter()
+                .filter(|e| !e.get_prob().is_zero())
+                .collect())
+        }
+        &Distribution::Categorical(ref weights, _) => {
+            // weights need not sum to one, normalize them
+            let total = weights
+                .iter()
+                .try_fold(Rational::zero(), |total, (_, weight)| total.checked_add(weight))
+                .ok_or_else(rational_overflow)?;
+            let mut merged = BTreeMap::<i8, Rational>::new();
+            for (value, weight) in weights.iter() {
+                let prob = match weight.checked_div(&total) {
+                    Option::Some(prob) => prob,
+                    Option::None => {
+                        return Err("categorical distribution has no weight".to_string())
+                    }
+                };
+                let entry = merged.entry(*value).or_insert_with(Rational::zero);
+                *entry = entry.checked_add(&prob).ok_or_else(rational_overflow)?;
+            }
+            Ok(merged
+                .into_iter()
+                .filter(|(_, prob)| !prob.is_zero())
+                .map(|(value, prob)| Element::new(value, prob))
+                .collect())
+        }
+    }
+}
+
+fn specialization(spec: &Spec, args: &[&Datum]) -> Result<Vec<Element>, String> {
+    let faces: Vec<i8> = match spec {
+        &Spec::SumMultiD6(_) => Dice6::new().into_iter().collect(),
+        &Spec::SumMultiD3(_) => Dice3::new().into_iter().collect(),
+    };
+    let mut sums = BTreeMap::<i8, Rational>::new();
+    let count = count_arg(args[0], "cannot roll a negative number of dice")?;
+    for (vec, prob) in roll(&faces, count)? {
+        let entry = sums.entry(sum(vec.as_slice())?).or_insert_with(Rational::zero);
+        *entry = entry.checked_add(&prob).ok_or_else(rational_overflow)?;
+    }
+    Ok(sums
+        .into_iter()
+        .map(|(value, prob)| Element::new(value, prob))
+        .collect())
+}
+
+/// applies an integer lambda to scalars, or to every item of a collection
+fn int_op<F>(lambda: F, left: &Datum, right: &Datum) -> Result<Datum, String>
+where
+    F: Fn(i8, i8) -> Result<i8, String>,
+{
+    match (left, right) {
+        (&Datum::Int(a), &Datum::Int(b)) => Ok(Datum::from(lambda(a, b)?)),
+        (&Datum::Int(a), &Datum::CollectionOfInt(ref b)) => Ok(Datum::from(
+            b.iter()
+                .map(|b| lambda(a, *b))
+                .collect::<Result<IntVec, String>>()?,
+        )),
+        (&Datum::CollectionOfInt(ref a), &Datum::Int(b)) => Ok(Datum::from(
+            a.iter()
+                .map(|a| lambda(*a, b))
+                .collect::<Result<IntVec, String>>()?,
+        )),
+        (a, b) => Err(format!("cannot apply integer operation to {:?} {:?}", a, b)),
+    }
+}
+
+/// applies a comparison to scalars, or to every item of a collection
+fn cmp_op<T, F>(lambda: F, left: &Datum, right: &Datum) -> Result<Datum, String>
+where
+    F: Fn(T, T) -> bool,
+    T: Copy + DatumScalar,
+{
+    match (T::scalar(left), T::scalar(right), T::slice(left), T::slice(right)) {
+        (Option::Some(a), Option::Some(b), _, _) => Ok(Datum::from(lambda(a, b))),
+        (Option::Some(a), _, _, Option::Some(b)) => Ok(Datum::from(
+            b.iter().map(|b| lambda(a, *b)).collect::<BoolVec>(),
+        )),
+        (_, Option::Some(b), Option::Some(a), _) => Ok(Datum::from(
+            a.iter().map(|a| lambda(*a, b)).collect::<BoolVec>(),
+        )),
+        _ => Err(format!("cannot compare {:?} {:?}", left, right)),
+    }
+}
+
+/// abstracts over bool & int scalars, so comparisons can be shared
+trait DatumScalar: Sized {
+    fn scalar(datum: &Datum) -> Option<Self>;
+    fn slice<'a>(datum: &'a Datum) -> Option<&'a [Self]>;
+}
+impl DatumScalar for i8 {
+    fn scalar(datum: &Datum) -> Option<i8> {
+        match datum {
+            &Datum::Int(x) => Some(x),
+            _ => None,
+        }
+    }
+    fn slice<'a>(datum: &'a Datum) -> Option<&'a [i8]> {
+        match datum {
+            &Datum::CollectionOfInt(ref x) => Some(x.as_slice()),
+            _ => None,
+        }
+    }
+}
+impl DatumScalar for bool {
+    fn scalar(datum: &Datum) -> Option<bool> {
+        match datum {
+            &Datum::Bool(x) => Some(x),
+            _ => None,
+        }
+    }
+    fn slice<'a>(datum: &'a Datum) -> Option<&'a [bool]> {
+        match datum {
+            &Datum::CollectionOfBool(ref x) => Some(x.as_slice()),
+            _ => None,
+        }
+    }
+}
+
+fn overflow(a: i8, b: i8, op: &str) -> String {
+    format!("integer overflow evaluating ({} {} {})", a, op, b)
+}
+
+fn operation(op: &Op, args: &[&Datum]) -> Result<Datum, String> {
+    let (left, right) = (args[0], args[1]);
+    match op {
+        &Op::Add(_) => int_op(
+            |a, b| a.checked_add(b).ok_or_else(|| overflow(a, b, "+")),
+            left,
+            right,
+        ),
+        &Op::Sub(_) => int_op(
+            |a, b| a.checked_sub(b).ok_or_else(|| overflow(a, b, "-")),
+            left,
+            right,
+        ),
+        &Op::Mul(_) => int_op(
+            |a, b| a.checked_mul(b).ok_or_else(|| overflow(a, b, "*")),
+            left,
+            right,
+        ),
+        &Op::Div(_) => int_op(
+            |a, b| {
+                if b == 0 {
+                    Err(format!("division by zero evaluating ({} / {})", a, b))
+                } else {
+                    a.checked_div(b).ok_or_else(|| overflow(a, b, "/"))
+                }
+            },
+            left,
+            right,
+        ),
+        &Op::GreaterThan(_) => cmp_op(|a: i8, b: i8| a > b, left, right),
+        &Op::GreaterThanEqual(_) => cmp_op(|a: i8, b: i8| a >= b, left, right),
+        &Op::LessThan(_) => cmp_op(|a: i8, b: i8| a < b, left, right),
+        &Op::LessThanEqual(_) => cmp_op(|a: i8, b: i8| a <= b, left, right),
+        &Op::Equal(_) => cmp_op(|a: i8, b: i8| a == b, left, right)
+            .or_else(|_| cmp_op(|a: bool, b: bool| a == b, left, right)),
+        &Op::NotEqual(_) => cmp_op(|a: i8, b: i8| a != b, left, right)
+            .or_else(|_| cmp_op(|a: bool, b: bool| a != b, left, right)),
+        &Op::And(_) => cmp_op(|a: bool, b: bool| a & b, left, right),
+        &Op::Or(_) => cmp_op(|a: bool, b: bool| a | b, left, right),
+    }
+}
@@ -1,121 +1,199 @@
-use num_rational::Ratio;
-pub type Rational = Ratio<usize>;
+use super::super::parser_output::Rational;
+use super::IntVec;
 
-pub fn dice_roll(expected_output: usize, num_dice: usize, sides_on_dice: usize) -> Ratio<i128> {
-    let expected_output: i128 = expected_output as i128;
-    let num_dice: i128 = num_dice as i128;
-    let sides_on_dice: i128 = sides_on_dice as i128;
+/// the error reported when an exact probability can't be represented
+pub fn rational_overflow() -> String {
+    "probabilities are too precise to be computed exactly".to_string()
+}
 
-    let mut k_max = expected_output
-        .checked_sub(num_dice)
-        .unwrap_or_else(|| 0i128)
-        / sides_on_dice;
-    if k_max == 0 {
-        k_max = 1;
+/// the number of ways to choose `k` items from `n`, or `None`
+/// upon overflow.
+pub fn combinations(n: u128, k: u128) -> Option<u128> {
+    if k > n {
+        return Some(0);
     }
+    let k = if k > n - k { n - k } else { k };
+    // each intermediate value is itself a binomial coefficient
+    // so the division is always exact
+    (0..k).try_fold(1u128, |acc, i| Some(acc.checked_mul(n - i)? / (i + 1)))
+}
 
-    let lambda = |k: i128| -> i128 {
-        let sign = if (k & 0x01i128) == 0 { 1i128 } else { -1i128 };
-        let comb1 = combinations(num_dice, k);
-        let comb2_top = expected_output
-            .checked_sub(sides_on_dice * k)
-            .unwrap_or_else(|| 0i128)
-            .checked_sub(1)
-            .unwrap_or_else(|| 0i128);
-        let comb2_bot = num_dice.checked_sub(1).unwrap_or_else(|| 0i128);
-        let comb2 = combinations(comb2_top, comb2_bot);
-        sign * comb1 * comb2
-    };
-    let output = (0i128..=k_max).map(lambda).sum::<i128>();
-    Ratio::new(output, sides_on_dice.pow(num_dice as u32) as i128)
+/// raise a rational to an integer power, or `None` upon overflow
+pub fn pow(base: Rational, exp: usize) -> Option<Rational> {
+    (0..exp).try_fold(Rational::one(), |acc, _| acc.checked_mul(&base))
 }
 
-#[test]
-fn test_dice_roll() {
-    struct TestSet {
-        expected: usize,
-        num: usize,
-        sides: usize,
-        output: Ratio<i128>,
-    }
-    impl TestSet {
-        fn exec_test(&self) {
-            let given = dice_roll(self.expected, self.num, self.sides);
-            if given != self.output {
-                panic!(
-                    "expected prob:'{:?}' found prob:'{:?}' for a sum of {} with N={} d{}",
-                    self.output, given, self.expected, self.num, self.sides
-                );
+/// enumerates every way to split `total` into `slots` ordered counts.
+///
+/// `limits` optionally caps the count of each slot.
+fn compositions(slots: usize, total: usize, limits: Option<&[usize]>) -> Vec<Vec<usize>> {
+    fn recurse(
+        slot: usize,
+        remaining: usize,
+        limits: Option<&[usize]>,
+        current: &mut Vec<usize>,
+        output: &mut Vec<Vec<usize>>,
+    ) {
+        if slot + 1 == current.len() {
+            if limits.map(|l| remaining <= l[slot]).unwrap_or(true) {
+                current[slot] = remaining;
+                output.push(current.clone());
             }
+            return;
+        }
+        let max = limits
+            .map(|l| if l[slot] < remaining { l[slot] } else { remaining })
+            .unwrap_or(remaining);
+        for count in 0..=max {
+            current[slot] = count;
+            recurse(slot + 1, remaining - count, limits, current, output);
         }
     }
 
-    let tests: &[TestSet] = &[
-        /*
-         * All results for 1 d6
-         *
-         */
-        TestSet {
-            expected: 1,
-            num: 1,
-            sides: 6,
-            output: Ratio::new(1, 6),
-        },
-        TestSet {
-            expected: 2,
-            num: 1,
-            sides: 6,
-            output: Ratio::new(1, 6),
-        },
-        TestSet {
-            expected: 3,
-            num: 1,
-            sides: 6,
-            output: Ratio::new(1, 6),
-        },
-        TestSet {
-            expected: 4,
-            num: 1,
-            sides: 6,
-            output: Ratio::new(1, 6),
-        },
-        TestSet {
-            expected: 5,
-            num: 1,
-            sides: 6,
-            output: Ratio::new(1, 6),
-        },
-        TestSet {
-            expected: 6,
-            num: 1,
-            sides: 6,
-            output: Ratio::new(1, 6),
-        },
-    ];
+    let mut output = Vec::new();
+    if slots == 0 {
+        if total == 0 {
+            output.push(Vec::new());
+        }
+        return output;
+    }
+    let mut current = vec![0usize; slots];
+    recurse(0, total, limits, &mut current, &mut output);
+    output
+}
+
+/// builds the sorted collection described by a composition
+fn expand(faces: &[i8], counts: &[usize]) -> IntVec {
+    let mut vec = IntVec::new();
+    for (face, count) in faces.iter().zip(counts) {
+        for _ in 0..*count {
+            vec.push(*face);
+        }
+    }
+    vec.as_mut_slice().sort_unstable();
+    vec
+}
 
-    for test in tests {
-        test.exec_test()
+/// every outcome of drawing `n` times, with replacement, from
+/// weighted faces.
+///
+/// outcomes are multisets, so this is `O(compositions)` not `O(faces^n)`.
+/// The weights of `faces` must sum to 1.
+pub fn with_replacement(
+    faces: &[(i8, Rational)],
+    n: usize,
+) -> Result<Vec<(IntVec, Rational)>, String> {
+    let values: Vec<i8> = faces.iter().map(|(value, _)| *value).collect();
+    let mut output = Vec::new();
+    for counts in compositions(faces.len(), n, None) {
+        let mut remaining = n as u128;
+        let mut prob = Rational::one();
+        for ((_, weight), count) in faces.iter().zip(counts.iter()) {
+            let count = *count as u128;
+            let ways = combinations(remaining, count).ok_or_else(rational_overflow)?;
+            prob = prob
+                .checked_mul(&Rational::new(ways, 1))
+                .and_then(|prob| prob.checked_mul(&pow(*weight, count as usize)?))
+                .ok_or_else(rational_overflow)?;
+            remaining -= count;
+        }
+        if !prob.is_zero() {
+            output.push((expand(&values, &counts), prob));
+        }
     }
+    Ok(output)
+}
 
-    drop(tests);
+/// rolls `n` dice which have the faces listed
+pub fn roll(faces: &[i8], n: usize) -> Result<Vec<(IntVec, Rational)>, String> {
+    let weight = Rational::new(1, faces.len() as u128);
+    let weighted: Vec<(i8, Rational)> = faces.iter().map(|face| (*face, weight)).collect();
+    with_replacement(&weighted, n)
 }
 
-#[inline(always)]
-fn combinations(n: i128, k: i128) -> i128 {
-    if k > n {
-        0
-    } else if k == n {
-        1
-    } else {
-        factorial(n) / (factorial(k) * factorial(n - k))
+/// every outcome of drawing `n` cards, without replacement, from a deck.
+///
+/// returns the cards drawn, the cards which remain, and the
+/// probability of the draw. The hypergeometric weight of drawing
+/// `c_i` of each face which occurs `k_i` times is
+/// `product(C(k_i, c_i)) / C(N, n)`.
+pub fn without_replacement(
+    cards: &[i8],
+    n: usize,
+) -> Result<Vec<(IntVec, IntVec, Rational)>, String> {
+    let mut faces: Vec<i8> = Vec::new();
+    let mut limits: Vec<usize> = Vec::new();
+    for card in cards {
+        match faces.iter().position(|face| face == card) {
+            Option::Some(index) => limits[index] += 1,
+            Option::None => {
+                faces.push(*card);
+                limits.push(1);
+            }
+        };
+    }
+    let total = combinations(cards.len() as u128, n as u128).ok_or_else(rational_overflow)?;
+    if total == 0 {
+        return Ok(Vec::new());
+    }
+    let mut output = Vec::new();
+    for counts in compositions(faces.len(), n, Some(&limits)) {
+        let ways = limits
+            .iter()
+            .zip(counts.iter())
+            .try_fold(1u128, |acc, (limit, count)| {
+                acc.checked_mul(combinations(*limit as u128, *count as u128)?)
+            })
+            .ok_or_else(rational_overflow)?;
+        let remaining: Vec<usize> = limits
+            .iter()
+            .zip(counts.iter())
+            .map(|(limit, count)| limit - count)
+            .collect();
+        output.push((
+            expand(&faces, &counts),
+            expand(&faces, &remaining),
+            Rational::new(ways, total),
+        ));
     }
+    Ok(output)
 }
 
-#[inline(always)]
-fn factorial(x: i128) -> i128 {
-    if x <= 1 {
-        1i128
-    } else {
-        (1..=x).fold(1i128, |a, b| a * b)
+#[test]
+fn test_combinatorics() {
+    assert_eq!(combinations(5, 2), Some(10));
+    assert_eq!(combinations(52, 5), Some(2598960));
+    assert_eq!(combinations(2, 5), Some(0));
+    assert_eq!(combinations(1000, 500), None);
+
+    // 2d6 has 21 distinct multisets
+    let two_dice = roll(&[1, 2, 3, 4, 5, 6], 2).unwrap();
+    assert_eq!(two_dice.len(), 21);
+    let total = two_dice
+        .iter()
+        .try_fold(Rational::zero(), |acc, (_, prob)| acc.checked_add(prob))
+        .unwrap();
+    assert!(total.is_one());
+    for (vec, prob) in two_dice {
+        if vec[0] == vec[1] {
+            assert_eq!(prob, Rational::new(1, 36));
+        } else {
+            assert_eq!(prob, Rational::new(2, 36));
+        }
+    }
+
+    // drawing 2 from [1, 1, 2]
+    let draws = without_replacement(&[1, 1, 2], 2).unwrap();
+    assert_eq!(draws.len(), 2);
+    for (drawn, remaining, prob) in draws {
+        if drawn.as_slice() == [1, 1] {
+            assert_eq!(remaining.as_slice(), &[2]);
+            assert_eq!(prob, Rational::new(1, 3));
+        } else {
+            assert_eq!(drawn.as_slice(), &[1, 2]);
+            assert_eq!(remaining.as_slice(), &[1]);
+            assert_eq!(prob, Rational::new(2, 3));
+        }
     }
+    assert!(without_replacement(&[1, 2], 3).unwrap().is_empty());
 }
@@ -1,37 +1,66 @@
-/*
-
 mod consts;
 pub use self::consts::{Dice3, Dice3Iter, Dice6, Dice6Iter};
 mod datum;
 pub use self::datum::{BoolVec, Datum, IntVec};
 mod element;
 pub use self::element::Element;
-mod lambda;
-pub use self::lambda::{
-    coalesce, const_bool, const_int, count, d3, d6, filter, from_op, join, len, max, min, sum,
-    Chain, Coalesce, CoalesceChain, CoalesceCombinator, Combinator, Init, Iter, LambdaKind,
-};
 mod report;
 pub use self::report::Report;
+mod math;
+pub use self::math::{rational_overflow, roll, without_replacement};
+mod eval;
+pub use self::eval::evaluate;
 mod coll;
 pub use self::coll::build_report;
-//mod math;
+mod sim;
+pub use self::sim::simulate;
+mod sweep;
+pub use self::sweep::{Sweep, SweepResults};
+
+use std::path::Path;
+
+use super::diagnostic::{render_all, Diagnostic, ErrorFormat};
+use super::namespace::Bindings;
+use super::program::{check, compile, CompileOptions};
 
 /// create report directly converts source code into a report.
+///
+/// errors are rendered against the source.
 pub fn create_report(source: &str) -> Result<Report, String> {
-    use super::cfgbuilder::ExpressionCollection;
-    use super::inliner::InlinedCollection;
-    use super::namespace::Namespace;
-    use super::parser_output::AbstractSyntaxTree;
+    create_report_in(source, Path::new(""), &Bindings::default())
+        .map_err(|d| render_all(&d, ErrorFormat::Human, "<input>", source))
+}
 
-    let ast = AbstractSyntaxTree::parse(source)?;
-    let namespace = Namespace::new(&ast)?;
-    let cfgcoll = ExpressionCollection::new(&namespace);
-    let inlinecoll = InlinedCollection::new(&cfgcoll);
-    Ok(build_report(&inlinecoll))
+/// converts source code into a report, files the program loads
+/// are resolved relative to `source_dir`, and parameters are
+/// resolved with `bindings`.
+pub fn create_report_in(
+    source: &str,
+    source_dir: &Path,
+    bindings: &Bindings,
+) -> Result<Report, Vec<Diagnostic>> {
+    compile(source, &options(source_dir, bindings))?.evaluate(bindings)
+}
+
+/// parses & resolves the program without evaluating it.
+///
+/// returns the program's warnings, or its errors.
+pub fn check_in(
+    source: &str,
+    source_dir: &Path,
+    bindings: &Bindings,
+) -> Result<Vec<Diagnostic>, Vec<Diagnostic>> {
+    check(source, &options(source_dir, bindings))
+}
+
+fn options(source_dir: &Path, bindings: &Bindings) -> CompileOptions {
+    CompileOptions {
+        source_dir: source_dir.to_path_buf(),
+        bindings: bindings.clone(),
+        ..CompileOptions::default()
+    }
 }
 
-/*
 #[test]
 fn test_removing_min_dice_roll() {
     let dut = r#"
@@ -44,7 +73,7 @@ analyze (sum(dice) - min(dice));
         (Datum::from(3), 3.0 / 216.0),
         (Datum::from(4), 7.0 / 216.0),
         (Datum::from(5), 12.0 / 216.0),
-        (Datum::from(6), 18.0 / 216.0),
+        (Datum::from(6), 19.0 / 216.0),
         (Datum::from(7), 27.0 / 216.0),
         (Datum::from(8), 34.0 / 216.0),
         (Datum::from(9), 36.0 / 216.0),
@@ -52,6 +81,10 @@ analyze (sum(dice) - min(dice));
         (Datum::from(11), 27.0 / 216.0),
         (Datum::from(12), 16.0 / 216.0),
     ]);
+    match output {
+        Ok(()) => {}
+        Err(e) => panic!("{:?}", e),
+    };
 }
 
 #[test]
@@ -69,34 +102,21 @@ analyze (sum(dice) - max(dice));
         (Datum::from(5), 36.0 / 216.0),
         (Datum::from(6), 34.0 / 216.0),
         (Datum::from(7), 27.0 / 216.0),
-        (Datum::from(8), 16.0 / 216.0),
+        (Datum::from(8), 19.0 / 216.0),
         (Datum::from(9), 12.0 / 216.0),
         (Datum::from(10), 7.0 / 216.0),
         (Datum::from(11), 3.0 / 216.0),
         (Datum::from(12), 1.0 / 216.0),
     ]);
+    match output {
+        Ok(()) => {}
+        Err(e) => panic!("{:?}", e),
+    };
 }
 
 #[test]
 fn test_1d6() {
-    use super::cfgbuilder::{ExpressionCollection, HashedExpression};
-    use super::inliner::{InlinedCollection, InlinedExpression};
-    use super::namespace::Namespace;
-    use super::parser_output::{AbstractSyntaxTree, Literal, Operation, TypeData};
-
-    let trivial_program = r#" analyze sum(roll_d6(1)); "#;
-
-    let ast = match AbstractSyntaxTree::parse(trivial_program) {
-        Ok(ast) => ast,
-        Err(e) => panic!("ast error: {:?}", e),
-    };
-    let namespace = match Namespace::new(&ast) {
-        Ok(namespace) => namespace,
-        Err(e) => panic!("namespace error: {:?}", e),
-    };
-    let cfgcoll = ExpressionCollection::new(&namespace);
-    let inlinecoll = InlinedCollection::new(&cfgcoll);
-    let report = build_report(&inlinecoll);
+    let report = create_report(r#" analyze sum(roll_d6(1)); "#).unwrap();
 
     // check if our report is correct
     let output = report.equal(&[
@@ -115,26 +135,7 @@ fn test_1d6() {
 
 #[test]
 fn test_2d6() {
-    use super::cfgbuilder::{ExpressionCollection, HashedExpression};
-    use super::inliner::InlinedCollection;
-    use super::namespace::Namespace;
-    use super::parser_output::{AbstractSyntaxTree, Literal, Operation, TypeData};
-
-    let trivial_program = r#" analyze sum(roll_d6(2)); "#;
-
-    let ast = match AbstractSyntaxTree::parse(trivial_program) {
-        Ok(ast) => ast,
-        Err(e) => panic!("ast error: {:?}", e),
-    };
-    let namespace = match Namespace::new(&ast) {
-        Ok(namespace) => namespace,
-        Err(e) => panic!("namespace error: {:?}", e),
-    };
-    let cfgcoll = ExpressionCollection::new(&namespace);
-    let inlinecoll = InlinedCollection::new(&cfgcoll);
-    let report = build_report(&inlinecoll);
-
-    // check if our report is correct
+    let report = create_report(r#" analyze sum(roll_d6(2)); "#).unwrap();
     let output = report.equal(&[
         (Datum::from(2), 1.0 / 36.0),
         (Datum::from(3), 2.0 / 36.0),
@@ -156,23 +157,12 @@ fn test_2d6() {
 
 #[test]
 fn test_2d6_join() {
-    use super::cfgbuilder::{ExpressionCollection, HashedExpression};
-    use super::inliner::{InlinedCollection, InlinedExpression};
-    use super::namespace::Namespace;
-    use super::parser_output::{AbstractSyntaxTree, Literal, Operation, TypeData};
+    use super::inliner::InlinedExpression;
+    use super::stdlib::Lowering;
 
     let trivial_program = r#" analyze sum(join(roll_d6(1),roll_d6(1))); "#;
-
-    let ast = match AbstractSyntaxTree::parse(trivial_program) {
-        Ok(ast) => ast,
-        Err(e) => panic!("ast error: {:?}", e),
-    };
-    let namespace = match Namespace::new(&ast) {
-        Ok(namespace) => namespace,
-        Err(e) => panic!("namespace error: {:?}", e),
-    };
-    let cfgcoll = ExpressionCollection::new(&namespace);
-    let inlinecoll = InlinedCollection::new(&cfgcoll);
+    let program = compile(trivial_program, &CompileOptions::default()).unwrap();
+    let inlinecoll = program.inline(program.get_bindings()).unwrap();
 
     /*
      * Validate the inline collection
@@ -186,22 +176,33 @@ fn test_2d6_join() {
             .next()
             .unwrap()
         {
-            InlinedExpression::Sum(arg) => arg,
+            InlinedExpression::StdLib(ref func, ref args, _)
+                if func.lowering() == Some(Lowering::Sum) =>
+            {
+                args[0]
+            }
             anything_else => panic!("{:?}", anything_else),
         };
         match inlinecoll.get_expr(&sum_expr).unwrap() {
-            InlinedExpression::Join(a, b) => {
-                assert_eq!(a, b);
-                match inlinecoll.get_expr(&a).unwrap() {
-                    InlinedExpression::D6(_) => {}
-                    anything_else => panic!("{:?}", anything_else),
-                };
+            InlinedExpression::StdLib(ref func, ref args, _)
+                if func.lowering() == Some(Lowering::Join) =>
+            {
+                // each roll is a die of its own
+                assert_ne!(args[0], args[1]);
+                for arg in args.iter() {
+                    match inlinecoll.get_expr(arg).unwrap() {
+                        InlinedExpression::StdLib(ref func, _, _)
+                            if func.lowering() == Some(Lowering::RollDice(6)) =>
+                        {}
+                        anything_else => panic!("{:?}", anything_else),
+                    };
+                }
             }
             anything_else => panic!("{:?}", anything_else),
         };
     }
 
-    let report = build_report(&inlinecoll);
+    let report = program.evaluate(program.get_bindings()).unwrap();
 
     // check if our report is correct
     let output = report.equal(&[
@@ -222,6 +223,256 @@ fn test_2d6_join() {
         Err(e) => panic!("{:?}", e),
     };
 }
-*/
 
-*/
+#[test]
+fn test_bernoulli_and_categorical() {
+    use super::parser_output::Rational;
+
+    // a 1/3 chance to proc, which then needs a 1 in 3 bonus
+    let dut = r#"
+const proc: bool = bernoulli(1/3);
+const bonus: int = categorical([(0, 2), (3, 1)]);
+analyze (proc & (bonus == 3));
+"#;
+    let report = create_report(dut).unwrap();
+    assert_eq!(
+        report.get_probability(&Datum::from(true)),
+        Rational::new(1, 9)
+    );
+    assert_eq!(
+        report.get_probability(&Datum::from(false)),
+        Rational::new(8, 9)
+    );
+
+    // a random variable read twice is the same value both times
+    let dut = r#"
+const proc: bool = bernoulli(1/3);
+analyze (proc == proc);
+"#;
+    let report = create_report(dut).unwrap();
+    assert!(report.get_probability(&Datum::from(true)).is_one());
+}
+
+#[test]
+fn test_identical_random_expressions() {
+    use super::parser_output::Rational;
+
+    // two rolls written identically are still two rolls
+    let report = create_report("analyze (sum(roll_d6(1)) + sum(roll_d6(1)));").unwrap();
+    assert_eq!(report.len(), 11);
+    assert_eq!(report.get_probability(&Datum::from(7)), Rational::new(1, 6));
+
+    // as are two bindings with identical definitions
+    let dut = r#"
+const p: bool = bernoulli(1/2);
+const q: bool = bernoulli(1/2);
+analyze (p & q);
+"#;
+    let report = create_report(dut).unwrap();
+    assert_eq!(report.get_probability(&Datum::from(true)), Rational::new(1, 4));
+
+    let dut = r#"
+analyze (categorical([(0, 1), (1, 1)]) + categorical([(0, 1), (1, 1)]));
+"#;
+    let report = create_report(dut).unwrap();
+    assert_eq!(report.get_probability(&Datum::from(1)), Rational::new(1, 2));
+
+    // and each call of a random function
+    let dut = r#"
+fn d6() -> int {
+    return sum(roll_d6(1));
+}
+const total: int = (d6() + d6());
+analyze (total + (total - total));
+"#;
+    let report = create_report(dut).unwrap();
+    assert_eq!(report.len(), 11);
+    assert_eq!(report.get_probability(&Datum::from(2)), Rational::new(1, 36));
+}
+
+#[test]
+fn test_roll_between() {
+    use super::parser_output::Rational;
+
+    // two dice, each either 1 or 2
+    let report = create_report("analyze sum(roll(2, 1, 2));").unwrap();
+    assert_eq!(report.get_probability(&Datum::from(2)), Rational::new(1, 4));
+    assert_eq!(report.get_probability(&Datum::from(3)), Rational::new(1, 2));
+    assert_eq!(report.get_probability(&Datum::from(4)), Rational::new(1, 4));
+
+    // a minimum above the maximum is an error, not a panic
+    assert!(create_report("analyze sum(roll(1, 2, 2));").is_err());
+}
+
+#[test]
+fn test_drawing_from_a_deck() {
+    use super::parser_output::Rational;
+
+    // the second draw depends upon the first
+    let dut = r#"
+const cards: deck = deck([1, 1, 2]);
+const a: vec<int> = draw(cards, 1);
+const b: vec<int> = draw(cards, 1);
+analyze (sum(a) + sum(b));
+"#;
+    let report = create_report(dut).unwrap();
+    assert_eq!(report.len(), 2);
+    assert_eq!(report.get_probability(&Datum::from(2)), Rational::new(1, 3));
+    assert_eq!(report.get_probability(&Datum::from(3)), Rational::new(2, 3));
+
+    // two draws written identically are still two draws
+    let dut = r#"
+const c: deck = deck([1, 2, 3]);
+analyze (sum(draw(c, 1)) + sum(draw(c, 1)));
+"#;
+    let report = create_report(dut).unwrap();
+    assert_eq!(report.len(), 3);
+    assert_eq!(report.get_probability(&Datum::from(3)), Rational::new(1, 3));
+    assert_eq!(report.get_probability(&Datum::from(4)), Rational::new(1, 3));
+    assert_eq!(report.get_probability(&Datum::from(5)), Rational::new(1, 3));
+
+    // a single hand of 2
+    let dut = r#"
+const cards: deck = deck([1, 1, 2, 2]);
+analyze sum(draw(cards, 2));
+"#;
+    let report = create_report(dut).unwrap();
+    assert_eq!(report.get_probability(&Datum::from(2)), Rational::new(1, 6));
+    assert_eq!(report.get_probability(&Datum::from(3)), Rational::new(2, 3));
+    assert_eq!(report.get_probability(&Datum::from(4)), Rational::new(1, 6));
+
+    // drawing too many cards is an error, not a panic
+    let dut = r#"
+const cards: deck = deck([1, 2]);
+analyze sum(draw(cards, 3));
+"#;
+    assert!(create_report(dut).is_err());
+}
+
+#[test]
+fn test_load_distribution() {
+    use super::parser_output::Rational;
+    use std::fs;
+
+    let dir = ::std::env::temp_dir().join(format!("dice_backend_csv_{}", ::std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("hits.csv"), "value,probability\n0,0.5\n1,0.3\n2,0.2\n").unwrap();
+    fs::write(dir.join("bad.csv"), "0,0.5\n1,0.3\n").unwrap();
+
+    let dut = r#"
+const hits: int = load_distribution("hits.csv");
+analyze (hits + hits);
+"#;
+    let report = create_report_in(dut, &dir, &Bindings::default()).unwrap();
+    assert_eq!(report.get_probability(&Datum::from(0)), Rational::new(1, 2));
+    assert_eq!(report.get_probability(&Datum::from(2)), Rational::new(3, 10));
+    assert_eq!(report.get_probability(&Datum::from(4)), Rational::new(1, 5));
+
+    let dut = r#"analyze load_distribution("bad.csv");"#;
+    match create_report_in(dut, &dir, &Bindings::default()) {
+        Ok(_) => panic!("probabilities which sum to 4/5 should be rejected"),
+        Err(e) => assert!(e[0].get_message().contains("bad.csv"), "{}", e[0]),
+    };
+    assert!(create_report(r#"analyze load_distribution("missing.csv");"#).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_parameter_bindings() {
+    let dut = r#"
+const attacks: int = %d{{ATTACKS}};
+const reroll: bool = %b{{REROLL}};
+analyze (reroll & (sum(roll_d6(attacks)) == 2));
+"#;
+    let mut bindings = Bindings::default();
+    bindings.read_environment(false);
+    bindings.define("ATTACKS", "1");
+    bindings.define("REROLL", "true");
+    let report = create_report_in(dut, Path::new(""), &bindings).unwrap();
+    assert_eq!(report.get_probability(&Datum::from(true)).to_f64(), 1.0 / 6.0);
+
+    // badly typed & missing parameters are errors, not panics
+    bindings.define("REROLL", "6");
+    assert!(create_report_in(dut, Path::new(""), &bindings).is_err());
+    let mut bindings = Bindings::default();
+    bindings.read_environment(false);
+    bindings.define("REROLL", "false");
+    assert!(create_report_in(dut, Path::new(""), &bindings).is_err());
+}
+
+#[test]
+fn test_type_error_diagnostic() {
+    let dut = "const dice: vec<int> = roll_d6(3);\nconst total: bool = sum(dice);\nanalyze total;\n";
+    let rendered = match create_report(dut) {
+        Ok(_) => panic!("binding an int to a bool should be rejected"),
+        Err(e) => e,
+    };
+    assert!(rendered.contains(" --> <input>:2:21\n"), "{}", rendered);
+    assert!(rendered.contains("2 | const total: bool = sum(dice);\n"), "{}", rendered);
+    assert!(rendered.contains("                     ^^^^^^^^^ this is int\n"), "{}", rendered);
+    assert!(rendered.contains(" declared as bool here\n"), "{}", rendered);
+}
+
+#[test]
+fn test_diagnostic_codes() {
+    use super::diagnostic::Code;
+
+    let code = |source: &str| match create_report_in(source, Path::new(""), &Bindings::default()) {
+        Ok(_) => panic!("'{}' should be rejected", source),
+        Err(e) => e[0].get_code(),
+    };
+    assert_eq!(code("analyze (1 + ;"), Code::SyntaxError);
+    assert_eq!(code("analyze missing;"), Code::UnknownIdentifier);
+    assert_eq!(code("analyze missing(1);"), Code::UnknownFunction);
+    assert_eq!(code("const x: int = 1; const x: int = 2; analyze x;"), Code::DuplicateDeclaration);
+    assert_eq!(code("const x: bool = 1; analyze x;"), Code::TypeMismatch);
+    assert_eq!(code("analyze roll_d6(1, 2);"), Code::ArgumentCount);
+    assert_eq!(code("analyze roll_d6(true);"), Code::ArgumentType);
+    assert_eq!(code("analyze (1 + true);"), Code::InvalidOperation);
+    assert_eq!(code("const x: int = 1;"), Code::MissingAnalyze);
+
+    // probabilities too precise to represent are an error, not a panic
+    let primes = [
+        101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191,
+        193, 197,
+    ];
+    let chain = primes.iter().skip(1).fold(
+        format!("bernoulli(1/{})", primes[0]),
+        |chain, prime| format!("({} | bernoulli(1/{}))", chain, prime),
+    );
+    assert_eq!(code(&format!("analyze {};", chain)), Code::Evaluation);
+}
+
+#[test]
+fn test_variable_shadows_constant() {
+    let dut = r#"
+const X: int = 10;
+fn f(y: int) -> int {
+    let X: int = (X + y);
+    return X;
+}
+analyze (f(1) + X);
+"#;
+    let report = create_report(dut).unwrap();
+    assert!(report.get_probability(&Datum::from(21)).is_one());
+}
+
+#[test]
+fn test_did_you_mean() {
+    let help = |source: &str| match create_report_in(source, Path::new(""), &Bindings::default()) {
+        Ok(_) => panic!("'{}' should be rejected", source),
+        Err(e) => e[0].get_help().to_vec(),
+    };
+    assert_eq!(help("analyze sum(rol_d6(3));"), vec!["did you mean `roll_d6`?"]);
+    assert_eq!(
+        help("const dice: vec<int> = roll_d6(3); analyze sum(dcie);"),
+        vec!["did you mean `dice`?"]
+    );
+    assert_eq!(
+        help("fn f(count_of: int) -> int { return roll_d6(count_off); } analyze f(1);")[0],
+        "did you mean `count_of`?"
+    );
+    assert!(help("const total: int = roll_d6(3); analyze total;")[0].contains("sum(...)"));
+    assert!(help("analyze xyzzy;").is_empty());
+}
@@ -1,12 +1,14 @@
-use std::collections::{BTreeMap, HashMap};
-use std::iter::{FromIterator, IntoIterator};
+use std::collections::HashMap;
+use std::iter::IntoIterator;
 
+use super::super::parser_output::Rational;
 use super::super::seahasher::DefaultSeaHasher;
+use super::math::rational_overflow;
 use super::{Datum, Element};
 
 #[derive(Clone)]
 pub struct Report {
-    data: HashMap<Datum, f64, DefaultSeaHasher>,
+    data: HashMap<Datum, Rational, DefaultSeaHasher>,
 }
 impl Report {
     /// equal is used for testing, so comparisons between the input & output can be easily done
@@ -20,9 +22,9 @@ impl Report {
                     ))
                 }
                 Option::Some(rational) => {
-                    if !approximately_equal(rational, &tuple.1) {
+                    if !approximately_equal(&rational.to_f64(), &tuple.1) {
                         return Err(format!(
-                            "for datum:'{:?}' expected value:'{:?}' found:'{:?}'",
+                            "for datum:'{:?}' expected value:'{:?}' found:'{}'",
                             &tuple.0, &tuple.1, rational
                         ));
                     }
@@ -39,11 +41,50 @@ impl Report {
         Ok(())
     }
 
-    /// converts the report
-    fn into_raw_report(&self) -> Vec<(Datum, f64)> {
-        let make_floats =
-            |(datum, rational): (&Datum, &f64)| -> (Datum, f64) { (datum.clone(), *rational) };
-        let mut vec: Vec<(Datum, f64)> = self.data.iter().map(make_floats).collect();
+    /// returns the exact probability of observing a value
+    pub fn get_probability(&self, datum: &Datum) -> Rational {
+        self.data
+            .get(datum)
+            .copied()
+            .unwrap_or_else(Rational::zero)
+    }
+
+    /// the number of distinct outcomes
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// the expected value, for reports of an `int` or `bool`.
+    ///
+    /// `true` counts as 1, and `false` as 0.
+    pub fn mean(&self) -> Option<f64> {
+        self.data.iter().try_fold(0.0f64, |acc, (datum, prob)| {
+            scalar(datum).map(|x| acc + (x * prob.to_f64()))
+        })
+    }
+
+    /// the standard deviation, for reports of an `int` or `bool`.
+    pub fn std_dev(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        self.data
+            .iter()
+            .try_fold(0.0f64, |acc, (datum, prob)| {
+                scalar(datum).map(|x| acc + ((x - mean) * (x - mean) * prob.to_f64()))
+            })
+            .map(|variance| variance.sqrt())
+    }
+
+    /// converts the report into a sorted list of outcomes
+    pub fn into_raw_report(&self) -> Vec<(Datum, Rational)> {
+        let mut vec: Vec<(Datum, Rational)> = self
+            .data
+            .iter()
+            .map(|(datum, prob)| (datum.clone(), *prob))
+            .collect();
         vec.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
         vec
     }
@@ -51,14 +92,15 @@ impl Report {
     pub fn serialize_report<I: Into<Option<usize>>>(&self, decimal: I) -> String {
         use std::fmt::Write;
 
-        let decimal = decimal.into().into_iter().next().unwrap_or_else(|| 12usize);
+        let decimal = decimal.into().into_iter().next().unwrap_or(12usize);
         let mut s = String::with_capacity(4096);
         for (datum, prob) in self.into_raw_report() {
-            write!(
+            writeln!(
                 &mut s,
-                " {datum}: {prob:.decimal$}\n",
+                " {datum}: {prob:.decimal$} ({exact})",
                 datum = datum,
-                prob = prob,
+                prob = prob.to_f64(),
+                exact = prob,
                 decimal = decimal
             )
             .unwrap();
@@ -67,8 +109,10 @@ impl Report {
     }
 }
 
-impl FromIterator<Element> for Report {
-    fn from_iter<T: IntoIterator<Item = Element>>(iter: T) -> Self {
+impl Report {
+    /// builds a report from every outcome, summing the probability
+    /// of outcomes which are equal. Fails if a sum overflows.
+    pub fn from_elements<T: IntoIterator<Item = Element>>(iter: T) -> Result<Report, String> {
         let iter = iter.into_iter();
         let capacity = match iter.size_hint() {
             (0, Option::None) => 0,
@@ -81,7 +125,7 @@ impl FromIterator<Element> for Report {
                 }
             }
         };
-        let mut map = HashMap::<Datum, f64, DefaultSeaHasher>::with_capacity_and_hasher(
+        let mut map = HashMap::<Datum, Rational, DefaultSeaHasher>::with_capacity_and_hasher(
             capacity,
             DefaultSeaHasher::default(),
         );
@@ -93,7 +137,7 @@ impl FromIterator<Element> for Report {
             datum.sort();
             match map.get_mut(&datum) {
                 Option::Some(p) => {
-                    *p += prob;
+                    *p = p.checked_add(&prob).ok_or_else(rational_overflow)?;
                     continue;
                 }
                 _ => {}
@@ -101,7 +145,15 @@ impl FromIterator<Element> for Report {
             map.insert(datum, prob);
         }
 
-        Report { data: map }
+        Ok(Report { data: map })
+    }
+}
+
+fn scalar(datum: &Datum) -> Option<f64> {
+    match datum {
+        &Datum::Int(x) => Some(x as f64),
+        &Datum::Bool(x) => Some(if x { 1.0 } else { 0.0 }),
+        _ => None,
     }
 }
 
@@ -112,10 +164,6 @@ fn approximately_equal(a: &f64, b: &f64) -> bool {
         let good_enough = 0.00000000001f64;
         let a_over = *a + good_enough;
         let a_under = *a - good_enough;
-        if (a_over > *b) & (a_under < *b) {
-            true
-        } else {
-            false
-        }
+        (a_over > *b) & (a_under < *b)
     }
 }
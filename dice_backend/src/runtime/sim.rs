@@ -0,0 +1,136 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::super::ordering::{OrdTrait, OrderedCollection, OrderedExpression};
+use super::super::parser_output::Rational;
+use super::super::rand::rngs::StdRng;
+use super::super::rand::{Rng, SeedableRng};
+use super::super::seahasher::DefaultSeaHasher;
+
+use super::coll::{last_use, topological_order};
+use super::eval::evaluate;
+use super::{Datum, Element, Report};
+
+/// the outcomes of an expression, given the values of its sources
+type Outcomes = HashMap<(u64, Vec<Datum>), Vec<Element>, DefaultSeaHasher>;
+
+/// simulates the program `trials` times, rather than computing
+/// every outcome exactly. The report holds how often each value
+/// was observed.
+///
+/// the same seed always produces the same report.
+pub fn simulate(
+    coll: &OrderedCollection,
+    trials: usize,
+    seed: Option<u64>,
+) -> Result<Report, String> {
+    if trials == 0 {
+        return Err("cannot simulate zero trials".to_string());
+    }
+    let mut rng = match seed {
+        Option::Some(seed) => StdRng::seed_from_u64(seed),
+        Option::None => StdRng::from_entropy(),
+    };
+    let order = topological_order(coll)?;
+    let last_use = last_use(coll, &order);
+    let mut outcomes = Outcomes::default();
+    let mut counts = BTreeMap::<Datum, u128>::new();
+    for _ in 0..trials {
+        let datum = trial(coll, &order, &last_use, &mut outcomes, &mut rng)?;
+        *counts.entry(datum).or_insert(0) += 1;
+    }
+    Report::from_elements(
+        counts
+            .into_iter()
+            .map(|(datum, count)| Element::new(datum, Rational::new(count, trials as u128))),
+    )
+}
+
+/// evaluates the program once, choosing a single outcome of every
+/// random expression
+fn trial(
+    coll: &OrderedCollection,
+    order: &[u64],
+    last_use: &BTreeMap<u64, Vec<u64>>,
+    outcomes: &mut Outcomes,
+    rng: &mut StdRng,
+) -> Result<Datum, String> {
+    let mut frame = BTreeMap::<u64, Datum>::new();
+    for id in order.iter() {
+        let expr = coll.get_expr(*id).unwrap();
+        let args: Vec<Datum> = expr
+            .get_sources()
+            .iter()
+            .map(|(source, _)| frame[source].clone())
+            .collect();
+        let key = (*id, args);
+        if !outcomes.contains_key(&key) {
+            let refs: Vec<&Datum> = key.1.iter().collect();
+            let elements = evaluate(expr, &refs)?;
+            outcomes.insert(key.clone(), elements);
+        }
+        let datum = choose(&outcomes[&key], rng);
+        let dead: &[u64] = last_use.get(id).map(|v| v.as_slice()).unwrap_or(&[]);
+        for source in dead {
+            frame.remove(source);
+        }
+        // drawing consumes cards, as when evaluating exactly
+        match expr {
+            &OrderedExpression::StdLib(ref func) if func.get_function().consumes() => {
+                let consumed = expr.get_sources()[0].0;
+                if !dead.contains(&consumed) {
+                    let refs: Vec<&Datum> = key.1.iter().collect();
+                    let remaining = func.get_function().remaining(&refs, &datum)?;
+                    frame.insert(consumed, remaining);
+                }
+            }
+            _ => {}
+        };
+        frame.insert(*id, datum);
+    }
+    Ok(frame.remove(&coll.get_return()).unwrap())
+}
+
+/// picks one element, each is as likely as its probability
+fn choose(elements: &[Element], rng: &mut StdRng) -> Datum {
+    let mut roll: f64 = rng.gen();
+    for element in elements.iter() {
+        let prob = element.get_prob().to_f64();
+        if roll < prob {
+            return element.get_datum().clone();
+        }
+        roll -= prob;
+    }
+    // rounding may leave a sliver after the last element
+    elements[elements.len() - 1].get_datum().clone()
+}
+
+#[test]
+fn test_simulate() {
+    use super::super::program::{compile, CompileOptions};
+
+    let program = compile(
+        "analyze (sum(roll_d6(2)) >= 7);",
+        &CompileOptions::default(),
+    )
+    .unwrap();
+    let report = simulate(program.get_graph(), 6000, Some(4)).unwrap();
+    let prob = report.get_probability(&Datum::from(true)).to_f64();
+    assert!(prob > 0.5 && prob < 0.67, "{}", prob);
+    let again = simulate(program.get_graph(), 6000, Some(4)).unwrap();
+    assert_eq!(
+        report.get_probability(&Datum::from(true)),
+        again.get_probability(&Datum::from(true))
+    );
+
+    // draws are simulated without replacement
+    let dut = r#"
+const cards: deck = deck([1, 2]);
+const a: vec<int> = draw(cards, 1);
+const b: vec<int> = draw(cards, 1);
+analyze (sum(a) + sum(b));
+"#;
+    let program = compile(dut, &CompileOptions::default()).unwrap();
+    let report = simulate(program.get_graph(), 100, None).unwrap();
+    assert!(report.get_probability(&Datum::from(3)).is_one());
+    assert!(simulate(program.get_graph(), 0, None).is_err());
+}
@@ -0,0 +1,213 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use super::super::diagnostic::Diagnostic;
+use super::super::namespace::Bindings;
+use super::super::parser_output::Rational;
+use super::super::program::Program;
+use super::{Datum, Report};
+
+/// Sweep describes a range of values to bind a parameter to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sweep {
+    name: String,
+    values: Vec<i8>,
+}
+impl Sweep {
+    /// parses `NAME=A..B` (exclusive) or `NAME=A..=B` (inclusive)
+    pub fn parse(arg: &str) -> Result<Sweep, String> {
+        let err = || {
+            format!(
+                "sweep '{}' is not of the form NAME=START..END or NAME=START..=END",
+                arg
+            )
+        };
+        let mut iter = arg.splitn(2, '=');
+        let name = iter.next().map(|x| x.trim()).unwrap_or("");
+        let range = iter.next().ok_or_else(err)?.trim();
+        if name.is_empty() {
+            return Err(err());
+        }
+        let mut bounds = range.splitn(2, "..");
+        let start = bounds.next().unwrap_or("").trim();
+        let end = bounds.next().ok_or_else(err)?;
+        let (end, inclusive) = match end.strip_prefix('=') {
+            Option::Some(end) => (end, true),
+            Option::None => (end, false),
+        };
+        let start = start.parse::<i8>().map_err(|_| err())?;
+        let end = end.trim().parse::<i8>().map_err(|_| err())?;
+        let values: Vec<i8> = if inclusive {
+            (start..=end).collect()
+        } else {
+            (start..end).collect()
+        };
+        if values.is_empty() {
+            return Err(format!("sweep '{}' contains no values", arg));
+        }
+        Ok(Sweep {
+            name: name.to_string(),
+            values: values,
+        })
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_values(&self) -> &[i8] {
+        &self.values
+    }
+
+    /// evaluates the program for every value of the parameter.
+    ///
+    /// the program is compiled once, each value is substituted
+    /// into its graph, which is then optimized & evaluated.
+    pub fn run(
+        &self,
+        program: &Program,
+        bindings: &Bindings,
+    ) -> Result<SweepResults, Vec<Diagnostic>> {
+        let mut reports = Vec::with_capacity(self.values.len());
+        for value in self.values.iter() {
+            let mut bindings = bindings.clone();
+            bindings.define(self.name.as_str(), value.to_string());
+            let report = match program.evaluate(&bindings) {
+                Ok(report) => report,
+                Err(e) => {
+                    return Err(e
+                        .into_iter()
+                        .map(|e| e.with_note(format!("with {}={}", self.name, value)))
+                        .collect())
+                }
+            };
+            reports.push((*value, report));
+        }
+        Ok(SweepResults {
+            name: self.name.clone(),
+            reports: reports,
+        })
+    }
+}
+
+/// the report for each value of a swept parameter
+pub struct SweepResults {
+    name: String,
+    reports: Vec<(i8, Report)>,
+}
+impl SweepResults {
+    pub fn get_reports(&self) -> &[(i8, Report)] {
+        &self.reports
+    }
+
+    /// a table, with one row per parameter value & one column
+    /// per outcome.
+    pub fn serialize_table<I: Into<Option<usize>>>(&self, decimal: I) -> String {
+        let decimal = decimal.into().unwrap_or(4);
+        let outcomes: BTreeSet<Datum> = self
+            .reports
+            .iter()
+            .flat_map(|(_, report)| report.into_raw_report().into_iter().map(|(datum, _)| datum))
+            .collect();
+        let width = outcomes
+            .iter()
+            .map(|datum| datum.to_string().len())
+            .chain(Some(decimal + 2))
+            .max()
+            .unwrap_or(0);
+        let name_width = self
+            .reports
+            .iter()
+            .map(|(value, _)| value.to_string().len())
+            .chain(Some(self.name.len()))
+            .max()
+            .unwrap_or(0);
+
+        let mut s = String::with_capacity(4096);
+        write!(&mut s, "{:>w$}", self.name, w = name_width).unwrap();
+        for datum in outcomes.iter() {
+            write!(&mut s, "  {:>w$}", datum.to_string(), w = width).unwrap();
+        }
+        s.push('\n');
+        for (value, report) in self.reports.iter() {
+            write!(&mut s, "{:>w$}", value, w = name_width).unwrap();
+            for datum in outcomes.iter() {
+                let prob: Rational = report.get_probability(datum);
+                write!(
+                    &mut s,
+                    "  {:>w$.d$}",
+                    prob.to_f64(),
+                    w = width,
+                    d = decimal
+                )
+                .unwrap();
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    /// the mean & standard deviation for each parameter value
+    pub fn serialize_summary<I: Into<Option<usize>>>(&self, decimal: I) -> String {
+        let decimal = decimal.into().unwrap_or(4);
+        let mut s = String::with_capacity(4096);
+        for (value, report) in self.reports.iter() {
+            write!(&mut s, "{}={}:", self.name, value).unwrap();
+            match (report.mean(), report.std_dev()) {
+                (Option::Some(mean), Option::Some(std_dev)) => {
+                    write!(
+                        &mut s,
+                        " mean {:.d$} stddev {:.d$}",
+                        mean,
+                        std_dev,
+                        d = decimal
+                    )
+                    .unwrap();
+                }
+                _ => {}
+            };
+            writeln!(&mut s, " outcomes {}", report.len()).unwrap();
+        }
+        s
+    }
+}
+
+#[test]
+fn test_sweep_parse() {
+    let sweep = Sweep::parse("T=3..=8").unwrap();
+    assert_eq!(sweep.get_name(), "T");
+    assert_eq!(sweep.get_values(), &[3, 4, 5, 6, 7, 8]);
+    assert_eq!(Sweep::parse("T = 1..3").unwrap().get_values(), &[1, 2]);
+    assert!(Sweep::parse("T=3..3").is_err());
+    assert!(Sweep::parse("T=3").is_err());
+    assert!(Sweep::parse("=1..=2").is_err());
+}
+
+#[test]
+fn test_sweep_run() {
+    use super::super::program::{compile, CompileOptions};
+
+    let dut = "analyze (sum(roll_d6(1)) >= %d{{T}});";
+    let mut options = CompileOptions::default();
+    options.bindings.read_environment(false);
+    options.bindings.define("T", "1");
+    let program = compile(dut, &options).unwrap();
+    // the parameter is an input of the graph, bound for each value
+    let ordered = program.order(&options.bindings).unwrap().to_string();
+    assert!(ordered.contains("= param T"), "{}", ordered);
+    let results = Sweep::parse("T=1..=3")
+        .unwrap()
+        .run(&program, &options.bindings)
+        .unwrap();
+    let reports = results.get_reports();
+    assert_eq!(reports.len(), 3);
+    for (value, report) in reports {
+        let expected = Rational::new(7 - *value as u128, 6);
+        assert_eq!(report.get_probability(&Datum::from(true)), expected);
+    }
+    let table = results.serialize_table(None);
+    assert_eq!(table.lines().count(), 4);
+    assert!(table.lines().next().unwrap().contains("true"));
+    let summary = results.serialize_summary(None);
+    assert!(summary.starts_with("T=1: mean 1.0000"), "{}", summary);
+}
@@ -2,7 +2,7 @@ use std::hash::BuildHasher;
 use std::sync::{Arc, Mutex};
 
 use super::rand::rngs::{OsRng, StdRng};
-use super::rand::{Rng, RngCore, SeedableRng};
+use super::rand::{RngCore, SeedableRng};
 
 use super::seahash::SeaHasher;
 
@@ -0,0 +1,191 @@
+use super::super::parser_output::TypeData;
+use super::super::runtime::{Datum, Element, IntVec};
+
+use super::{bool_vec, int_vec, sum, Lowering, StdFunction};
+
+fn certain(datum: Datum) -> Result<Vec<Element>, String> {
+    Ok(vec![Element::certain(datum)])
+}
+
+pub struct Filter;
+impl StdFunction for Filter {
+    fn name(&self) -> &'static str {
+        "filter"
+    }
+    fn args(&self) -> &'static [(&'static str, TypeData)] {
+        &[
+            (
+                "collection_of_tests_to_filter_with",
+                TypeData::CollectionOfBool,
+            ),
+            ("collection_of_data_to_filter", TypeData::CollectionOfInt),
+        ]
+    }
+    fn ret(&self) -> TypeData {
+        TypeData::CollectionOfInt
+    }
+    fn doc(&self) -> &'static [&'static str] {
+        &[
+            "keeps the values of the second collection where the first",
+            "collection is `true`. Both collections must be the same length.",
+        ]
+    }
+    fn evaluate(&self, args: &[&Datum]) -> Result<Vec<Element>, String> {
+        let mask = bool_vec(args[0])?;
+        let values = int_vec(args[1])?;
+        if mask.len() != values.len() {
+            return Err(format!(
+                "filter arguments have different lengths {} and {}",
+                mask.len(),
+                values.len()
+            ));
+        }
+        certain(Datum::from(
+            mask.iter()
+                .zip(values)
+                .filter(|(keep, _)| **keep)
+                .map(|(_, value)| *value)
+                .collect::<IntVec>(),
+        ))
+    }
+}
+
+pub struct Count;
+impl StdFunction for Count {
+    fn name(&self) -> &'static str {
+        "count"
+    }
+    fn args(&self) -> &'static [(&'static str, TypeData)] {
+        &[("collection_to_sum_over", TypeData::CollectionOfBool)]
+    }
+    fn ret(&self) -> TypeData {
+        TypeData::Int
+    }
+    fn doc(&self) -> &'static [&'static str] {
+        &["the number of `true` values within a collection."]
+    }
+    fn evaluate(&self, args: &[&Datum]) -> Result<Vec<Element>, String> {
+        certain(Datum::from(
+            bool_vec(args[0])?.iter().filter(|b| **b).count() as i8,
+        ))
+    }
+}
+
+pub struct Len;
+impl StdFunction for Len {
+    fn name(&self) -> &'static str {
+        "len"
+    }
+    fn args(&self) -> &'static [(&'static str, TypeData)] {
+        &[("length_of_collection", TypeData::CollectionOfInt)]
+    }
+    fn ret(&self) -> TypeData {
+        TypeData::Int
+    }
+    fn doc(&self) -> &'static [&'static str] {
+        &["the number of values within a collection."]
+    }
+    fn lowering(&self) -> Option<Lowering> {
+        Some(Lowering::Len)
+    }
+    fn evaluate(&self, args: &[&Datum]) -> Result<Vec<Element>, String> {
+        certain(Datum::from(args[0].len()))
+    }
+}
+
+pub struct Join;
+impl StdFunction for Join {
+    fn name(&self) -> &'static str {
+        "join"
+    }
+    fn args(&self) -> &'static [(&'static str, TypeData)] {
+        &[
+            ("join_collection_over_a", TypeData::CollectionOfInt),
+            ("join_collection_over_b", TypeData::CollectionOfInt),
+        ]
+    }
+    fn ret(&self) -> TypeData {
+        TypeData::CollectionOfInt
+    }
+    fn doc(&self) -> &'static [&'static str] {
+        &["both collections, one after the other."]
+    }
+    fn lowering(&self) -> Option<Lowering> {
+        Some(Lowering::Join)
+    }
+    fn evaluate(&self, args: &[&Datum]) -> Result<Vec<Element>, String> {
+        let mut vec = IntVec::new();
+        vec.extend_from_slice(int_vec(args[0])?);
+        vec.extend_from_slice(int_vec(args[1])?);
+        certain(Datum::from(vec))
+    }
+}
+
+pub struct Sum;
+impl StdFunction for Sum {
+    fn name(&self) -> &'static str {
+        "sum"
+    }
+    fn args(&self) -> &'static [(&'static str, TypeData)] {
+        &[("collection_to_sum_with", TypeData::CollectionOfInt)]
+    }
+    fn ret(&self) -> TypeData {
+        TypeData::Int
+    }
+    fn doc(&self) -> &'static [&'static str] {
+        &["the total of every value within a collection."]
+    }
+    fn lowering(&self) -> Option<Lowering> {
+        Some(Lowering::Sum)
+    }
+    fn evaluate(&self, args: &[&Datum]) -> Result<Vec<Element>, String> {
+        certain(Datum::from(sum(int_vec(args[0])?)?))
+    }
+}
+
+pub struct Max;
+impl StdFunction for Max {
+    fn name(&self) -> &'static str {
+        "max"
+    }
+    fn args(&self) -> &'static [(&'static str, TypeData)] {
+        &[("maximum_of_dice_collection", TypeData::CollectionOfInt)]
+    }
+    fn ret(&self) -> TypeData {
+        TypeData::Int
+    }
+    fn doc(&self) -> &'static [&'static str] {
+        &["the largest value within a collection."]
+    }
+    fn evaluate(&self, args: &[&Datum]) -> Result<Vec<Element>, String> {
+        match int_vec(args[0])?.iter().max() {
+            Option::Some(max) => certain(Datum::from(*max)),
+            Option::None => Err("max of an empty collection".to_string()),
+        }
+    }
+}
+
+pub struct Min;
+impl StdFunction for Min {
+    fn name(&self) -> &'static str {
+        "min"
+    }
+    fn args(&self) -> &'static [(&'static str, TypeData)] {
+        &[("minimum_of_dice_collection", TypeData::CollectionOfInt)]
+    }
+    fn ret(&self) -> TypeData {
+        TypeData::Int
+    }
+    fn doc(&self) -> &'static [&'static str] {
+        &["the smallest value within a collection."]
+    }
+    fn lowering(&self) -> Option<Lowering> {
+        Some(Lowering::Min)
+    }
+    fn evaluate(&self, args: &[&Datum]) -> Result<Vec<Element>, String> {
+        match int_vec(args[0])?.iter().min() {
+            Option::Some(min) => certain(Datum::from(*min)),
+            Option::None => Err("min of an empty collection".to_string()),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use super::super::parser_output::TypeData;
+use super::super::runtime::{without_replacement, Datum, Element, IntVec};
+
+use super::{count_arg, deck, int_vec, StdFunction};
+
+/// Draw takes cards from a deck without replacement. The deck is
+/// consumed, later draws from it only see the cards which remain.
+pub struct Draw;
+impl StdFunction for Draw {
+    fn name(&self) -> &'static str {
+        "draw"
+    }
+    fn args(&self) -> &'static [(&'static str, TypeData)] {
+        &[
+            ("deck_to_draw_from", TypeData::Deck),
+            ("number_of_cards_to_draw", TypeData::Int),
+        ]
+    }
+    fn ret(&self) -> TypeData {
+        TypeData::CollectionOfInt
+    }
+    fn doc(&self) -> &'static [&'static str] {
+        &[
+            "draws cards from a deck without replacement. Later draws from the",
+            "same deck only see the cards which remain.",
+        ]
+    }
+    fn is_random(&self) -> bool {
+        true
+    }
+    fn length_arg(&self) -> Option<usize> {
+        Some(1)
+    }
+    fn consumes(&self) -> bool {
+        true
+    }
+    fn remaining(&self, args: &[&Datum], result: &Datum) -> Result<Datum, String> {
+        // both the deck & the hand are sorted
+        let mut hand = int_vec(result)?.iter().peekable();
+        let mut remaining = IntVec::new();
+        for card in deck(args[0])? {
+            match hand.peek() {
+                Option::Some(drawn) if *drawn == card => {
+                    hand.next();
+                }
+                _ => remaining.push(*card),
+            };
+        }
+        Ok(Datum::Deck(remaining))
+    }
+    fn evaluate(&self, args: &[&Datum]) -> Result<Vec<Element>, String> {
+        let cards = deck(args[0])?;
+        let count = count_arg(args[1], "cannot draw a negative number of cards")?;
+        if count > cards.len() {
+            return Err(format!(
+                "cannot draw {} cards from a deck of {}",
+                count,
+                cards.len()
+            ));
+        }
+        Ok(without_replacement(cards, count)?
+            .into_iter()
+            .map(|(drawn, _, prob)| Element::new(drawn, prob))
+            .collect())
+    }
+}
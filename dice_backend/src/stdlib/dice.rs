@@ -0,0 +1,118 @@
+use super::super::parser_output::TypeData;
+use super::super::runtime::{roll, Datum, Dice3, Dice6, Element};
+
+use super::{count_arg, int_arg, Lowering, StdFunction};
+
+const NEGATIVE: &str = "cannot roll a negative number of dice";
+
+/// rolls dice with the faces listed
+fn roll_faces(faces: &[i8], count: &Datum) -> Result<Vec<Element>, String> {
+    Ok(roll(faces, count_arg(count, NEGATIVE)?)?
+        .into_iter()
+        .map(|(vec, prob)| Element::new(vec, prob))
+        .collect())
+}
+
+pub struct RollD6;
+impl StdFunction for RollD6 {
+    fn name(&self) -> &'static str {
+        "roll_d6"
+    }
+    fn args(&self) -> &'static [(&'static str, TypeData)] {
+        &[("number_of_d6_to_roll", TypeData::Int)]
+    }
+    fn ret(&self) -> TypeData {
+        TypeData::CollectionOfInt
+    }
+    fn doc(&self) -> &'static [&'static str] {
+        &["rolls a number of six sided dice, each die is a value from 1 to 6."]
+    }
+    fn lowering(&self) -> Option<Lowering> {
+        Some(Lowering::RollDice(6))
+    }
+    fn is_random(&self) -> bool {
+        true
+    }
+    fn length_arg(&self) -> Option<usize> {
+        Some(0)
+    }
+    fn evaluate(&self, args: &[&Datum]) -> Result<Vec<Element>, String> {
+        let faces: Vec<i8> = Dice6::new().into_iter().collect();
+        roll_faces(&faces, args[0])
+    }
+}
+
+pub struct RollD3;
+impl StdFunction for RollD3 {
+    fn name(&self) -> &'static str {
+        "roll_d3"
+    }
+    fn args(&self) -> &'static [(&'static str, TypeData)] {
+        &[("number_of_d3_to_roll", TypeData::Int)]
+    }
+    fn ret(&self) -> TypeData {
+        TypeData::CollectionOfInt
+    }
+    fn doc(&self) -> &'static [&'static str] {
+        &["rolls a number of three sided dice, each die is a value from 1 to 3."]
+    }
+    fn lowering(&self) -> Option<Lowering> {
+        Some(Lowering::RollDice(3))
+    }
+    fn is_random(&self) -> bool {
+        true
+    }
+    fn length_arg(&self) -> Option<usize> {
+        Some(0)
+    }
+    fn evaluate(&self, args: &[&Datum]) -> Result<Vec<Element>, String> {
+        let faces: Vec<i8> = Dice3::new().into_iter().collect();
+        roll_faces(&faces, args[0])
+    }
+}
+
+/// Roll rolls dice whose faces are every value between a minimum
+/// & a maximum. The faces must be known before the dice are rolled.
+pub struct Roll;
+impl StdFunction for Roll {
+    fn name(&self) -> &'static str {
+        "roll"
+    }
+    fn args(&self) -> &'static [(&'static str, TypeData)] {
+        &[
+            ("max_value_roll_can_return_inclusive", TypeData::Int),
+            ("min_value_roll_can_return_inclusive", TypeData::Int),
+            ("number_of_rolls_to_make", TypeData::Int),
+        ]
+    }
+    fn ret(&self) -> TypeData {
+        TypeData::CollectionOfInt
+    }
+    fn doc(&self) -> &'static [&'static str] {
+        &[
+            "rolls a number of dice, each die is a value between the minimum",
+            "& the maximum inclusive.",
+        ]
+    }
+    fn is_random(&self) -> bool {
+        true
+    }
+    fn constant_args(&self) -> &'static [usize] {
+        &[0, 1]
+    }
+    fn length_arg(&self) -> Option<usize> {
+        Some(2)
+    }
+    fn evaluate(&self, args: &[&Datum]) -> Result<Vec<Element>, String> {
+        let max = int_arg(args[0])?;
+        let min = int_arg(args[1])?;
+        if min > max {
+            return Err(format!(
+                "cannot roll a die whose minimum {} is greater than its maximum {}",
+                min, max
+            ));
+        }
+        let faces: Vec<i8> = (min..=max).collect();
+        roll_faces(&faces, args[2])
+    }
+}
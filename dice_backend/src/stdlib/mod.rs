@@ -0,0 +1,314 @@
+//! The standard library is a registry of functions. Each function
+//! describes its own signature, how it is lowered, and how it is
+//! evaluated, so adding a function only touches this module.
+//!
+//! Functions outside of this crate can be added by implementing
+//! `StdFunction` & registering it with `Registry::register`.
+mod collection;
+pub use self::collection::{Count, Filter, Join, Len, Max, Min, Sum};
+mod deck;
+pub use self::deck::Draw;
+mod dice;
+pub use self::dice::{Roll, RollD3, RollD6};
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use super::parser_output::TypeData;
+use super::runtime::{Datum, Element};
+
+/// Lowering identifies the functions the compiler knows how to
+/// rewrite. Functions without one are only ever evaluated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lowering {
+    /// rolls a number of dice, each with this many faces
+    RollDice(i8),
+    /// the number of items within a collection
+    Len,
+    /// appends one collection to another
+    Join,
+    /// the sum of a collection
+    Sum,
+    /// the least item of a collection
+    Min,
+}
+
+/// StdFunction is a function provided by the host, rather than
+/// declared within a program.
+pub trait StdFunction: Send + Sync {
+    /// the name a program invokes the function with
+    fn name(&self) -> &'static str;
+
+    /// the name & type of each argument
+    fn args(&self) -> &'static [(&'static str, TypeData)];
+
+    /// the type the function returns
+    fn ret(&self) -> TypeData;
+
+    /// describes the function, one line per item
+    fn doc(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// how the compiler may rewrite invocations of the function
+    fn lowering(&self) -> Option<Lowering> {
+        None
+    }
+
+    /// if the result is random, even when every argument is constant
+    fn is_random(&self) -> bool {
+        false
+    }
+
+    /// the indexes of arguments which must be constant
+    fn constant_args(&self) -> &'static [usize] {
+        &[]
+    }
+
+    /// the index of the argument which sets the length of the
+    /// collection returned, a random length is warned about
+    fn length_arg(&self) -> Option<usize> {
+        None
+    }
+
+    /// if the function consumes its first argument, such as drawing
+    /// cards from a deck. Each invocation is then a distinct event,
+    /// and later readers of the argument see what `remaining` leaves.
+    fn consumes(&self) -> bool {
+        false
+    }
+
+    /// the value of the consumed argument, after `result` was returned
+    fn remaining(&self, args: &[&Datum], _result: &Datum) -> Result<Datum, String> {
+        Ok(args[0].clone())
+    }
+
+    /// every possible result & its probability, given the value of
+    /// each argument. Deterministic functions return one `Element`.
+    fn evaluate(&self, args: &[&Datum]) -> Result<Vec<Element>, String>;
+}
+
+/// StdFunc is a shared handle to a standard library function.
+///
+/// Names are unique within a registry, so handles compare, order,
+/// and hash by name. This lets them be held by the IR.
+#[derive(Clone)]
+pub struct StdFunc(Arc<dyn StdFunction>);
+impl StdFunc {
+    pub fn new<F: StdFunction + 'static>(func: F) -> StdFunc {
+        StdFunc(Arc::new(func))
+    }
+}
+impl ::std::ops::Deref for StdFunc {
+    type Target = dyn StdFunction;
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+impl PartialEq for StdFunc {
+    fn eq(&self, other: &StdFunc) -> bool {
+        self.name() == other.name()
+    }
+}
+impl Eq for StdFunc {}
+impl PartialOrd for StdFunc {
+    fn partial_cmp(&self, other: &StdFunc) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for StdFunc {
+    fn cmp(&self, other: &StdFunc) -> Ordering {
+        self.name().cmp(other.name())
+    }
+}
+impl Hash for StdFunc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name().hash(state)
+    }
+}
+impl fmt::Debug for StdFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Registry holds every function of the standard library
+#[derive(Clone, Default)]
+pub struct Registry {
+    functions: BTreeMap<&'static str, StdFunc>,
+}
+impl Registry {
+    /// the functions every program may use
+    pub fn standard() -> Registry {
+        let mut registry = Registry::default();
+        let builtins = vec![
+            StdFunc::new(RollD6),
+            StdFunc::new(RollD3),
+            StdFunc::new(Roll),
+            StdFunc::new(Filter),
+            StdFunc::new(Count),
+            StdFunc::new(Len),
+            StdFunc::new(Join),
+            StdFunc::new(Sum),
+            StdFunc::new(Max),
+            StdFunc::new(Min),
+            StdFunc::new(Draw),
+        ];
+        for func in builtins {
+            registry.insert(func).unwrap();
+        }
+        registry
+    }
+
+    /// adds a function, a name may only be registered once
+    pub fn register<F: StdFunction + 'static>(&mut self, func: F) -> Result<(), String> {
+        self.insert(StdFunc::new(func))
+    }
+
+    fn insert(&mut self, func: StdFunc) -> Result<(), String> {
+        if self.functions.contains_key(func.name()) {
+            return Err(format!("function '{}' is already registered", func.name()));
+        }
+        self.functions.insert(func.name(), func);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&StdFunc> {
+        self.functions.get(name)
+    }
+
+    /// every function, ordered by name
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = &'a StdFunc> {
+        self.functions.values()
+    }
+}
+
+/*
+ * Argument conversions shared by the functions
+ *
+ */
+
+/// a count of dice or cards, `negative` describes the error when
+/// the count is below zero.
+pub fn count_arg(datum: &Datum, negative: &str) -> Result<usize, String> {
+    match datum {
+        &Datum::Int(x) if x >= 0 => Ok(x as usize),
+        &Datum::Int(x) => Err(format!("{}: {}", negative, x)),
+        x => Err(format!("expected an int found: {:?}", x)),
+    }
+}
+
+pub fn int_arg(datum: &Datum) -> Result<i8, String> {
+    match datum {
+        &Datum::Int(x) => Ok(x),
+        x => Err(format!("expected an int found: {:?}", x)),
+    }
+}
+
+pub fn int_vec<'a>(datum: &'a Datum) -> Result<&'a [i8], String> {
+    match datum {
+        &Datum::CollectionOfInt(ref vec) => Ok(vec.as_slice()),
+        x => Err(format!("expected a vec<int> found: {:?}", x)),
+    }
+}
+
+pub fn bool_vec<'a>(datum: &'a Datum) -> Result<&'a [bool], String> {
+    match datum {
+        &Datum::CollectionOfBool(ref vec) => Ok(vec.as_slice()),
+        x => Err(format!("expected a vec<bool> found: {:?}", x)),
+    }
+}
+
+pub fn deck<'a>(datum: &'a Datum) -> Result<&'a [i8], String> {
+    match datum {
+        &Datum::Deck(ref vec) => Ok(vec.as_slice()),
+        x => Err(format!("expected a deck found: {:?}", x)),
+    }
+}
+
+/// sums a collection, failing upon overflow
+pub fn sum(slice: &[i8]) -> Result<i8, String> {
+    slice
+        .iter()
+        .try_fold(0i8, |acc, x| acc.checked_add(*x))
+        .ok_or_else(|| format!("integer overflow summing {:?}", slice))
+}
+
+#[test]
+fn test_registry() {
+    use super::namespace::Bindings;
+    use super::parser_output::Rational;
+    use super::program::{compile, CompileOptions};
+
+    let mut registry = Registry::standard();
+    let names: Vec<&str> = registry.iter().map(|func| func.name()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "count", "draw", "filter", "join", "len", "max", "min", "roll", "roll_d3", "roll_d6",
+            "sum"
+        ]
+    );
+    assert!(registry.register(Sum).is_err());
+
+    // a function from outside the standard library
+    struct Double;
+    impl StdFunction for Double {
+        fn name(&self) -> &'static str {
+            "double"
+        }
+        fn args(&self) -> &'static [(&'static str, TypeData)] {
+            &[("value", TypeData::Int)]
+        }
+        fn ret(&self) -> TypeData {
+            TypeData::Int
+        }
+        fn evaluate(&self, args: &[&Datum]) -> Result<Vec<Element>, String> {
+            let x = int_arg(args[0])?;
+            Ok(vec![Element::certain(x * 2)])
+        }
+    }
+    assert!(registry.register(Double).is_ok());
+    let double = registry.get("double").unwrap();
+    assert_eq!(double.ret(), TypeData::Int);
+    let result = double.evaluate(&[&Datum::from(4)]).unwrap();
+    assert_eq!(result[0].get_datum(), &Datum::from(8));
+
+    // each call of a random function is an independent event
+    struct Coin;
+    impl StdFunction for Coin {
+        fn name(&self) -> &'static str {
+            "coin"
+        }
+        fn args(&self) -> &'static [(&'static str, TypeData)] {
+            &[]
+        }
+        fn ret(&self) -> TypeData {
+            TypeData::Int
+        }
+        fn is_random(&self) -> bool {
+            true
+        }
+        fn evaluate(&self, _: &[&Datum]) -> Result<Vec<Element>, String> {
+            Ok(vec![
+                Element::new(0, Rational::new(1, 2)),
+                Element::new(1, Rational::new(1, 2)),
+            ])
+        }
+    }
+    assert!(registry.register(Coin).is_ok());
+    let options = CompileOptions {
+        registry: registry,
+        ..CompileOptions::default()
+    };
+    let report = compile("analyze (coin() + coin());", &options)
+        .unwrap()
+        .evaluate(&Bindings::default())
+        .unwrap();
+    assert_eq!(report.get_probability(&Datum::from(0)), Rational::new(1, 4));
+    assert_eq!(report.get_probability(&Datum::from(1)), Rational::new(1, 2));
+    assert_eq!(report.get_probability(&Datum::from(2)), Rational::new(1, 4));
+}
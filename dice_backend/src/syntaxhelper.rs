@@ -1,91 +1,78 @@
+/// CharacterLookup converts byte offsets, which is what the
+/// parser reports, into lines & columns of the source.
 pub struct CharacterLookup<'a> {
     buffer: &'a str,
-    char_index: Box<[CharIndex]>,
     line_index: Box<[LineIndex]>,
 }
 impl<'a> CharacterLookup<'a> {
     pub fn new(arg: &'a str) -> CharacterLookup<'a> {
-        let mut char_index = Vec::new();
         let mut line_index = Vec::new();
         let mut line_byte_start = 0usize;
-        let mut line_char_start = 0usize;
-        let mut buffer_index = 0usize;
-        for (char_count, character) in arg.chars().enumerate() {
-            char_index.push(CharIndex {
-                character: character.clone(),
-                byte_index: buffer_index.clone(),
-                char_index: char_count.clone(),
-            });
+        for (byte_index, character) in arg.char_indices() {
             if character == '\n' {
-                // push our curent line
                 line_index.push(LineIndex {
-                    byte_start: line_byte_start.clone(),
-                    char_start: line_char_start.clone(),
-                    byte_end: buffer_index.clone(),
-                    char_end: char_count.clone(),
+                    byte_start: line_byte_start,
+                    byte_end: byte_index,
                 });
-                // reset our start marker
-                line_byte_start = buffer_index;
-                line_char_start = char_count;
+                line_byte_start = byte_index + 1;
             }
-            buffer_index += character.len_utf8();
         }
+        // the final line need not be terminated
+        line_index.push(LineIndex {
+            byte_start: line_byte_start,
+            byte_end: arg.len(),
+        });
         CharacterLookup {
             buffer: arg,
-            char_index: char_index.into_boxed_slice(),
             line_index: line_index.into_boxed_slice(),
         }
     }
 
-    pub fn get_char(&self, index: usize) -> char {
-        self.char_index[index].character.clone()
-    }
-
+    /// returns the zero-indexed line a byte offset falls upon
     pub fn get_line_number(&self, index: usize) -> usize {
         self.line_index
             .iter()
-            .enumerate()
-            .filter(|(_, i)| i.char_start <= index && i.char_end >= index)
-            .map(|(i, _)| i)
-            .next()
-            .unwrap_or(0)
+            .position(|line| line.byte_start <= index && index <= line.byte_end)
+            .unwrap_or(self.line_index.len() - 1)
     }
 
-    pub fn get_line(&self, index: usize) -> &'a str {
-        let line = self.get_line_number(index);
-        let start = self.line_index[line].byte_start.clone();
-        let end = self.line_index[line].byte_end.clone();
-        unsafe { ::std::str::from_utf8_unchecked(&self.buffer.as_bytes()[start..end]) }
-    }
-
-    /// get_span is used to return the text between character numbers start/end
-    pub fn get_span(&self, start: usize, end: usize) -> &'a str {
-        let start = self.char_index[start].byte_index.clone();
-        let end = self.char_index[end].byte_index.clone();
-        unsafe { ::std::str::from_utf8_unchecked(&self.buffer.as_bytes()[start..end]) }
+    /// returns the zero-indexed column, in characters, of a byte offset
+    pub fn get_column(&self, index: usize) -> usize {
+        let line = &self.line_index[self.get_line_number(index)];
+        let end = if index > line.byte_end {
+            line.byte_end
+        } else {
+            index
+        };
+        self.buffer
+            .get(line.byte_start..end)
+            .map(|prefix| prefix.chars().count())
+            .unwrap_or(0)
     }
 
-    /// get_span_lines is used to return the "lines" between "start" and "end"
-    /// this means it'll skip forward to beginning of the "start" line, and
-    /// the terminator of the "end" line.
-    pub fn get_span_lines(self, start: usize, end: usize) -> &'a str {
-        let line_start = self.get_line_number(start);
-        let start = self.line_index[line_start].byte_start.clone();
-        let line_end = self.get_line_number(end);
-        let end = self.line_index[line_end].byte_end.clone();
-        unsafe { ::std::str::from_utf8_unchecked(&self.buffer.as_bytes()[start..end]) }
+    /// returns the text of a zero-indexed line, without its terminator
+    pub fn get_line(&self, line: usize) -> &'a str {
+        match self.line_index.get(line) {
+            Option::Some(line) => self
+                .buffer
+                .get(line.byte_start..line.byte_end)
+                .unwrap_or("")
+                .trim_end_matches('\r'),
+            Option::None => "",
+        }
     }
 }
 
-struct CharIndex {
-    character: char,
-    byte_index: usize,
-    char_index: usize,
-}
-
 struct LineIndex {
     byte_start: usize,
-    char_start: usize,
     byte_end: usize,
-    char_end: usize,
+}
+
+#[test]
+fn test_character_lookup() {
+    let lookup = CharacterLookup::new("const x: int = 5;\nanalyze x;");
+    assert_eq!(lookup.get_line_number(0), 0);
+    assert_eq!(lookup.get_line_number(18), 1);
+    assert_eq!(lookup.get_column(26), 8);
+    assert_eq!(lookup.get_line(1), "analyze x;");
 }
@@ -0,0 +1,255 @@
+use std::fs::write;
+
+use clap::ArgMatches;
+use dice_backend::doc::{document, DocFormat};
+use dice_backend::formatter::{format_source, formatter};
+use dice_backend::{
+    check, compile, read_path, render_all, Artifact, Bindings, CompileOptions, Diagnostic,
+    ErrorFormat, Program, Registry, Stage, Sweep,
+};
+
+use super::input::{read_bindings, Input};
+use super::{EXIT_OK, EXIT_USER_ERROR};
+
+/// Options control how a program is run & how its errors are reported.
+pub struct Options {
+    pub input: Input,
+    pub format: ErrorFormat,
+    /// treat warnings as errors
+    pub deny_warnings: bool,
+    /// verify the graph after every optimization
+    pub verify_ir: bool,
+}
+impl Options {
+    pub fn from_args(args: &ArgMatches) -> Result<Options, String> {
+        Ok(Options {
+            input: Input::from_args(args),
+            format: ErrorFormat::parse(args.value_of("error-format").unwrap_or("human"))?,
+            deny_warnings: args.is_present("deny-warnings"),
+            verify_ir: args.is_present("verify-ir"),
+        })
+    }
+
+    fn render(&self, diagnostics: &[Diagnostic], source: &str) -> String {
+        render_all(diagnostics, self.format, self.input.get_name(), source)
+    }
+
+    /// warnings are printed to stderr. When warnings are denied
+    /// they are returned as an error instead.
+    fn warn(&self, warnings: &[Diagnostic], source: &str) -> Result<(), String> {
+        if warnings.is_empty() {
+            return Ok(());
+        }
+        let rendered = self.render(warnings, source);
+        if self.deny_warnings {
+            return Err(match self.format {
+                ErrorFormat::Human => format!(
+                    "{}\nerror: aborting due to {} warning(s), --deny-warnings is set\n",
+                    rendered,
+                    warnings.len()
+                ),
+                ErrorFormat::Json => rendered,
+            });
+        }
+        eprintln!("{}", rendered);
+        Ok(())
+    }
+
+    /// compiles the input's source, printing its warnings
+    fn compile(&self, source: &str, bindings: &Bindings) -> Result<Program, String> {
+        let mut options = self.input.compile_options(bindings);
+        options.verify_ir = self.verify_ir;
+        let program = match compile(source, &options) {
+            Ok(program) => program,
+            Err(e) => return Err(self.render(&e, source)),
+        };
+        self.warn(program.get_warnings(), source)?;
+        Ok(program)
+    }
+}
+
+/// evaluates the exact distribution of the program
+pub fn run(args: &ArgMatches) -> Result<String, String> {
+    if args.is_present("stage") {
+        return emit(args);
+    }
+    let options = Options::from_args(args)?;
+    let sweep = match args.value_of("sweep") {
+        Option::Some(sweep) => Some(Sweep::parse(sweep)?),
+        Option::None => None,
+    };
+    let bindings = read_bindings(args)?;
+    let source = options.input.read()?;
+    if Artifact::is_artifact(&source) {
+        if sweep.is_some() {
+            return Err("a sweep requires the program's source, not an artifact".to_string());
+        }
+        let artifact = Artifact::parse(&source, &Registry::standard())?;
+        return match artifact.evaluate(&bindings) {
+            Ok(report) => Ok(report.serialize_report(None)),
+            Err(e) => Err(options.render(&e, "")),
+        };
+    }
+    // a sweep is checked with its first value
+    let mut checked = bindings.clone();
+    if let Option::Some(ref sweep) = sweep {
+        checked.define(sweep.get_name(), sweep.get_values()[0].to_string());
+    }
+    let program = options.compile(&source, &checked)?;
+    match sweep {
+        Option::Some(sweep) => match sweep.run(&program, &bindings) {
+            Ok(ref results) if args.is_present("summary") => Ok(results.serialize_summary(None)),
+            Ok(results) => Ok(results.serialize_table(None)),
+            Err(e) => Err(options.render(&e, &source)),
+        },
+        Option::None => match program.evaluate(&bindings) {
+            Ok(report) => Ok(report.serialize_report(None)),
+            Err(e) => Err(options.render(&e, &source)),
+        },
+    }
+}
+
+/// parses, resolves, & type checks the program without building it
+pub fn check_program(args: &ArgMatches) -> Result<String, String> {
+    let options = Options::from_args(args)?;
+    let source = options.input.read()?;
+    let compile_options = options.input.compile_options(&read_bindings(args)?);
+    match check(&source, &compile_options) {
+        Ok(warnings) => options.warn(&warnings, &source).map(|()| String::new()),
+        Err(e) => Err(options.render(&e, &source)),
+    }
+}
+
+/// prints the program after one stage of the compiler
+pub fn emit(args: &ArgMatches) -> Result<String, String> {
+    let options = Options::from_args(args)?;
+    let bindings = read_bindings(args)?;
+    let source = options.input.read()?;
+    let program = options.compile(&source, &bindings)?;
+    let stage = Stage::parse(args.value_of("stage").unwrap_or("optimized"))?;
+    match program.emit(stage, &bindings) {
+        Ok(emitted) => Ok(emitted),
+        Err(e) => Err(options.render(&e, &source)),
+    }
+}
+
+/// compiles the program into an artifact, which `run` may load
+/// without compiling the source again
+pub fn build(args: &ArgMatches) -> Result<String, String> {
+    let options = Options::from_args(args)?;
+    let bindings = read_bindings(args)?;
+    let source = options.input.read()?;
+    let program = options.compile(&source, &bindings)?;
+    let artifact = Artifact::new(&program).serialize();
+    match args.value_of("output") {
+        Option::Some(path) => match write(path, artifact) {
+            Ok(()) => Ok(String::new()),
+            Err(e) => Err(format!(
+                "failed to write file={:?} with error={:?}",
+                path, e
+            )),
+        },
+        Option::None => Ok(artifact),
+    }
+}
+
+/// samples outcomes of the program, rather than computing them exactly
+pub fn sim(args: &ArgMatches) -> Result<String, String> {
+    let options = Options::from_args(args)?;
+    let trials = match args.value_of("trials").unwrap_or("10000").parse::<usize>() {
+        Ok(trials) => trials,
+        Err(_) => return Err("--trials must be a positive number".to_string()),
+    };
+    let seed = match args.value_of("seed").map(|seed| seed.parse::<u64>()) {
+        Option::Some(Ok(seed)) => Some(seed),
+        Option::Some(Err(_)) => return Err("--seed must be a positive number".to_string()),
+        Option::None => None,
+    };
+    let bindings = read_bindings(args)?;
+    let source = options.input.read()?;
+    let program = options.compile(&source, &bindings)?;
+    match program.simulate(&bindings, trials, seed) {
+        Ok(report) => Ok(report.serialize_report(None)),
+        Err(e) => Err(options.render(&e, &source)),
+    }
+}
+
+/// formats every input, returns the exit code. Standard input is
+/// written to standard output.
+pub fn fmt(args: &ArgMatches) -> i32 {
+    let check = args.is_present("check");
+    let paths: Vec<&str> = args
+        .values_of("input")
+        .into_iter()
+        .flatten()
+        .collect();
+    if paths.is_empty() || paths == ["-"] {
+        return match fmt_stdin(check) {
+            Ok(true) => EXIT_OK,
+            Ok(false) => EXIT_USER_ERROR,
+            Err(e) => {
+                eprintln!("{}", e);
+                EXIT_USER_ERROR
+            }
+        };
+    }
+    let mut rc = EXIT_OK;
+    for path in paths {
+        match formatter(path, check) {
+            Ok(true) => {}
+            Ok(false) => {
+                if check {
+                    println!("{} is not formatted", path);
+                    rc = EXIT_USER_ERROR;
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                rc = EXIT_USER_ERROR;
+            }
+        };
+    }
+    rc
+}
+
+/// formats standard input, returns if it was already formatted
+fn fmt_stdin(check: bool) -> Result<bool, String> {
+    let source = Input::Stdin.read()?;
+    let formatted = match format_source(&source) {
+        Ok(formatted) => formatted,
+        Err(e) => return Err(render_all(&e, ErrorFormat::Human, "<stdin>", &source)),
+    };
+    if check {
+        if formatted != source {
+            println!("<stdin> is not formatted");
+        }
+    } else {
+        print!("{}", formatted);
+    }
+    Ok(!check || formatted == source)
+}
+
+/// documents the input, if any, and the standard library
+pub fn doc(args: &ArgMatches) -> Result<String, String> {
+    let format = DocFormat::parse(args.value_of("format").unwrap_or("markdown"))?;
+    match args.value_of("input") {
+        Option::Some(path) => {
+            let source = read_path(path)?;
+            document(Some((path, &source)), format)
+                .map_err(|e| render_all(&e, ErrorFormat::Human, path, &source))
+        }
+        Option::None => {
+            document(None, format).map_err(|e| render_all(&e, ErrorFormat::Human, "<stdlib>", ""))
+        }
+    }
+}
+
+/// evaluates definitions & expressions as they are entered
+pub fn repl(args: &ArgMatches) -> Result<String, String> {
+    let options = CompileOptions {
+        bindings: read_bindings(args)?,
+        verify_ir: args.is_present("verify-ir"),
+        ..CompileOptions::default()
+    };
+    dice_backend::repl::run(options).map(|()| String::new())
+}
@@ -0,0 +1,67 @@
+use std::io::{stdin, Read};
+
+use clap::ArgMatches;
+use dice_backend::{read_path, Bindings, CompileOptions};
+
+/// Input is where the source of a program is read from
+pub enum Input {
+    /// a file, the files it loads are relative to it
+    File(String),
+    /// standard input, when no file is given or the file is `-`
+    Stdin,
+    /// an expression given with `-e`, which is analyzed
+    Expr(String),
+}
+impl Input {
+    pub fn from_args(args: &ArgMatches) -> Input {
+        match (args.value_of("expr"), args.value_of("input")) {
+            (Option::Some(expr), _) => Input::Expr(expr.to_string()),
+            (Option::None, Option::Some(path)) if path != "-" => Input::File(path.to_string()),
+            _ => Input::Stdin,
+        }
+    }
+
+    /// the name diagnostics refer to the source by
+    pub fn get_name(&self) -> &str {
+        match *self {
+            Input::File(ref path) => path,
+            Input::Stdin => "<stdin>",
+            Input::Expr(_) => "<expr>",
+        }
+    }
+
+    pub fn read(&self) -> Result<String, String> {
+        match *self {
+            Input::File(ref path) => read_path(path),
+            Input::Stdin => {
+                let mut source = String::with_capacity(4096);
+                match stdin().read_to_string(&mut source) {
+                    Ok(_) => Ok(source),
+                    Err(e) => Err(format!("failed to read stdin with error={:?}", e)),
+                }
+            }
+            Input::Expr(ref expr) => Ok(format!("analyze {};\n", expr)),
+        }
+    }
+
+    /// options to compile the input with, the files it loads are
+    /// relative to the program itself, or the working directory.
+    pub fn compile_options(&self, bindings: &Bindings) -> CompileOptions {
+        let mut options = match *self {
+            Input::File(ref path) => CompileOptions::for_path(path),
+            _ => CompileOptions::default(),
+        };
+        options.bindings = bindings.clone();
+        options
+    }
+}
+
+/// parameters bound upon the command line
+pub fn read_bindings(args: &ArgMatches) -> Result<Bindings, String> {
+    let mut bindings = Bindings::default();
+    bindings.read_environment(!args.is_present("no-env"));
+    for define in args.values_of("define").into_iter().flatten() {
+        bindings.define_from_str(define)?;
+    }
+    Ok(bindings)
+}
@@ -1,13 +1,30 @@
 extern crate clap;
-use clap::{Arg,App,SubCommand};
-
 extern crate dice_backend;
+extern crate jemallocator;
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::process::exit;
 
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+mod commands;
+mod input;
 mod validator;
-mod formatter;
 
-fn main() {
 
+#[global_allocator]
+static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
+
+/// the command succeeded
+pub const EXIT_OK: i32 = 0;
+
+/// the program, or the arguments, contain an error
+pub const EXIT_USER_ERROR: i32 = 1;
+
+/// foxhole itself failed, which is always a bug
+pub const EXIT_INTERNAL_ERROR: i32 = 2;
+
+fn main() {
     let matches = App::new("foxhole")
         .version("0.1.0")
         .version_short("0.1")
@@ -15,27 +32,243 @@ fn main() {
         .about("procedural dice roll simulator")
         .set_term_width(80)
         .max_term_width(80)
-        .subcommand(Subcommand::with_name("fmt")
-            .version("0.1.0")
-            .version_short("0.1")
-            .author("cody laeder <codylaeder@gmail.com>")
-            .set_term_width(80)
-            .max_term_width(80)
-            .about("formats the input argument")
-            .arg(Arg::with_name("input")
-                 .index(1)
-                 .takes_value(true)
-                 .required(true)
-                 .validator(validator::file_path)
-                 .help("input file to format")))
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("prints the exact distribution of a program, or a compiled artifact")
+                .args(&input_args())
+                .args(&binding_args())
+                .args(&report_args())
+                .arg(verify_arg())
+                .arg(
+                    Arg::with_name("sweep")
+                        .long("sweep")
+                        .takes_value(true)
+                        .value_name("NAME=START..=END")
+                        .help("evaluates the program once per value of a %d{{NAME}} parameter"),
+                )
+                .arg(
+                    Arg::with_name("summary")
+                        .long("summary")
+                        .requires("sweep")
+                        .help("prints the mean & standard deviation of each sweep value"),
+                )
+                .arg(
+                    stage_arg()
+                        .long("emit")
+                        .conflicts_with("sweep")
+                        .help("prints the program after a compiler stage, instead of running it"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("build")
+                .about("compiles a program into an artifact, which `run` loads without its source")
+                .args(&input_args())
+                .args(&binding_args())
+                .args(&report_args())
+                .arg(verify_arg())
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .help("where the artifact is written, rather than standard output"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about("parses, resolves, & type checks a program without running it")
+                .args(&input_args())
+                .args(&binding_args())
+                .args(&report_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .about("rewrites source files in their canonical formatting")
+                .arg(
+                    Arg::with_name("input")
+                        .index(1)
+                        .multiple(true)
+                        .help("paths to the files to format, or standard input when missing"),
+                )
+                .arg(
+                    Arg::with_name("check")
+                        .long("check")
+                        .help("lists unformatted files & exits non-zero, without rewriting them"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("emit")
+                .about("prints the program after a stage of the compiler")
+                .args(&input_args())
+                .args(&binding_args())
+                .args(&report_args())
+                .arg(verify_arg())
+                .arg(
+                    stage_arg()
+                        .long("stage")
+                        .alias("emit")
+                        .default_value("optimized"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("sim")
+                .about("estimates the distribution of a program by sampling it")
+                .args(&input_args())
+                .args(&binding_args())
+                .args(&report_args())
+                .arg(verify_arg())
+                .arg(
+                    Arg::with_name("trials")
+                        .long("trials")
+                        .takes_value(true)
+                        .default_value("10000")
+                        .help("the number of times the program is sampled"),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .takes_value(true)
+                        .help("seeds the random number generator, so results are reproducible"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("doc")
+                .about("prints reference documentation for a program & the standard library")
+                .arg(
+                    Arg::with_name("input")
+                        .index(1)
+                        .validator(validator::file_path)
+                        .help("path to the file to document, the standard library is always documented"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["markdown", "html"])
+                        .default_value("markdown")
+                        .help("how the documentation is written"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("repl")
+                .about("evaluates definitions & expressions interactively")
+                .args(&binding_args())
+                .arg(verify_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("lsp")
+                .about("runs a language server for dice programs over stdio"),
+        )
         .get_matches();
-    match matches.subcommand() {
-        ("fmt",Option::Some(arg)) => {
-            match formatter::formatter(arg.value_of("input").unwrap()) {
-                Ok(_) => { },
-                Err(e) => println!("{}", e)
-            };
-        },
-        (_,_) => { }
+
+    // a panic is a bug within foxhole, not within the program
+    let rc = match catch_unwind(AssertUnwindSafe(|| dispatch(&matches))) {
+        Ok(rc) => rc,
+        Err(_) => {
+            eprintln!("error: internal compiler error, this is a bug in foxhole");
+            EXIT_INTERNAL_ERROR
+        }
     };
+    exit(rc)
+}
+
+/// runs the subcommand, returns the exit code
+fn dispatch(matches: &ArgMatches) -> i32 {
+    match matches.subcommand() {
+        ("run", Option::Some(args)) => print(commands::run(args)),
+        ("build", Option::Some(args)) => print(commands::build(args)),
+        ("check", Option::Some(args)) => print(commands::check_program(args)),
+        ("fmt", Option::Some(args)) => commands::fmt(args),
+        ("emit", Option::Some(args)) => print(commands::emit(args)),
+        ("sim", Option::Some(args)) => print(commands::sim(args)),
+        ("doc", Option::Some(args)) => print(commands::doc(args)),
+        ("repl", Option::Some(args)) => print(commands::repl(args)),
+        ("lsp", Option::Some(_)) => print(dice_backend::lsp::serve().map(|()| String::new())),
+        _ => EXIT_USER_ERROR,
+    }
+}
+
+/// prints the output of a command, returns the exit code
+fn print(output: Result<String, String>) -> i32 {
+    match output {
+        Ok(ref output) if output.is_empty() || output.ends_with('\n') => {
+            print!("{}", output);
+            EXIT_OK
+        }
+        Ok(output) => {
+            println!("{}", output);
+            EXIT_OK
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            EXIT_USER_ERROR
+        }
+    }
+}
+
+/// where the program is read from
+fn input_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("input")
+            .index(1)
+            .validator(validator::file_path)
+            .help("path to the program, standard input is read when it is `-` or missing"),
+        Arg::with_name("expr")
+            .short("e")
+            .long("expr")
+            .takes_value(true)
+            .value_name("EXPR")
+            .conflicts_with("input")
+            .help("analyzes an expression, rather than reading a program"),
+    ]
+}
+
+/// a stage of the compiler, the program is printed after it
+fn stage_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("stage")
+        .takes_value(true)
+        .value_name("STAGE")
+        .possible_values(&["ast", "namespace", "cfg", "inlined", "ordered", "optimized", "dot"])
+        .help("the stage to print")
+}
+
+/// checks the compiler, rather than the program
+fn verify_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("verify-ir")
+        .long("verify-ir")
+        .help("verifies the expression graph after every optimization, as debug builds do")
+}
+
+/// how parameters are bound
+fn binding_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("define")
+            .long("define")
+            .short("D")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("NAME=VALUE")
+            .help("sets the value of a %d{{NAME}} or %b{{NAME}} parameter"),
+        Arg::with_name("no-env")
+            .long("no-env")
+            .help("never read parameters from the process environment"),
+    ]
+}
+
+/// how problems within the program are reported
+fn report_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("error-format")
+            .long("error-format")
+            .takes_value(true)
+            .possible_values(&["human", "json"])
+            .default_value("human")
+            .help("how errors in the program are printed"),
+        Arg::with_name("deny-warnings")
+            .long("deny-warnings")
+            .help("treats warnings, such as unused constants, as errors"),
+    ]
 }
@@ -1,9 +1,12 @@
+use std::fs::OpenOptions;
 
-use std::fs::{OpenOptions};
-
-pub fn file_path(arg: String) -> Result<(),String> {
+/// checks the input can be read, `-` is standard input
+pub fn file_path(arg: String) -> Result<(), String> {
+    if arg == "-" {
+        return Ok(());
+    }
     match OpenOptions::new().read(true).open(&arg) {
         Ok(_) => Ok(()),
-        Err(e) => Err(format!("failed to open file: {} with error {:?}", &arg, e))
+        Err(e) => Err(format!("failed to open file: {} with error {:?}", &arg, e)),
     }
 }